@@ -1,6 +1,8 @@
 use crate::{ErrorCode, MyError};
 use clap::Subcommand;
-use fastlink_core::utils::path::get_path_type;
+use fastlink_core::utils::expand::expand_env;
+use fastlink_core::utils::path::{inspect_path, PathStatus};
+use fastlink_core::utils::vfs::FsFileType;
 use std::path::PathBuf;
 
 #[derive(Subcommand, Debug, PartialEq, Eq)]
@@ -60,8 +62,10 @@ pub enum Commands {
 // / 4. 是否已存在的符号链接(TargetLinkExists)
 
 /// 检查new_desktop_dir_path
-/// 需是目录或指向目录的符号链接
+/// 需是目录或指向目录的符号链接，路径中的`~`与环境变量会先被展开
 fn validate_new_desktop_dir_path(s: &str) -> Result<PathBuf, String> {
+    let expanded = expand_env(s)?;
+    let s = expanded.as_str();
     let path = std::path::Path::new(s);
 
     if s.trim().is_empty() {
@@ -72,29 +76,26 @@ fn validate_new_desktop_dir_path(s: &str) -> Result<PathBuf, String> {
     } else if s.len() < 4 {
         Err(MyError::new(ErrorCode::InvalidInput, "无效的路径".into()).into())
     } else {
-        match get_path_type(path) {
-            Ok(_) => Ok(s.into()),
-            Err(e) if e.code == ErrorCode::TargetExistsAndNotLink => Ok(s.into()),
-            Err(e) if e.code == ErrorCode::TargetLinkExists => {
-                if path.is_dir() {
+        match inspect_path(path) {
+            PathStatus::Dir | PathStatus::File => Ok(s.into()),
+            PathStatus::Symlink { target_kind, .. } => {
+                if target_kind == FsFileType::Dir {
                     Ok(s.into())
                 } else {
                     Err("目标是个符号链接，但指向的不是一个目录".into())
                 }
             }
-            Err(mut e) if e.code == ErrorCode::BrokenSymlink => {
-                e.msg = format!("目标是损坏的符号链接：{}", e.msg);
-                Err(e.into())
-            }
-            Err(mut e) if e.code == ErrorCode::FileNotExist => {
-                e.msg = format!("目标不存在：{}", e.msg);
-                Err(e.into())
-            }
-            Err(mut e) if e.code == ErrorCode::FileNotExist => {
-                e.msg = format!("目标不存在：{}", e.msg);
-                Err(e.into())
-            }
-            Err(e) => Err(format!("检查路径失败：{e}")),
+            PathStatus::BrokenSymlink { .. } => Err(MyError::new(
+                ErrorCode::BrokenSymlink,
+                format!("目标是损坏的符号链接：{}", path.display()),
+            )
+            .into()),
+            PathStatus::Missing => Err(MyError::new(
+                ErrorCode::FileNotExist,
+                format!("目标不存在：{}", path.display()),
+            )
+            .into()),
+            PathStatus::Inaccessible(e) => Err(format!("检查路径失败：{e}")),
         }
     }
 }
@@ -2,7 +2,7 @@ use crate::state::DESKTOP_STATE;
 use crate::utils::func::{get_dir_temp, get_original_desktop_path};
 use crate::utils::rollback::Transaction;
 use crate::{ErrorCode, MyError, MyResult};
-use fastlink_core::utils::path::get_path_type;
+use fastlink_core::utils::path::{inspect_path, PathStatus};
 use std::path::PathBuf;
 
 pub fn handle_desktop_init() -> MyResult<bool> {
@@ -15,14 +15,11 @@ pub fn handle_desktop_init() -> MyResult<bool> {
 
     // 检查Desktop库与临时位置的性质
     // 部分情况需要纠正 todo
-    let desktop_status = get_path_type(desktop.as_path()).err().unwrap();
-    let desktop_temp_status = get_path_type(desktop_temp.as_path()).err().unwrap();
+    let desktop_status = inspect_path(desktop.as_path());
+    let desktop_temp_status = inspect_path(desktop_temp.as_path());
 
     // 一般情况：desktop为目录，而desktop_temp不存在
-    if desktop_status.code == ErrorCode::TargetExistsAndNotLink
-        && desktop.is_dir()
-        && desktop_temp_status.code == ErrorCode::FileNotExist
-    {
+    if matches!(desktop_status, PathStatus::Dir) && desktop_temp_status.is_missing() {
         log::info!("开始初始化");
         desktop_init(desktop.clone(), desktop_temp.clone()).map_err(|mut e| {
             e.msg = format!("初始化失败：{e}");
@@ -42,13 +39,15 @@ pub fn handle_desktop_init() -> MyResult<bool> {
         log::info!("初始化成功");
         Ok(true)
     // desktop不为目录
-    } else if desktop_status.code == ErrorCode::TargetExistsAndNotLink && !desktop.is_dir() {
+    } else if matches!(desktop_status, PathStatus::File) {
         Err(MyError::new(
             ErrorCode::Unknown,
             format!("你的Desktop库不是目录: {}", desktop.display()),
         ))
     // 已完成情况
-    } else if desktop_status.code == ErrorCode::TargetLinkExists && desktop_temp.is_dir() {
+    } else if let (PathStatus::Symlink { target, .. }, true) =
+        (&desktop_status, desktop_temp.is_dir())
+    {
         // todo: 检查状态，查看用户是否转移Desktop库，或有其他变动
 
         // 如果状态为空则修补
@@ -59,17 +58,8 @@ pub fn handle_desktop_init() -> MyResult<bool> {
                 state.initial_path = Some(desktop.clone());
                 state.initial_path_temp = Some(desktop_temp.clone());
 
-                let target = std::fs::read_link(&desktop);
                 state.cur_path = Some(desktop);
-                if let Ok(target) = target {
-                    state.cur_target = Some(target);
-                } else {
-                    log::debug!(
-                        "修补状态文件时，无法读取符号链接desktop指向: {}",
-                        target.err().unwrap()
-                    );
-                    state.cur_target = Some(desktop_temp);
-                }
+                state.cur_target = Some(target.clone());
             }
             {
                 DESKTOP_STATE.save()?;
@@ -82,7 +72,7 @@ pub fn handle_desktop_init() -> MyResult<bool> {
         Ok(false)
     } else {
         log::warn!(
-            "意外情况: \n{}\n{}\n{}\n{}",
+            "意外情况: \n{}\n{:?}\n{}\n{:?}",
             desktop.display(),
             desktop_status,
             desktop_temp.display(),
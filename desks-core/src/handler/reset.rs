@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use crate::handler::fresh::handle_fresh_desktop;
 use crate::{state::DESKTOP_STATE, utils::func::get_temp_path, utils::rollback::Transaction};
 use crate::{ErrorCode, MyError, MyResult};
-use fastlink_core::utils::path::get_path_type;
+use fastlink_core::utils::path::{inspect_path, PathStatus};
 
 pub fn handle_desktop_reset(keep_usual_paths: Option<bool>) -> MyResult<bool> {
     log::debug!("handle_desktop_reset");
@@ -38,13 +38,15 @@ pub fn handle_desktop_reset(keep_usual_paths: Option<bool>) -> MyResult<bool> {
     } else {
         let initial_path = initial_path.unwrap();
         let initial_path_temp = initial_path_temp.unwrap();
-        let initial_path_status = get_path_type(&initial_path).err().unwrap();
-        let initial_path_temp_status = get_path_type(&initial_path_temp).err().unwrap();
+        let initial_path_status = inspect_path(&initial_path);
+        let initial_path_temp_status = inspect_path(&initial_path_temp);
 
         // 仅当initial_path存在且为符号链接，initial_path_temp存在时重置
-        if initial_path_status.code == ErrorCode::TargetLinkExists
-            && (initial_path_temp_status.code == ErrorCode::TargetExistsAndNotLink
-                || initial_path_temp_status.code == ErrorCode::TargetLinkExists)
+        if matches!(initial_path_status, PathStatus::Symlink { .. })
+            && matches!(
+                initial_path_temp_status,
+                PathStatus::File | PathStatus::Dir | PathStatus::Symlink { .. }
+            )
         {
             desktop_reset(initial_path, initial_path_temp, cur_target.unwrap())?;
             {
@@ -56,9 +58,8 @@ pub fn handle_desktop_reset(keep_usual_paths: Option<bool>) -> MyResult<bool> {
             log::info!("桌面已刷新");
             Ok(true)
         // 或者当initial_path_temp存在且为目录，initial_path不存在时 (其他情况`1`)
-        } else if initial_path_temp_status.code == ErrorCode::TargetExistsAndNotLink
-            && initial_path_temp.is_dir()
-            && initial_path_status.code == ErrorCode::FileNotExist
+        } else if matches!(initial_path_temp_status, PathStatus::Dir)
+            && initial_path_status.is_missing()
         {
             // 把initial_path_temp重命名为initial_path
             desktop_reset_1(initial_path, initial_path_temp)?;
@@ -1,7 +1,8 @@
 use crate::{ErrorCode, MyError, MyResult};
-use fastlink_core::utils::path::get_path_type;
+use fastlink_core::utils::expand::expand_env;
+use fastlink_core::utils::path::{inspect_path, PathStatus};
+use fastlink_core::utils::vfs::FsFileType;
 use std::path::{Path, PathBuf};
-use std::{env, fs};
 use winreg::enums::*;
 use winreg::RegKey;
 
@@ -61,33 +62,23 @@ pub fn get_real_desktop_path() -> MyResult<PathBuf> {
     let desktop = get_original_desktop_path_string()?;
     let path = parse_env_vars(desktop)?;
 
-    let path_status = get_path_type(&path).err().unwrap();
-    match path_status.code {
+    match inspect_path(&path) {
         // 是目录
-        ErrorCode::TargetExistsAndNotLink => {
-            if path.is_dir() {
-                Ok(path)
-            } else {
-                Err(MyError::new(
-                    ErrorCode::Unknown,
-                    "当前桌面是一个文件".into(),
-                ))
-            }
-        }
+        PathStatus::Dir => Ok(path),
+        PathStatus::File => Err(MyError::new(
+            ErrorCode::Unknown,
+            "当前桌面是一个文件".into(),
+        )),
         // 是符号链接
-        ErrorCode::TargetLinkExists => {
-            if path.is_dir() {
-                let target = fs::read_link(path)
-                    .map_err(|e| MyError::new(ErrorCode::Unknown, format!("{e}")))?;
-                Ok(target)
-            } else {
-                Err(MyError::new(
-                    ErrorCode::Unknown,
-                    "当前桌面指向一个文件".into(),
-                ))
-            }
-        }
-        _ => Err(path_status),
+        PathStatus::Symlink {
+            target,
+            target_kind: FsFileType::Dir,
+        } => Ok(target),
+        PathStatus::Symlink { .. } => Err(MyError::new(
+            ErrorCode::Unknown,
+            "当前桌面指向一个文件".into(),
+        )),
+        status => Err(status.into_error(&path)),
     }
 }
 
@@ -103,131 +94,15 @@ fn get_original_desktop_path_string() -> MyResult<String> {
     Ok(desktop_path)
 }
 
-/// 查找并替换字符串中的环境变量占位符
+/// 查找并替换字符串中的环境变量占位符，见`fastlink_core::utils::expand::expand_env`
 pub fn parse_env_vars(path: String) -> MyResult<PathBuf> {
-    let replacements = match_env_placeholders(&path)?;
-    let result = apply_replacements(path, replacements);
-    Ok(result.into())
-}
-
-/// 查找字符串中的环境变量占位符，返回占位符及其值的列表
-fn match_env_placeholders(path: &str) -> MyResult<Vec<(String, String)>> {
-    let mut replacements = Vec::new();
-    let chars: Vec<char> = path.chars().collect();
-    let len = chars.len();
-    let mut i = 0;
-
-    while i < len {
-        if i + 1 < len && chars[i] == '%' {
-            if let Some((placeholder, value, next_i)) = try_parse_windows_var(&chars, path, i)? {
-                replacements.push((placeholder, value));
-                i = next_i;
-                continue;
-            }
-        } else if chars[i] == '$' {
-            if let Some((placeholder, value, next_i)) = try_parse_unix_var(&chars, path, i)? {
-                replacements.push((placeholder, value));
-                i = next_i;
-                continue;
-            }
-        }
-        i += 1;
-    }
-
-    Ok(replacements)
-}
-
-/// 尝试解析 Windows 风格的 %VAR% 占位符
-fn try_parse_windows_var(
-    chars: &[char],
-    path: &str,
-    start: usize,
-) -> MyResult<Option<(String, String, usize)>> {
-    let len = chars.len();
-    let mut i = start + 1; // 跳过起始 %
-    let var_start = i;
-
-    while i < len && chars[i] != '%' && (chars[i].is_alphanumeric() || chars[i] == '_') {
-        i += 1;
-    }
-
-    if i < len && chars[i] == '%' && i > var_start {
-        let var_name = &path[var_start..i];
-        let var_value = env::var(var_name).map_err(|e| {
-            MyError::new(
-                ErrorCode::IoError,
-                format!("解析Desktop库位置失败，环境变量 {var_name} 不存在: {e}"),
-            )
-        })?;
-        let placeholder = &path[start..=i];
-        Ok(Some((placeholder.to_string(), var_value, i + 1)))
-    } else {
-        Ok(None)
-    }
-}
-
-/// 尝试解析 Unix 风格的 ${VAR} 或 $VAR 占位符
-fn try_parse_unix_var(
-    chars: &[char],
-    path: &str,
-    start: usize,
-) -> MyResult<Option<(String, String, usize)>> {
-    let len = chars.len();
-    let mut i = start + 1; // 跳过 $
-
-    if i < len && chars[i] == '{' {
-        i += 1; // 跳过 {
-        let var_start = i;
-
-        while i < len && chars[i] != '}' && (chars[i].is_alphanumeric() || chars[i] == '_') {
-            i += 1;
-        }
-
-        if i < len && chars[i] == '}' && i > var_start {
-            let var_name = &path[var_start..i];
-            let var_value = env::var(var_name).map_err(|e| {
-                MyError::new(
-                    ErrorCode::IoError,
-                    format!("解析Desktop库位置失败，环境变量 {var_name} 不存在: {e}"),
-                )
-            })?;
-            let placeholder = &path[start..=i];
-            return Ok(Some((placeholder.to_string(), var_value, i + 1)));
-        }
-    } else {
-        let var_start = i;
-
-        while i < len && (chars[i].is_alphanumeric() || chars[i] == '_') {
-            i += 1;
-        }
-
-        if i > var_start {
-            let var_name = &path[var_start..i];
-            let var_value = env::var(var_name).map_err(|e| {
-                MyError::new(
-                    ErrorCode::IoError,
-                    format!("解析Desktop库位置失败，环境变量 {var_name} 不存在: {e}"),
-                )
-            })?;
-            let placeholder = &path[start..i];
-            return Ok(Some((placeholder.to_string(), var_value, i)));
-        }
-    }
-
-    Ok(None)
-}
-
-/// 将占位符替换为环境变量值
-fn apply_replacements(mut path: String, replacements: Vec<(String, String)>) -> String {
-    for (placeholder, value) in replacements {
-        path = path.replace(&placeholder, &value);
-    }
-    path
+    expand_env(&path).map(PathBuf::from)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
 
     #[test]
     fn test_get_dir_temp() {
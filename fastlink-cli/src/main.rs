@@ -14,8 +14,8 @@ pub mod utils;
 pub use fastlink_core::types::err::{ErrorCode, MyError, MyResult};
 
 use crate::types::args::Args;
-use fastlink_core::types::link_task::LinkTask;
-use utils::func::special_warn;
+use fastlink_core::types::link_task_args::LinkTaskArgsBuilder;
+use utils::func::{run_command, special_warn, work_paths};
 
 fn main() {
     let args: Args = Args::parse();
@@ -25,15 +25,23 @@ fn main() {
         .init();
     log::debug!("{:?}", args);
 
-    special_warn(&args);
+    if let Some(command) = &args.command {
+        if let Err(e) = run_command(command, args.profile()) {
+            e.log();
+        }
+        return;
+    }
 
-    let task_res = LinkTask::try_from(&args);
+    // rm模式与check模式不做警告
+    if !(args.check || args.rm) {
+        special_warn(&args.paths);
+    }
 
-    match task_res {
-        Ok(task) => match task.work() {
-            Ok(()) => (),
-            Err(e) => e.log(),
-        },
-        Err(e) => e.log(),
+    if let Err(e) = work_paths(
+        LinkTaskArgsBuilder::from(&args),
+        &args.paths,
+        args.profile(),
+    ) {
+        e.log();
     }
 }
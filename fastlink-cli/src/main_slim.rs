@@ -1,11 +1,11 @@
 /// 与 main.rs 基本一致
 pub mod utils;
-use utils::func::special_warn;
+use utils::func::{run_command, special_warn, work_paths};
 pub mod types;
 use crate::types::args::Args;
 use clap::Parser;
 pub use fastlink_core::types::err::{ErrorCode, MyError, MyResult};
-use fastlink_core::types::link_task::LinkTask;
+use fastlink_core::types::link_task_args::LinkTaskArgsBuilder;
 
 fn main() {
     let args: Args = Args::parse();
@@ -14,15 +14,23 @@ fn main() {
     fastlink_core::utils::logs::LogIniter::new(args.quiet, args.debug, None).init();
     log::debug!("{:?}", args);
 
-    special_warn(&args);
+    if let Some(command) = &args.command {
+        if let Err(e) = run_command(command, args.profile()) {
+            e.log();
+        }
+        return;
+    }
 
-    let task_res = LinkTask::try_from(&args);
+    // rm模式与check模式不做警告
+    if !(args.check || args.rm) {
+        special_warn(&args.paths);
+    }
 
-    match task_res {
-        Ok(task) => match task.work() {
-            Ok(()) => (),
-            Err(e) => e.log(),
-        },
-        Err(e) => e.log(),
+    if let Err(e) = work_paths(
+        LinkTaskArgsBuilder::from(&args),
+        &args.paths,
+        args.profile(),
+    ) {
+        e.log();
     }
 }
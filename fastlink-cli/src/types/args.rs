@@ -1,6 +1,18 @@
-use clap::Parser;
-use fastlink_core::types::err::{ErrorCode, MyError};
+use clap::{ArgGroup, Parser, Subcommand};
+use fastlink_core::types::err::{ErrorCode, MyError, MyResult};
+use fastlink_core::types::link_batch::{dir_dst, load_link_pairs, LinkPair};
+use fastlink_core::types::link_task_args::{
+    BrokenSrcPolicy, ConflictPolicy, LinkFallback, LinkKind, TargetKind,
+};
+#[cfg(feature = "fastlink-regex")]
+use fastlink_core::types::link_task_args::{ReDirGranularity, ReMatchTarget};
+#[cfg(feature = "fastlink-regex")]
+use fastlink_core::types::meta_filter::{parse_size, parse_time};
+use fastlink_core::utils::dedupe::{DedupeKeep, DedupeLinkKind};
+use fastlink_core::utils::script::ScriptKind;
 use path_clean::PathClean;
+#[cfg(feature = "fastlink-regex")]
+use std::time::SystemTime;
 
 use crate::types::args_example_text::EXAMPLE;
 
@@ -8,59 +20,280 @@ use crate::types::args_example_text::EXAMPLE;
 #[command(
     version,
     about = "A tool to make symlink fastly and smartly\n一个智能且方便的符号链接创建工具",
-    long_about = EXAMPLE
+    long_about = EXAMPLE,
+    override_usage = "fastlink [OPTIONS] <SRC> [DST]\n       fastlink [OPTIONS] -t <DIR> <SRC>...\n       fastlink [OPTIONS] <COMMAND>",
+    subcommand_negates_reqs = true,
+    group(ArgGroup::new("check_or_rm").args(["check", "rm"])),
+    mut_arg("only_managed", |arg| arg.requires("check_or_rm"))
 )]
+/// 不使用子命令时为旧版参数形式：`fastlink <SRC> [DST] [-c|--rm] [OPTIONS]`，
+/// 等同于make/check/rm子命令，保留以兼容已有脚本
 pub struct Args {
-    /// 源文件/源目录路径，表示的是符号链接指向的路径(Point at who)。
-    #[arg(required = true, value_parser = validate_src)]
-    pub src: String,
+    #[command(subcommand)]
+    pub command: Option<Commands>,
 
-    /// 目标路径，可选，区分文件拓展名，表示的是要创建在什么位置(Where to create)。
-    /// 为空则自动以<SRC>路径名称填充
-    pub dst: Option<String>,
+    #[command(flatten)]
+    pub paths: PathArgs,
 
     /// 高优先级参数, 切换为检查模式，添加后不会创建链接: 检查<SRC>属性，包含文件/目录/符号链接，以及符号链接损坏与否
     ///
-    /// 如果只给出<SRC>，则检查SRC，若同时传入DST，则检查DST.支持Re.
-    #[arg(short, long)]
+    /// 如果只给出<SRC>，则检查SRC，若同时传入DST，则检查DST.支持Re. 推荐使用check子命令
+    #[arg(short, long, conflicts_with = "rm")]
     pub check: bool,
 
     /// 高优先级参数, 切换为删除模式，添加后不会创建链接: 若<SRC>是符号链接，则删除。
     ///
-    /// 如果只给出<SRC>，则删除SRC，若同时传入DST，则检查DST.支持Re.
+    /// 如果只给出<SRC>，则删除SRC，若同时传入DST，则删除DST.支持Re. 推荐使用rm子命令
     #[arg(long)]
     pub rm: bool,
 
+    #[command(flatten)]
+    pub make: MakeArgs,
+
+    #[command(flatten)]
+    pub check_rm: CheckRmArgs,
+
+    #[command(flatten)]
+    pub matching: MatchArgs,
+
+    /// 只输出warn与error level的日志
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// 输出debug level的日志
+    #[arg(long, global = true)]
+    pub debug: bool,
+
+    #[cfg(feature = "save-log")]
+    /// 在目标路径输出/保存/导出本次处理日志
+    /// 若路径不存在，则将当前工作目录并重命名为fastlink-%y-%m-%d-%h-%m-%s.log
+    #[arg(long, global = true)]
+    pub save_log: Option<String>,
+
+    #[cfg(feature = "fastlink-config")]
+    /// 使用配置文件中[profiles.PROFILE]的参数，覆盖同一配置文件的[defaults]
+    ///
+    /// 参数优先级：命令行 > 项目配置(.fastlink.toml，从当前目录向上查找) > 用户配置(<配置目录>/fastlink/config.toml) > 内置默认值
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+}
+
+impl Args {
+    /// 命令行选择的profile，未启用配置文件功能时始终为None
+    pub fn profile(&self) -> Option<&str> {
+        #[cfg(feature = "fastlink-config")]
+        return self.profile.as_deref();
+        #[cfg(not(feature = "fastlink-config"))]
+        None
+    }
+}
+
+/// <SRC>与[DST]，make/check/rm共用
+#[derive(clap::Args, Debug, Default, Clone)]
+pub struct PathArgs {
+    /// 源文件/源目录路径，表示的是符号链接指向的路径(Point at who)。
+    ///
+    /// <SRC>与[DST]支持`~`与环境变量：%VAR%、$VAR、${VAR}、${VAR:-默认值}，使用%%、$$转义
+    #[arg(required_unless_present = "from_file", value_parser = validate_src)]
+    pub src: Option<String>,
+
+    /// 目标路径，可选，区分文件拓展名，表示的是要创建在什么位置(Where to create)。
+    /// 为空则自动以<SRC>路径名称填充。使用-t时作为另一个<SRC>
+    pub dst: Option<String>,
+
+    /// 使用-t时的更多<SRC>
+    #[arg(value_name = "SRC", value_parser = validate_src, requires = "target_dir")]
+    pub more_srcs: Vec<String>,
+
+    /// 将所有<SRC>链接到目录DIR下（使用<SRC>名称），类似`ln -t`，此时不再接受[DST]
+    ///
+    /// 所有路径对作为一批处理：任一<SRC>无效则不创建任何链接，创建前只确认一次，最后汇总结果
+    #[arg(short = 't', long, value_name = "DIR")]
+    pub target_dir: Option<String>,
+
+    /// 从文件读取路径对，`-`表示标准输入。每行为`SRC`或`SRC<TAB>DST`，空行会被忽略
+    ///
+    /// 与-t同时使用时，只有SRC的行链接到DIR下。所有路径对先经过解析与验证，
+    /// 任一行无效则逐行报告（带行号）且不创建任何链接
+    #[arg(long, value_name = "PATH|-", conflicts_with = "src")]
+    pub from_file: Option<String>,
+
+    /// --from-file的输入以NUL分隔（如`find -print0`、`fd -0`）
+    #[arg(short = '0', long = "null", requires = "from_file")]
+    pub null: bool,
+
     /// *追加*<SRC>的文件拓展名到[DST]，不会去除/替换
     /// 保留拓展名之后可以通过对符号链接双击、运行等操作让系统使用默认应用打开或执行。
     ///
     /// src:".jpg", dst: ".jpg" -> dst: ".jpg"; src:".jpg", dst: ".temp" -> dst: ".jpg.temp"
     #[arg(short, long)]
     pub keep_extention: bool,
+}
+
+impl PathArgs {
+    /// 使用-t或--from-file时返回批量处理的路径对，否则为None
+    pub fn batch(&self) -> MyResult<Option<Vec<LinkPair>>> {
+        let mut pairs = match (&self.from_file, &self.target_dir) {
+            (Some(source), _) => load_link_pairs(source, self.null)?,
+            (None, Some(_)) => self
+                .src
+                .iter()
+                .chain(self.dst.iter())
+                .chain(self.more_srcs.iter())
+                .map(|src| LinkPair::new(src.clone(), None))
+                .collect(),
+            (None, None) => return Ok(None),
+        };
+        if let Some(dir) = self.target_dir.as_deref() {
+            for pair in pairs.iter_mut().filter(|pair| pair.dst.is_none()) {
+                pair.dst = Some(dir_dst(dir));
+            }
+        }
+        Ok(Some(pairs))
+    }
+}
 
+/// 只在创建链接时生效的参数
+#[derive(clap::Args, Debug, Default, Clone)]
+#[command(next_help_heading = "Make Options")]
+pub struct MakeArgs {
     /// 自动创建不存在的目录
     #[arg(long, visible_alias("md"))]
     pub make_dir: bool,
 
-    /// 只输出warn与error level的日志
-    #[arg(short, long)]
-    pub quiet: bool,
+    /// [DST]处已存在符号链接时的处理方式，默认overwrite-broken，即只覆盖损坏的符号链接
+    #[arg(
+        long,
+        visible_alias("conflict"),
+        value_name = "error|skip|overwrite|overwrite-broken",
+        conflicts_with_all = ["overwrite_links", "skip_exist_links"]
+    )]
+    pub on_conflict: Option<ConflictPolicy>,
 
-    /// 输出debug level的日志
+    /// 覆盖同名已存在的符号链接，同--on-conflict overwrite
+    #[arg(
+        long,
+        visible_alias("overwrite"),
+        visible_alias("overwrite-link"),
+        conflicts_with = "skip_exist_links"
+    )]
+    pub overwrite_links: bool,
+
+    /// 针对[DST]，跳过同名已存在的符号链接，同--on-conflict skip
+    #[arg(
+        long,
+        visible_alias("skip-exist"),
+        visible_alias("skip-exists"),
+        visible_alias("skip-exist-link"),
+        visible_alias("skip-exists-links"),
+        conflicts_with = "overwrite_links"
+    )]
+    pub skip_exist_links: bool,
+
+    /// <SRC>为损坏的符号链接或不存在时的处理方式，默认skip，allow则创建悬空的符号链接
+    #[arg(
+        long,
+        value_name = "error|skip|allow",
+        conflicts_with = "allow_broken_src"
+    )]
+    pub broken_src: Option<BrokenSrcPolicy>,
+
+    /// 允许使用不存在或损坏的符号链接作为src，创建悬空的符号链接，同--broken-src allow
     #[arg(long)]
-    pub debug: bool,
+    pub allow_broken_src: bool,
+
+    /// 符号链接目标的类型，Windows下<SRC>不存在时需指定file或dir，默认auto
+    #[arg(long, value_name = "auto|file|dir")]
+    pub target_kind: Option<TargetKind>,
+
+    /// 创建的链接类型，shortcut创建Windows快捷方式(.lnk，[DST]自动追加扩展名)，不需要管理员权限，默认symlink
+    #[arg(long, value_name = "symlink|shortcut|hardlink|copy")]
+    pub link_kind: Option<LinkKind>,
+
+    /// 以--link-kind创建失败（如没有创建符号链接的权限）时，对每个路径对依次尝试的链接类型，如hardlink,copy
+    #[arg(long, value_name = "KIND,...")]
+    pub fallback: Option<LinkFallback>,
+
+    /// 限定根目录：链接路径或链接目标解析（含`..`与父目录中的符号链接）后不在ROOT内时拒绝创建
+    #[arg(long, value_name = "ROOT")]
+    pub within: Option<String>,
+
+    #[cfg(feature = "fastlink-regex")]
+    /// 取消re匹配后，创建链接前的用户手动检查阶段
+    #[arg(long, visible_alias("no-check"))]
+    pub re_no_check: bool,
+
+    #[cfg(feature = "fastlink-regex")]
+    /// 流式处理re匹配：边遍历边创建链接，不预先收集匹配结果，内存占用不随目录树大小增长
+    ///
+    /// 会多遍历一次<SRC>以确定最深层目录并检查展平模式下的重复名称；无法在创建前分页确认，需要同时指定--re-no-check
+    #[arg(long, visible_alias("stream"))]
+    pub re_stream: bool,
+}
+
+impl MakeArgs {
+    /// 命令行中给出的冲突处理方式，未给出时为None
+    pub fn conflict_policy(&self) -> Option<ConflictPolicy> {
+        if self.overwrite_links {
+            Some(ConflictPolicy::Overwrite)
+        } else if self.skip_exist_links {
+            Some(ConflictPolicy::Skip)
+        } else {
+            self.on_conflict
+        }
+    }
+
+    /// 命令行中给出的损坏src处理方式，未给出时为None
+    pub fn broken_src_policy(&self) -> Option<BrokenSrcPolicy> {
+        if self.allow_broken_src {
+            Some(BrokenSrcPolicy::Allow)
+        } else {
+            self.broken_src
+        }
+    }
+}
+
+/// 只在检查/删除链接时生效的参数
+#[derive(clap::Args, Debug, Default, Clone)]
+#[command(next_help_heading = "Check/Rm Options")]
+pub struct CheckRmArgs {
+    /// 只处理由fastlink创建的链接，其他工具或用户自行创建的链接会被跳过
+    #[arg(long)]
+    pub only_managed: bool,
+}
 
+/// re匹配相关参数，make/check/rm共用
+#[derive(clap::Args, Debug, Default, Clone)]
+#[command(next_help_heading = "Match Options")]
+pub struct MatchArgs {
     #[cfg(feature = "fastlink-regex")]
     /// 对<SRC>内容应用正则表达式，匹配项将于[DST]相应创建。对于程序如何处理多层级的目录见only_dir参数
     ///
+    /// 可多次传入，多个正则表达式之间为或关系，任一匹配即可。
+    /// 默认匹配相对<SRC>的路径，见--re-match参数。
+    ///
     /// 注：若启用make_dir参数，则还会尝试对<SRC>的子目录以及更深层(默认最大4层)进行匹配并创建，
     /// 若要限制深度，使用--re-max-depth参数。
     ///
     /// 注：匹配的路径不受--keep_extention参数影响。
     ///
-    /// 注：只会为最深层的目录创建符号链接，其他层次目录则会正常创建文件夹
+    /// 注：默认只会为最深层的目录创建符号链接，其他层次目录则会正常创建文件夹，见--re-dir-granularity参数
     #[arg(long, visible_alias("re"), value_parser = validate_regex)]
-    pub regex: Option<regex::Regex>,
+    pub regex: Vec<regex::Regex>,
+
+    #[cfg(feature = "fastlink-regex")]
+    /// 正则表达式匹配的路径形式，默认relative
+    ///
+    /// name: 只匹配文件/目录名称；
+    /// relative: 匹配相对<SRC>的路径（使用系统路径分隔符），结果不受<SRC>所在位置影响；
+    /// absolute: 匹配完整的绝对路径
+    #[arg(long, value_name = "name|relative|absolute")]
+    pub re_match: Option<ReMatchTarget>,
+
+    #[cfg(feature = "fastlink-regex")]
+    /// 正则表达式匹配时忽略大小写
+    #[arg(long, visible_alias("ignore-case"))]
+    pub re_ignore_case: bool,
 
     #[cfg(feature = "fastlink-regex")]
     /// 限制regex匹配的最大深度，启用make_dir参数时，默认4层，否则为1层,
@@ -73,9 +306,9 @@ pub struct Args {
     #[arg(long, conflicts_with = "only_dir", visible_alias("F"))]
     pub only_file: bool,
 
-    /// 只为最深目录创建符号链接，其他目录则会创建文件夹，受re-depth参数约束
+    /// 只为目录创建符号链接，为哪一层目录创建由--re-dir-granularity参数决定，受re-depth参数约束
     ///
-    /// 程序将如何为目录创建符号链接？
+    /// 程序将如何为目录创建符号链接？（默认的deepest粒度）
     /// e.g.1 给定src的子目录最深为5层，re-depth参数默认为4层，会为层级为4的目录创建符号链接，
     /// 1、2、3层只会创建文件夹
     ///
@@ -84,16 +317,24 @@ pub struct Args {
     #[arg(long, conflicts_with = "only_file", visible_alias("D"))]
     pub only_dir: bool,
 
+    #[cfg(feature = "fastlink-regex")]
+    /// re匹配时为目录创建符号链接的粒度，默认deepest
+    ///
+    /// deepest: 只为匹配到的最深层目录创建符号链接，较浅层的匹配目录作为普通目录创建；
+    /// shallowest: 为首个匹配到的目录创建符号链接，并跳过其子树（<SRC>自身不参与匹配）；
+    /// files: 从不为目录创建符号链接，只为文件创建
+    #[arg(
+        long,
+        visible_alias("granularity"),
+        value_name = "deepest|shallowest|files"
+    )]
+    pub re_dir_granularity: Option<ReDirGranularity>,
+
     #[cfg(feature = "fastlink-regex")]
     /// re匹配过程中，深入读取符号链接进行匹配
     #[arg(long, visible_alias("follow-links"), visible_alias("follow-link"))]
     pub re_follow_links: bool,
 
-    #[cfg(feature = "fastlink-regex")]
-    /// 取消re匹配后，创建链接前的用户手动检查阶段
-    #[arg(long, visible_alias("no-check"))]
-    pub re_no_check: bool,
-
     #[cfg(feature = "fastlink-regex")]
     /// 对于re匹配的后所有内容，不按照原本目录（镜像）创建链接，
     /// 而是直接创建到[DST]中。
@@ -101,51 +342,240 @@ pub struct Args {
     #[arg(long, visible_alias("flatten"))]
     pub re_output_flatten: bool,
 
-    /// 覆盖同名已存在的符号链接，与--skip-exist-links互斥
-    #[arg(
-        long,
-        visible_alias("overwrite"),
-        visible_alias("overwrite-link"),
-        conflicts_with = "skip_exist_links"
-    )]
-    pub overwrite_links: bool,
+    #[cfg(feature = "fastlink-regex")]
+    /// re匹配时只匹配大小不小于SIZE的文件，如10K、1.5M、2G（按1024进位）。设置后目录不会被匹配
+    #[arg(long, value_parser = parse_size, value_name = "SIZE")]
+    pub min_size: Option<u64>,
 
-    /// --overwrite-links的较弱版本，但优先级高于--skip-exist-link，只覆盖损坏的符号链接.
-    /// 默认为true, 暂不支持关闭
-    #[arg(long, visible_alias("overwrite-broken"), default_value_t = true)]
-    pub overwrite_broken_link: bool,
+    #[cfg(feature = "fastlink-regex")]
+    /// re匹配时只匹配大小不大于SIZE的文件，格式同--min-size。设置后目录不会被匹配
+    #[arg(long, value_parser = parse_size, value_name = "SIZE")]
+    pub max_size: Option<u64>,
 
-    /// 针对[DST]，跳过同名已存在的符号链接，与--overwrite-links互斥
-    #[arg(
-        long,
-        visible_alias("skip-exist"),
-        visible_alias("skip-exists"),
-        visible_alias("skip-exist-link"),
-        visible_alias("skip-exists-links"),
-        conflicts_with = "overwrite_links"
-    )]
-    pub skip_exist_links: bool,
+    #[cfg(feature = "fastlink-regex")]
+    /// re匹配时只匹配修改时间晚于TIME的路径
+    ///
+    /// TIME为本地时间如`2024-01-31`、`"2024-01-31 12:00:00"`，或相对当前时间的时长如`30m`、`12h`、`7d`、`2w`
+    #[arg(long, value_parser = parse_time, value_name = "TIME")]
+    pub newer: Option<SystemTime>,
 
-    /// 针对<SRC>，跳过损坏的符号链接.
-    /// 默认为true, 暂不支持关闭
-    #[arg(
-        long,
-        visible_alias("skip-broken"),
-        visible_alias("skip-broken-link"),
-        visible_alias("skip-broken-links"),
-        default_value_t = true
-    )]
-    pub skip_broken_src_links: bool,
+    #[cfg(feature = "fastlink-regex")]
+    /// re匹配时只匹配修改时间早于TIME的路径，格式同--newer
+    #[arg(long, value_parser = parse_time, value_name = "TIME")]
+    pub older: Option<SystemTime>,
 
-    #[cfg(feature = "save-log")]
-    /// 在目标路径输出/保存/导出本次处理日志
-    /// 若路径不存在，则将当前工作目录并重命名为fastlink-%y-%m-%d-%h-%m-%s.log
+    #[cfg(feature = "fastlink-regex")]
+    /// re匹配时只匹配隐藏的文件/目录（Windows下为隐藏属性，其他平台为`.`开头的名称）
     #[arg(long)]
-    pub save_log: Option<String>,
+    pub hidden: bool,
 
-    /// 允许使用损坏的符号链接作为src (开了也不行，想都别想)
+    #[cfg(feature = "fastlink-regex")]
+    /// re匹配时只匹配可执行文件（Windows下按exe/com/bat/cmd/ps1拓展名判断）。设置后目录不会被匹配
     #[arg(long)]
-    pub allow_broken_src: bool,
+    pub executable: bool,
+
+    #[cfg(feature = "fastlink-regex")]
+    /// re匹配时只匹配符号链接（为符号链接本身再创建链接）
+    #[arg(long)]
+    pub symlink: bool,
+
+    #[cfg(feature = "fastlink-regex")]
+    /// re匹配遍历时不跨越文件系统（不进入其他分区/挂载点）
+    #[arg(long, visible_alias("xdev"))]
+    pub one_file_system: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// 创建符号链接，与不使用子命令的旧版参数形式相同
+    #[command(visible_alias = "mk")]
+    Make {
+        #[command(flatten)]
+        paths: PathArgs,
+
+        #[command(flatten)]
+        make: MakeArgs,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+    },
+
+    /// 检查路径属性，包含文件/目录/符号链接，以及符号链接损坏与否
+    ///
+    /// 如果只给出<SRC>，则检查SRC，若同时传入DST，则按与make相同的规则解析并检查DST.支持Re.
+    Check {
+        #[command(flatten)]
+        paths: PathArgs,
+
+        #[command(flatten)]
+        check_rm: CheckRmArgs,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+    },
+
+    /// 删除符号链接，不会删除普通文件/目录
+    ///
+    /// 如果只给出<SRC>，则删除SRC，若同时传入DST，则按与make相同的规则解析并删除DST.支持Re.
+    #[command(visible_alias = "remove")]
+    Rm {
+        #[command(flatten)]
+        paths: PathArgs,
+
+        #[command(flatten)]
+        check_rm: CheckRmArgs,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+    },
+
+    /// 列出目录下的所有符号链接及其目标（不跟随符号链接深入），损坏的符号链接会被标出
+    #[command(visible_alias = "list")]
+    Ls {
+        /// 要列出的目录或符号链接，默认为当前目录
+        #[arg(default_value = ".", value_parser = validate_src)]
+        path: String,
+
+        /// 只列出损坏的符号链接
+        #[arg(long)]
+        broken: bool,
+
+        #[cfg(feature = "fastlink-regex")]
+        /// 只列出相对<PATH>的路径匹配任一正则表达式的符号链接，可多次传入
+        #[arg(long, visible_alias("re"), value_parser = validate_regex)]
+        regex: Vec<regex::Regex>,
+    },
+
+    /// 将文件/目录移动到新位置，并在原位置创建指向新位置的符号链接
+    ///
+    /// 流程：复制并校验 -> 将原路径重命名为临时备份 -> 在原路径创建符号链接 -> 删除备份。
+    /// 创建符号链接之前的任一步骤失败，都会回滚，原路径保持不变
+    Relocate {
+        /// 要移动的文件/目录
+        #[arg(value_parser = validate_src)]
+        path: String,
+
+        /// 新位置。以'\'或'/'结尾或为已存在的目录时，移动到该目录下并保持原名称
+        #[arg(value_parser = validate_src)]
+        new_location: String,
+
+        /// 复制后额外比较文件内容的SHA-256，默认只比较目录结构与文件大小
+        #[arg(long)]
+        hash: bool,
+
+        /// 自动创建不存在的目录
+        #[arg(long, visible_alias("md"))]
+        make_dir: bool,
+    },
+
+    /// 将符号链接替换为其目标的真实副本（保留权限与修改时间），relocate/创建链接的逆操作
+    ///
+    /// <PATH>为目录时，处理目录下所有的符号链接（不跟随符号链接深入）；损坏的符号链接会被跳过，
+    /// 指向自身所在目录（或其上级目录）的符号链接无法复制，计为失败。
+    /// 副本先写入临时路径并校验，再通过重命名替换符号链接，中断时不会丢失路径；
+    /// 指向目录的符号链接需要先重命名为备份再将副本重命名到原处，两次重命名之间原路径短暂不存在
+    Materialize {
+        /// 符号链接，或包含符号链接的目录
+        #[arg(value_parser = validate_src)]
+        path: String,
+
+        #[cfg(feature = "fastlink-regex")]
+        /// 只处理相对<PATH>的路径匹配任一正则表达式的符号链接，可多次传入
+        #[arg(long, visible_alias("re"), value_parser = validate_regex)]
+        regex: Vec<regex::Regex>,
+
+        /// 只列出将被替换的符号链接，不做任何修改
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// 查找内容相同的文件，将重复的副本替换为指向保留原件的硬链接（同一卷）或符号链接
+    ///
+    /// 先按大小、再按SHA-256分组；符号链接与已有多个硬链接的文件视为已是链接，不会被处理，空文件不处理。
+    /// 链接先以临时名称创建再覆盖副本，中断时副本不会丢失
+    Dedupe {
+        /// 要查找重复文件的目录，可传入多个，跨目录的重复文件也会被处理
+        #[arg(required = true, value_parser = validate_src)]
+        dirs: Vec<String>,
+
+        /// 每组保留哪一份作为原件：修改时间最早/最新、路径最短、按目录顺序最先遍历到的
+        #[arg(long, value_name = "oldest|newest|shortest|first", default_value_t)]
+        keep: DedupeKeep,
+
+        /// 替换副本使用的链接：优先硬链接跨卷时改用符号链接、只用硬链接、只用符号链接
+        #[arg(long, value_name = "auto|hard|symlink", default_value_t)]
+        link: DedupeLinkKind,
+
+        /// 只列出重复文件与可节省的空间，不做任何修改
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// 将创建计划导出为可独立运行的脚本，在没有fastlink的机器上重建符号链接
+    ///
+    /// 计划来源：与make相同的<SRC> [DST]（支持Re与-t）、--from-file路径列表，
+    /// 或使用--scan递归扫描<SRC>下已有的符号链接。脚本会先创建所需的目录，已存在的链接路径会被跳过
+    Export {
+        /// 脚本类型：sh使用ln -s，ps1使用New-Item -ItemType SymbolicLink，cmd使用mklink
+        #[arg(long, value_name = "sh|ps1|cmd")]
+        script: ScriptKind,
+
+        /// 将<SRC>视为目录，递归扫描其中已有的符号链接并导出（链接目标原样保留）
+        #[arg(long, conflicts_with_all = ["dst", "target_dir", "from_file"])]
+        scan: bool,
+
+        /// 写入脚本的文件，默认输出到标准输出
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+
+        #[command(flatten)]
+        paths: PathArgs,
+
+        #[command(flatten)]
+        make: MakeArgs,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+    },
+
+    #[cfg(feature = "fastlink-config")]
+    /// 保存一次创建链接的参数（合并配置文件后），之后可用run重放
+    ///
+    /// 任务保存在<配置目录>/fastlink/tasks/<NAME>.toml，<SRC>与[DST]会转为绝对路径
+    Save {
+        /// 任务名，只允许字母、数字、-、_与.
+        name: String,
+
+        #[command(flatten)]
+        paths: PathArgs,
+
+        #[command(flatten)]
+        make: MakeArgs,
+
+        #[command(flatten)]
+        matching: MatchArgs,
+    },
+
+    #[cfg(feature = "fastlink-config")]
+    /// 重放使用save保存的任务，不再读取配置文件
+    Run {
+        /// 任务名
+        name: String,
+    },
+
+    #[cfg(feature = "fastlink-config")]
+    /// 配置文件相关操作
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[cfg(feature = "fastlink-config")]
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// 输出配置文件路径，以及合并后各参数的值与来源（受--profile影响）
+    Show,
 }
 
 /// 仅用于测试的Default实现
@@ -153,33 +583,19 @@ pub struct Args {
 impl Default for Args {
     fn default() -> Self {
         Args {
-            src: String::new(),
-            dst: None,
+            command: None,
+            paths: PathArgs::default(),
             check: false,
             rm: false,
-            keep_extention: false,
-            make_dir: false,
+            make: MakeArgs::default(),
+            check_rm: CheckRmArgs::default(),
+            matching: MatchArgs::default(),
             quiet: false,
             debug: false,
-            #[cfg(feature = "fastlink-regex")]
-            regex: None,
-            #[cfg(feature = "fastlink-regex")]
-            re_max_depth: None,
-            only_file: false,
-            only_dir: false,
-            #[cfg(feature = "fastlink-regex")]
-            re_follow_links: false,
-            #[cfg(feature = "fastlink-regex")]
-            re_no_check: false,
-            #[cfg(feature = "fastlink-regex")]
-            re_output_flatten: false,
-            overwrite_links: false,
-            overwrite_broken_link: true,
-            skip_exist_links: false,
-            skip_broken_src_links: true,
             #[cfg(feature = "save-log")]
             save_log: None,
-            allow_broken_src: false,
+            #[cfg(feature = "fastlink-config")]
+            profile: None,
         }
     }
 }
@@ -21,6 +21,42 @@ Example：
 
     // 6. src为目录，dst有目录倾向，在backup中创建名为mydir的链接
     fastlink mydir/ backup/ --md
+
+    // 7. 将saves目录移动到D:/backup中，并在原位置创建指向新位置的符号链接（复制后校验哈希，失败自动回滚）
+    fastlink relocate saves D:/backup/ --md --hash
+
+    // 8. 预览并将project目录下所有的符号链接替换为其目标的真实副本
+    fastlink materialize project --dry-run
+    fastlink materialize project
+
+    // 9. 使用子命令：创建、检查、删除符号链接，以及列出当前目录下损坏的符号链接
+    fastlink make data.csv tmp/ --md
+    fastlink check tmp/data.csv
+    fastlink rm data.csv tmp/
+    fastlink ls --broken
+
+    // 10. 预览assets与backup目录中的重复文件及可节省的空间，再将副本替换为链接（保留修改时间最早的一份）
+    fastlink dedupe assets backup --dry-run
+    fastlink dedupe assets backup
+
+    // 11. 将多个src一次性链接到links目录下（类似`ln -t`），创建前只确认一次
+    fastlink a.txt b.txt docs/ -t links --md
+
+    // 12. 从其他工具的输出读取路径对（每行SRC或SRC<TAB>DST），-0用于NUL分隔的输入
+    find assets -name '*.png' -print0 | fastlink --from-file - -0 -t links
+
+    // 13. 递归扫描dotfiles目录下已有的符号链接，导出为可在其他机器上重建它们的sh脚本
+    fastlink export --script sh --scan dotfiles -o links.sh
+
+    // 14. 不需要管理员权限：在桌面创建指向game目录的Windows快捷方式game.lnk，并查看其指向
+    fastlink game ~/Desktop/game --link-kind shortcut
+    fastlink check ~/Desktop/game.lnk
+
+    // 15. 没有创建符号链接的权限时，对每个路径对依次回退为硬链接、复制，日志中会给出实际使用的类型
+    fastlink ./saves D:/backup/saves --fallback hardlink,copy
+
+    // 16. 只删除由fastlink创建的链接，其他工具或手动创建的同名链接保留不动
+    fastlink rm ./saves D:/backup/saves --only-managed
 "#;
 
 #[cfg(not(feature = "fastlink-slim"))]
@@ -60,4 +96,51 @@ Example：
 
     // 11. 保存日志到默认路径（fastlink-YY-MM-DD-HH-MM-SS.log）
     fastlink data.csv tmp/ --md --save-log=""
+
+    // 12. 名称匹配jpg或png（多个--re为或关系，忽略大小写）的文件 -> 镜像目录创建链接到output目录中
+    fastlink ./photos output --re '\.jpg$' --re '\.png$' --re-match name --ignore-case --md
+
+    // 13. 将saves目录移动到D:/backup中，并在原位置创建指向新位置的符号链接（复制后校验哈希，失败自动回滚）
+    fastlink relocate saves D:/backup/ --md --hash
+
+    // 14. 只将project目录下txt文件的符号链接替换为其目标的真实副本
+    fastlink materialize project --re '\.txt$'
+
+    // 15. 使用子命令：创建、检查、删除符号链接，以及列出output目录下名称以.txt结尾的符号链接
+    fastlink make ./test-dir output --re .*\.txt --md
+    fastlink check ./test-dir output --re .*\.txt
+    fastlink rm ./test-dir output --re .*\.txt
+    fastlink ls output --re '\.txt$'
+
+    // 16. 使用配置文件中[profiles.game]的参数创建链接，并查看合并后的参数及其来源
+    fastlink make ./saves D:/backup/ --profile game
+    fastlink config show --profile game
+
+    // 17. 保存一次复杂的调用，之后在任意目录下重放
+    fastlink save videos ./test-dir D:/videos/ --re '\.mp4$' --md
+    fastlink run videos
+
+    // 18. 将重复文件替换为指向路径最短的一份的符号链接
+    fastlink dedupe D:/assets --keep shortest --link symlink
+
+    // 19. 将多个src一次性链接到links目录下（类似`ln -t`），创建前只确认一次
+    fastlink a.txt b.txt docs/ -t links --md
+
+    // 20. 从其他工具的输出读取路径对（每行SRC或SRC<TAB>DST），-0用于NUL分隔的输入
+    find assets -name '*.png' -print0 | fastlink --from-file - -0 -t links
+
+    // 21. 将Re匹配的创建计划导出为PowerShell脚本（脚本会先创建所需目录），不创建任何链接
+    fastlink export --script ps1 ./photos output --re '\.jpg$' -o links.ps1
+
+    // 22. 不需要管理员权限：在桌面创建指向game目录的Windows快捷方式game.lnk，并查看其指向
+    fastlink game ~/Desktop/game --link-kind shortcut
+    fastlink check ~/Desktop/game.lnk
+
+    // 23. 没有创建符号链接的权限时，对每个路径对依次回退为硬链接、复制；check/rm同样能识别回退创建的硬链接与副本
+    fastlink ./test-dir output --re '\.txt$' --fallback hardlink,copy
+    fastlink rm ./test-dir output --re '\.txt$'
+
+    // 24. 只检查/删除由fastlink创建的链接，其他工具或手动创建的同名链接会被跳过
+    fastlink check ./test-dir output --re '\.txt$' --only-managed
+    fastlink rm ./test-dir output --re '\.txt$' --only-managed
 "#;
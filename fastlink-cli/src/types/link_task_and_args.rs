@@ -1,8 +1,12 @@
-use crate::types::args::Args;
-use crate::MyError;
+use crate::types::args::{Args, CheckRmArgs, MakeArgs, MatchArgs, PathArgs};
+use crate::{MyError, MyResult};
+#[cfg(feature = "fastlink-config")]
+use fastlink_core::types::config::Config;
+#[cfg(feature = "fastlink-regex")]
+use fastlink_core::types::meta_filter::MetaFilter;
 use fastlink_core::types::{
     link_task::LinkTask,
-    link_task_args::{LinkTaskArgs, LinkTaskOpMode},
+    link_task_args::{LinkTaskArgs, LinkTaskArgsBuilder, LinkTaskOpMode},
     link_task_pre::LinkTaskPre,
 };
 
@@ -10,10 +14,9 @@ impl TryFrom<&Args> for LinkTask {
     type Error = MyError;
 
     fn try_from(args: &Args) -> Result<Self, Self::Error> {
-        let mut task_pre = LinkTaskPre::from(args);
-        task_pre.parse()?;
-        let task = LinkTask::try_from(task_pre)?;
-        log::debug!("已从LinkTaskPre构建LinkTask");
+        let task_args = with_config(LinkTaskArgsBuilder::from(args), args.profile())?;
+        let task = LinkTask::try_new(task_args)?;
+        log::debug!("已从Args构建LinkTask");
         Ok(task)
     }
 }
@@ -31,14 +34,9 @@ impl From<&Args> for LinkTaskPre {
 
 impl From<&Args> for LinkTaskOpMode {
     fn from(args: &Args) -> Self {
-        let check = args.check;
-        let rm = args.rm;
-        if check {
-            if rm {
-                log::warn!("rm模式请单独使用");
-            }
+        if args.check {
             Self::Check
-        } else if rm {
+        } else if args.rm {
             Self::Remove
         } else {
             Self::Make
@@ -46,41 +44,170 @@ impl From<&Args> for LinkTaskOpMode {
     }
 }
 
+#[cfg(feature = "fastlink-regex")]
+impl From<&MatchArgs> for MetaFilter {
+    fn from(matching: &MatchArgs) -> Self {
+        MetaFilter {
+            min_size: matching.min_size,
+            max_size: matching.max_size,
+            newer: matching.newer,
+            older: matching.older,
+            hidden: matching.hidden,
+            executable: matching.executable,
+            symlink: matching.symlink,
+            one_file_system: matching.one_file_system,
+        }
+    }
+}
+
 impl From<&Args> for LinkTaskArgs {
     fn from(args: &Args) -> Self {
-        LinkTaskArgs {
-            src: args.src.clone(),
-            dst: args.dst.clone(),
-            op_mode: LinkTaskOpMode::from(args),
-            #[cfg(feature = "fastlink-regex")]
-            re_pattern: args.regex.clone(),
-            #[cfg(feature = "fastlink-regex")]
-            re_max_depth: args
-                .re_max_depth
-                .unwrap_or(fastlink_core::types::link_task_args::DEFAULT_RE_MAX_DEPTH),
-            #[cfg(feature = "fastlink-regex")]
-            re_follow_links: args.re_follow_links,
-            keep_extention: args.keep_extention,
-            make_dir: args.make_dir,
-            only_file: args.only_file,
-            only_dir: args.only_dir,
-            overwrite_links: args.overwrite_links,
-            overwrite_broken_link: args.overwrite_broken_link,
-            skip_exist_links: args.skip_exist_links,
-            skip_broken_src_links: args.skip_broken_src_links,
-            #[cfg(feature = "fastlink-regex")]
-            re_no_check: args.re_no_check,
-            #[cfg(feature = "fastlink-regex")]
-            re_output_flatten: args.re_output_flatten,
-            allow_broken_src: args.allow_broken_src,
+        LinkTaskArgsBuilder::from(args).build()
+    }
+}
+
+impl From<&Args> for LinkTaskArgsBuilder {
+    fn from(args: &Args) -> Self {
+        // 旧版参数形式下，make参数在检查/删除模式同样参与解析[DST]与Re匹配深度
+        let builder = link_task_builder(LinkTaskOpMode::from(args), &args.paths, &args.matching);
+        args.check_rm.apply(args.make.apply(builder))
+    }
+}
+
+type FlagSetter = fn(LinkTaskArgsBuilder, bool) -> LinkTaskArgsBuilder;
+
+/// 由路径与re匹配参数构建LinkTaskArgsBuilder，旧版参数形式与make/check/rm子命令共用，
+/// 各子命令独有的参数再由`MakeArgs::apply`、`CheckRmArgs::apply`设置
+///
+/// 只设置命令行中显式给出的参数，未给出的参数留给配置文件与内置默认值
+pub fn link_task_builder(
+    op_mode: LinkTaskOpMode,
+    paths: &PathArgs,
+    matching: &MatchArgs,
+) -> LinkTaskArgsBuilder {
+    let mut builder =
+        LinkTaskArgsBuilder::new(paths.src.clone().unwrap_or_default()).op_mode(op_mode);
+    if let Some(dst) = &paths.dst {
+        builder = builder.dst(dst);
+    }
+
+    // 开关参数只能由命令行打开，未传入时不覆盖配置文件
+    let flags: [(bool, FlagSetter); 3] = [
+        (paths.keep_extention, LinkTaskArgsBuilder::keep_extention),
+        (matching.only_file, LinkTaskArgsBuilder::only_file),
+        (matching.only_dir, LinkTaskArgsBuilder::only_dir),
+    ];
+    for (on, set) in flags {
+        if on {
+            builder = set(builder, true);
+        }
+    }
+
+    #[cfg(feature = "fastlink-regex")]
+    {
+        let re_flags: [(bool, FlagSetter); 3] = [
+            (matching.re_ignore_case, LinkTaskArgsBuilder::re_ignore_case),
+            (
+                matching.re_follow_links,
+                LinkTaskArgsBuilder::re_follow_links,
+            ),
+            (
+                matching.re_output_flatten,
+                LinkTaskArgsBuilder::re_output_flatten,
+            ),
+        ];
+        for (on, set) in re_flags {
+            if on {
+                builder = set(builder, true);
+            }
+        }
+        builder = builder
+            .re_patterns(matching.regex.clone())
+            .re_meta_filter(MetaFilter::from(matching));
+        if let Some(target) = matching.re_match {
+            builder = builder.re_match_target(target);
+        }
+        if let Some(depth) = matching.re_max_depth {
+            builder = builder.re_max_depth(depth);
+        }
+        if let Some(granularity) = matching.re_dir_granularity {
+            builder = builder.re_dir_granularity(granularity);
+        }
+    }
+    builder
+}
+
+impl MakeArgs {
+    /// 设置只在创建链接时生效的参数
+    pub fn apply(&self, mut builder: LinkTaskArgsBuilder) -> LinkTaskArgsBuilder {
+        if let Some(conflict) = self.conflict_policy() {
+            builder = builder.conflict(conflict);
+        }
+        if let Some(broken_src) = self.broken_src_policy() {
+            builder = builder.broken_src(broken_src);
+        }
+        if let Some(kind) = self.target_kind {
+            builder = builder.target_kind(kind);
+        }
+        if let Some(kind) = self.link_kind {
+            builder = builder.link_kind(kind);
+        }
+        if let Some(fallback) = self.fallback {
+            builder = builder.link_fallback(fallback);
         }
+        if let Some(root) = self.within.as_deref() {
+            builder = builder.within(root);
+        }
+        if self.make_dir {
+            builder = builder.make_dir(true);
+        }
+
+        #[cfg(feature = "fastlink-regex")]
+        {
+            let re_flags: [(bool, FlagSetter); 2] = [
+                (self.re_no_check, LinkTaskArgsBuilder::re_no_check),
+                (self.re_stream, LinkTaskArgsBuilder::re_stream),
+            ];
+            for (on, set) in re_flags {
+                if on {
+                    builder = set(builder, true);
+                }
+            }
+        }
+        builder
     }
 }
 
+impl CheckRmArgs {
+    /// 设置只在检查/删除链接时生效的参数
+    pub fn apply(&self, mut builder: LinkTaskArgsBuilder) -> LinkTaskArgsBuilder {
+        if self.only_managed {
+            builder = builder.only_managed(true);
+        }
+        builder
+    }
+}
+
+/// 按 命令行 > 项目配置 > 用户配置 > 内置默认值 的优先级合并参数
+#[cfg(feature = "fastlink-config")]
+pub fn with_config(builder: LinkTaskArgsBuilder, profile: Option<&str>) -> MyResult<LinkTaskArgs> {
+    let config = Config::load()?.resolve(profile)?;
+    Ok(builder.merge(config.builder()).build())
+}
+
+/// 未启用配置文件功能时，直接使用命令行参数与内置默认值
+#[cfg(not(feature = "fastlink-config"))]
+pub fn with_config(builder: LinkTaskArgsBuilder, _profile: Option<&str>) -> MyResult<LinkTaskArgs> {
+    Ok(builder.build())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::types::args::Args;
-    use fastlink_core::types::link_task_args::{LinkTaskArgs, LinkTaskOpMode};
+    use crate::types::args::{Args, MakeArgs, MatchArgs, PathArgs};
+    use clap::Parser;
+    use fastlink_core::types::link_task_args::{
+        BrokenSrcPolicy, ConflictPolicy, LinkOptions, LinkTaskArgs, LinkTaskOpMode,
+    };
 
     #[test]
     fn test_link_task_op_mode_from_args() {
@@ -93,48 +220,107 @@ mod tests {
         assert_eq!(LinkTaskOpMode::from(&args), LinkTaskOpMode::Remove);
     }
 
+    #[test]
+    /// 子命令各自校验参数，旧版参数形式仍可使用
+    fn test_subcommands_parse() {
+        use crate::types::args::Commands;
+
+        let parse = |argv: &[&str]| Args::try_parse_from(argv);
+        let args = parse(&["fastlink", "make", "a", "b", "--md"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Commands::Make { ref paths, ref make, .. })
+                if paths.dst.as_deref() == Some("b") && make.make_dir
+        ));
+        let args = parse(&["fastlink", "mk", "a"]).unwrap();
+        assert!(matches!(args.command, Some(Commands::Make { .. })));
+        let args = parse(&["fastlink", "check", "a"]).unwrap();
+        assert!(matches!(args.command, Some(Commands::Check { .. })));
+        let args = parse(&["fastlink", "remove", "a", "b"]).unwrap();
+        assert!(matches!(args.command, Some(Commands::Rm { .. })));
+        let args = parse(&["fastlink", "ls", "--broken"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Commands::Ls { ref path, broken: true, .. }) if path == "."
+        ));
+        // 只在创建时生效的参数不能用于check/rm
+        assert!(parse(&["fastlink", "check", "a", "--md"]).is_err());
+        assert!(parse(&["fastlink", "rm", "a", "--overwrite"]).is_err());
+        assert!(parse(&["fastlink", "make"]).is_err());
+
+        // 旧版参数形式
+        let args = parse(&["fastlink", "a", "b", "--md"]).unwrap();
+        assert!(args.command.is_none());
+        assert_eq!(args.paths.src.as_deref(), Some("a"));
+        assert!(args.make.make_dir);
+        assert_eq!(LinkTaskOpMode::from(&args), LinkTaskOpMode::Make);
+        let args = parse(&["fastlink", "a", "--rm"]).unwrap();
+        assert_eq!(LinkTaskOpMode::from(&args), LinkTaskOpMode::Remove);
+        assert!(parse(&["fastlink"]).is_err());
+    }
+
+    #[test]
+    fn test_options_scoped_to_op_mode() {
+        let parse = |argv: &[&str]| Args::try_parse_from(argv).map(|args| args.command);
+        assert!(parse(&["fastlink", "a", "b", "--check", "--rm"]).is_err());
+        // --only-managed只用于检查/删除
+        assert!(parse(&["fastlink", "a", "b", "--only-managed"]).is_err());
+        assert!(parse(&["fastlink", "a", "b", "--rm", "--only-managed"]).is_ok());
+        assert!(parse(&["fastlink", "make", "a", "--only-managed"]).is_err());
+        assert!(parse(&["fastlink", "check", "a", "--only-managed"]).is_ok());
+        assert!(parse(&["fastlink", "rm", "a", "--make-dir"]).is_err());
+        #[cfg(feature = "fastlink-regex")]
+        {
+            assert!(parse(&["fastlink", "check", "a", "--re", "x", "--re-stream"]).is_err());
+            assert!(parse(&["fastlink", "rm", "a", "--re", "x", "--re-no-check"]).is_err());
+            assert!(parse(&["fastlink", "make", "a", "--re", "x", "--re-stream"]).is_ok());
+        }
+    }
+
     #[test]
     fn test_link_task_args_from_args() {
         let args = Args {
-            src: String::from("/tmp/source"),
-            dst: Some(String::from("/tmp/dest")),
-            keep_extention: true,
-            make_dir: true,
-            only_file: true,
-            only_dir: false,
-            overwrite_links: true,
-            overwrite_broken_link: true,
-            skip_exist_links: true,
-            skip_broken_src_links: true,
-            allow_broken_src: true,
-            check: false,
-            rm: false,
-            quiet: false,
-            debug: false,
-            #[cfg(feature = "fastlink-regex")]
-            regex: None,
-            #[cfg(feature = "fastlink-regex")]
-            re_max_depth: None,
-            #[cfg(feature = "fastlink-regex")]
-            re_follow_links: false,
-            #[cfg(feature = "fastlink-regex")]
-            re_no_check: false,
-            #[cfg(feature = "fastlink-regex")]
-            re_output_flatten: false,
-            #[cfg(feature = "save-log")]
-            save_log: None,
+            paths: PathArgs {
+                src: Some(String::from("/tmp/source")),
+                dst: Some(String::from("/tmp/dest")),
+                keep_extention: true,
+                ..Default::default()
+            },
+            make: MakeArgs {
+                make_dir: true,
+                overwrite_links: true,
+                allow_broken_src: true,
+                ..Default::default()
+            },
+            matching: MatchArgs {
+                only_file: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let link_args = LinkTaskArgs::from(&args);
+        assert_eq!(Some(link_args.src), args.paths.src);
+        assert_eq!(link_args.dst, args.paths.dst);
+        assert_eq!(link_args.keep_extention, args.paths.keep_extention);
+        assert_eq!(link_args.make_dir, args.make.make_dir);
+        assert_eq!(link_args.only_file, args.matching.only_file);
+        assert_eq!(link_args.only_dir, args.matching.only_dir);
+        assert_eq!(
+            link_args.link_options,
+            LinkOptions::new(ConflictPolicy::Overwrite, BrokenSrcPolicy::Allow)
+        );
+    }
+
+    #[test]
+    fn test_link_options_default_from_args() {
+        let args = Args {
+            paths: PathArgs {
+                src: Some(String::from("/tmp/source")),
+                ..Default::default()
+            },
+            ..Default::default()
         };
         let link_args = LinkTaskArgs::from(&args);
-        assert_eq!(link_args.src, args.src);
-        assert_eq!(link_args.dst, args.dst);
-        assert_eq!(link_args.keep_extention, args.keep_extention);
-        assert_eq!(link_args.make_dir, args.make_dir);
-        assert_eq!(link_args.only_file, args.only_file);
-        assert_eq!(link_args.only_dir, args.only_dir);
-        assert_eq!(link_args.overwrite_links, args.overwrite_links);
-        assert_eq!(link_args.overwrite_broken_link, args.overwrite_broken_link);
-        assert_eq!(link_args.skip_exist_links, args.skip_exist_links);
-        assert_eq!(link_args.skip_broken_src_links, args.skip_broken_src_links);
-        assert_eq!(link_args.allow_broken_src, args.allow_broken_src);
+        assert_eq!(link_args.link_options, LinkOptions::default());
     }
 }
@@ -1,16 +1,256 @@
-use crate::types::args::Args;
-use std::path::Path;
+#[cfg(feature = "fastlink-config")]
+use crate::types::args::ConfigAction;
+use crate::types::args::{Commands, PathArgs};
+use crate::types::link_task_and_args::{link_task_builder, with_config};
+#[cfg(feature = "fastlink-config")]
+use fastlink_core::types::config::Config;
+use fastlink_core::types::err::{ErrorCode, MyError, MyResult};
+use fastlink_core::types::link_batch::LinkBatch;
+use fastlink_core::types::link_task::LinkTask;
+use fastlink_core::types::link_task_args::{LinkTaskArgsBuilder, LinkTaskOpMode};
+#[cfg(feature = "fastlink-config")]
+use fastlink_core::types::saved_task::TaskStore;
+use fastlink_core::utils::dedupe::{dedupe, format_size, DedupeOptions};
+use fastlink_core::utils::expand::expand_path;
+use fastlink_core::utils::fs::collect_symlinks;
+use fastlink_core::utils::materialize::materialize;
+use fastlink_core::utils::path::canonicalize_path;
+use fastlink_core::utils::relocate::relocate;
+use fastlink_core::utils::script::{render_script, scan_links, ScriptLink};
+use std::path::{Path, PathBuf};
 
-/// 对一些特殊情况进行警告
-pub fn special_warn(args: &Args) {
-    // rm模式与check模式不做警告
-    if args.rm || args.check {
-        return;
+/// 合并配置文件后执行创建/检查/删除，使用-t或--from-file时作为批量任务处理
+pub fn work_paths(
+    builder: LinkTaskArgsBuilder,
+    paths: &PathArgs,
+    profile: Option<&str>,
+) -> MyResult<()> {
+    let args = with_config(builder, profile)?;
+    match paths.batch()? {
+        Some(pairs) => LinkBatch::from_pairs(args, pairs)?.work(),
+        None => LinkTask::try_new(args)?.work(),
+    }
+}
+
+/// 执行子命令，profile为命令行选择的配置文件profile
+pub fn run_command(command: &Commands, profile: Option<&str>) -> MyResult<()> {
+    match command {
+        Commands::Make {
+            paths,
+            make,
+            matching,
+        } => {
+            special_warn(paths);
+            let builder = make.apply(link_task_builder(LinkTaskOpMode::Make, paths, matching));
+            work_paths(builder, paths, profile)
+        }
+        Commands::Check {
+            paths,
+            check_rm,
+            matching,
+        } => {
+            let builder = check_rm.apply(link_task_builder(LinkTaskOpMode::Check, paths, matching));
+            work_paths(builder, paths, profile)
+        }
+        Commands::Rm {
+            paths,
+            check_rm,
+            matching,
+        } => {
+            let builder =
+                check_rm.apply(link_task_builder(LinkTaskOpMode::Remove, paths, matching));
+            work_paths(builder, paths, profile)
+        }
+        Commands::Ls {
+            path,
+            broken,
+            #[cfg(feature = "fastlink-regex")]
+            regex,
+        } => {
+            let root = canonicalize_path(expand_path(Path::new(path))?);
+            let mut links = collect_symlinks(&root)?;
+            links.sort();
+            let mut count = 0;
+            for link in links {
+                #[cfg(feature = "fastlink-regex")]
+                {
+                    let relative = link.strip_prefix(&root).unwrap_or(&link);
+                    let relative = relative.to_string_lossy();
+                    if !regex.is_empty() && !regex.iter().any(|re| re.is_match(&relative)) {
+                        continue;
+                    }
+                }
+                // 目标不存在即为损坏的符号链接
+                let is_broken = !link.exists();
+                if *broken && !is_broken {
+                    continue;
+                }
+                let target = std::fs::read_link(&link)
+                    .map(|t| t.display().to_string())
+                    .unwrap_or_else(|e| format!("<{}>", e));
+                if is_broken {
+                    println!("{} -> {} (broken)", link.display(), target);
+                } else {
+                    println!("{} -> {}", link.display(), target);
+                }
+                count += 1;
+            }
+            log::info!("共{}个符号链接", count);
+            Ok(())
+        }
+        Commands::Relocate {
+            path,
+            new_location,
+            hash,
+            make_dir,
+        } => relocate(Path::new(path), Path::new(new_location), *make_dir, *hash).map(|_| ()),
+        Commands::Materialize {
+            path,
+            #[cfg(feature = "fastlink-regex")]
+            regex,
+            dry_run,
+        } => {
+            #[cfg(feature = "fastlink-regex")]
+            let filter = |relative: &Path| {
+                let relative = relative.to_string_lossy();
+                regex.is_empty() || regex.iter().any(|re| re.is_match(&relative))
+            };
+            #[cfg(not(feature = "fastlink-regex"))]
+            let filter = |_: &Path| true;
+
+            let done = materialize(Path::new(path), *dry_run, &filter)?;
+            if *dry_run {
+                log::info!("dry-run: 共{}个符号链接将被替换", done.len());
+            } else {
+                log::info!("共替换{}个符号链接", done.len());
+            }
+            Ok(())
+        }
+        Commands::Dedupe {
+            dirs,
+            keep,
+            link,
+            dry_run,
+        } => {
+            let dirs: Vec<PathBuf> = dirs.iter().map(PathBuf::from).collect();
+            let options = DedupeOptions {
+                keep: *keep,
+                link: *link,
+                dry_run: *dry_run,
+            };
+            let report = dedupe(&dirs, options)?;
+            let replaced: usize = report.groups.iter().map(|g| g.duplicates.len()).sum();
+            if *dry_run {
+                log::info!(
+                    "dry-run: 共{}组重复文件，{}个副本将被替换，可节省{}",
+                    report.groups.len(),
+                    replaced,
+                    format_size(report.saved)
+                );
+            } else {
+                log::info!(
+                    "共{}组重复文件，替换{}个副本，节省{}",
+                    report.groups.len(),
+                    replaced,
+                    format_size(report.saved)
+                );
+            }
+            Ok(())
+        }
+        Commands::Export {
+            script,
+            scan,
+            output,
+            paths,
+            make,
+            matching,
+        } => {
+            let links = if *scan {
+                let root = paths.src.as_deref().unwrap_or(".");
+                scan_links(Path::new(root))?
+            } else {
+                // 脚本会自行创建所需的目录，导出时不要求也不创建[DST]的父目录
+                let builder = make.apply(link_task_builder(LinkTaskOpMode::Make, paths, matching));
+                let args = with_config(builder, profile)?;
+                let mut tasks = match paths.batch()? {
+                    Some(pairs) => LinkBatch::from_pairs(args, pairs)?.tasks,
+                    None => vec![LinkTask::try_new_plan(args)?],
+                };
+                let mut links = Vec::new();
+                for task in tasks.iter_mut() {
+                    task.plan()?;
+                    links.extend(
+                        task.iter_planned_pairs()?
+                            .map(|(src, dst)| ScriptLink::from_pair(src, dst)),
+                    );
+                }
+                links
+            };
+            let text = render_script(*script, &links)?;
+            match output {
+                Some(output) => {
+                    std::fs::write(output, text).map_err(|e| {
+                        MyError::new(ErrorCode::IoError, format!("写入脚本{}失败: {}", output, e))
+                    })?;
+                    log::info!(
+                        "已导出{}个符号链接到{}脚本: {}",
+                        links.len(),
+                        script,
+                        output
+                    );
+                }
+                None => print!("{}", text),
+            }
+            Ok(())
+        }
+        #[cfg(feature = "fastlink-config")]
+        Commands::Save {
+            name,
+            paths,
+            make,
+            matching,
+        } => {
+            if paths.src.is_none() {
+                return Err(MyError::new(
+                    ErrorCode::InvalidInput,
+                    "save需要给出<SRC>".into(),
+                ));
+            }
+            if paths.target_dir.is_some() {
+                return Err(MyError::new(
+                    ErrorCode::InvalidInput,
+                    "save不支持-t/--target-dir，请为每个<SRC>分别保存".into(),
+                ));
+            }
+            let builder = make.apply(link_task_builder(LinkTaskOpMode::Make, paths, matching));
+            let saved = TaskStore::open()?.save(name, &with_config(builder, profile)?)?;
+            log::info!("已保存任务 '{}': {}", name, saved.display());
+            Ok(())
+        }
+        #[cfg(feature = "fastlink-config")]
+        Commands::Run { name } => {
+            let args = TaskStore::open()?.load(name)?;
+            log::info!("重放任务 '{}'", name);
+            LinkTask::try_new(args)?.work()
+        }
+        #[cfg(feature = "fastlink-config")]
+        Commands::Config { action } => match action {
+            ConfigAction::Show => {
+                print!("{}", Config::load()?.resolve(profile)?.show());
+                Ok(())
+            }
+        },
     }
+}
 
-    let src = &args.src;
+/// 对一些特殊情况进行警告，仅用于make模式
+pub fn special_warn(paths: &PathArgs) {
+    // 使用-t时[DST]位置的参数是另一个<SRC>
+    let (Some(src), None) = (&paths.src, &paths.target_dir) else {
+        return;
+    };
     let src_path = Path::new(src);
-    let dst = &args.dst;
+    let dst = &paths.dst;
     // let keep_extention = args.keep_extention;
 
     // if keep_extention && dst.is_none() {
@@ -20,7 +260,7 @@ pub fn special_warn(args: &Args) {
     // dst不为空的情况
     if let Some(dst) = dst {
         let dst_path = Path::new(dst);
-        let dst_comps = dst_path.components().into_iter().collect::<Vec<_>>();
+        let dst_comps = dst_path.components().collect::<Vec<_>>();
 
         if src_path.is_file() && dst_path.is_dir() {
             log::warn!("<SRC>为文件路径而[DST]为目录路径，将自动使用<SRC>文件名追加到[DST]")
@@ -0,0 +1,436 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::types::link_task_args::{
+    BrokenSrcPolicy, ConflictPolicy, LinkFallback, LinkKind, LinkTaskArgsBuilder,
+};
+#[cfg(feature = "fastlink-regex")]
+use crate::types::link_task_args::{ReDirGranularity, ReMatchTarget, DEFAULT_RE_MAX_DEPTH};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// 项目配置文件名，从工作目录开始向上查找
+pub const PROJECT_CONFIG_NAME: &str = ".fastlink.toml";
+
+/// 配置文件中允许的键，与命令行参数对应（使用下划线），在`[defaults]`与`[profiles.NAME]`中设置
+pub const CONFIG_KEYS: [&str; 17] = [
+    "make_dir",
+    "keep_extention",
+    "only_file",
+    "only_dir",
+    "only_managed",
+    "on_conflict",
+    "broken_src",
+    "link_kind",
+    "link_fallback",
+    "re_match",
+    "re_ignore_case",
+    "re_max_depth",
+    "re_follow_links",
+    "re_no_check",
+    "re_output_flatten",
+    "re_dir_granularity",
+    "re_stream",
+];
+
+/// 单个配置文件的内容
+///
+/// ```toml
+/// [defaults]
+/// make_dir = true
+/// re_no_check = true
+///
+/// [profiles.game]
+/// re_max_depth = 2
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    defaults: toml::Table,
+    #[serde(default)]
+    profiles: BTreeMap<String, toml::Table>,
+}
+
+/// 一层配置，即一个配置文件
+#[derive(Debug)]
+struct ConfigLayer {
+    name: &'static str,
+    path: PathBuf,
+    file: ConfigFile,
+}
+
+/// 已加载的配置，优先级：项目配置 > 用户配置 > 内置默认值，命令行参数由调用方再叠加在最上层
+///
+/// 同一配置文件中，`--profile`选中的`[profiles.NAME]`覆盖该文件的`[defaults]`
+#[derive(Debug, Default)]
+pub struct Config {
+    /// 按优先级从低到高排列
+    layers: Vec<ConfigLayer>,
+    /// 查找过的配置文件路径，包含不存在的
+    searched: Vec<(&'static str, PathBuf)>,
+}
+
+/// 解析后的配置值，每个键记录其来源
+#[derive(Debug, Default)]
+pub struct ResolvedConfig {
+    pub profile: Option<String>,
+    /// (键, 值, 来源)
+    pub values: Vec<(String, toml::Value, String)>,
+    searched: Vec<(&'static str, PathBuf)>,
+}
+
+/// 用户配置文件路径：`<config_dir>/fastlink/config.toml`
+///
+/// Windows下为`%APPDATA%\fastlink\config.toml`，Linux下为`$XDG_CONFIG_HOME/fastlink/config.toml`
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("fastlink").join("config.toml"))
+}
+
+/// 从start开始向上查找项目配置文件
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_NAME))
+        .find(|path| path.is_file())
+}
+
+impl Config {
+    /// 加载用户配置与工作目录下（或其上级目录中）的项目配置，不存在的配置文件会被忽略
+    pub fn load() -> MyResult<Self> {
+        let mut paths = Vec::new();
+        if let Some(path) = user_config_path() {
+            paths.push(("用户配置", path));
+        }
+        match find_project_config(&crate::WORK_DIR) {
+            Some(path) => paths.push(("项目配置", path)),
+            None => paths.push(("项目配置", crate::WORK_DIR.join(PROJECT_CONFIG_NAME))),
+        }
+        Self::load_from(paths)
+    }
+
+    /// 按优先级从低到高加载给定的配置文件，不存在的配置文件会被忽略
+    pub fn load_from(paths: Vec<(&'static str, PathBuf)>) -> MyResult<Self> {
+        let mut config = Config::default();
+        for (name, path) in paths {
+            if path.is_file() {
+                let file = read_config_file(&path)?;
+                log::debug!("已加载{}: {}", name, path.display());
+                config.layers.push(ConfigLayer {
+                    name,
+                    path: path.clone(),
+                    file,
+                });
+            }
+            config.searched.push((name, path));
+        }
+        Ok(config)
+    }
+
+    /// 合并各层配置，profile不存在于任何配置文件中时返回错误
+    pub fn resolve(&self, profile: Option<&str>) -> MyResult<ResolvedConfig> {
+        if let Some(profile) = profile {
+            if !self
+                .layers
+                .iter()
+                .any(|layer| layer.file.profiles.contains_key(profile))
+            {
+                return Err(MyError::new(
+                    ErrorCode::InvalidInput,
+                    format!("配置文件中不存在profile '{}'", profile),
+                ));
+            }
+        }
+
+        let mut merged: BTreeMap<String, (toml::Value, String)> = BTreeMap::new();
+        for layer in &self.layers {
+            let mut tables = vec![("[defaults]".to_string(), &layer.file.defaults)];
+            if let Some(table) = profile.and_then(|p| layer.file.profiles.get(p)) {
+                tables.push((format!("[profiles.{}]", profile.unwrap()), table));
+            }
+            for (section, table) in tables {
+                for (key, value) in table {
+                    let source = format!("{} {} {}", layer.name, layer.path.display(), section);
+                    merged.insert(key.clone(), (value.clone(), source));
+                }
+            }
+        }
+
+        let values = CONFIG_KEYS
+            .iter()
+            .filter_map(|key| {
+                merged
+                    .remove(*key)
+                    .map(|(value, source)| (key.to_string(), value, source))
+            })
+            .collect();
+        Ok(ResolvedConfig {
+            profile: profile.map(String::from),
+            values,
+            searched: self.searched.clone(),
+        })
+    }
+}
+
+impl ResolvedConfig {
+    /// 转换为只设置了配置文件中给出的参数的LinkTaskArgsBuilder，用于与命令行参数合并
+    pub fn builder(&self) -> LinkTaskArgsBuilder {
+        self.values.iter().fold(
+            LinkTaskArgsBuilder::default(),
+            |builder, (key, value, _)| {
+                // 值已在加载时校验
+                apply_value(builder, key, value).unwrap()
+            },
+        )
+    }
+
+    /// 输出配置文件路径，以及所有键的最终值与来源
+    pub fn show(&self) -> String {
+        let mut out = String::new();
+        for (name, path) in &self.searched {
+            let state = if path.is_file() { "" } else { " (不存在)" };
+            let _ = writeln!(out, "# {}: {}{}", name, path.display(), state);
+        }
+        let _ = writeln!(
+            out,
+            "# profile: {}",
+            self.profile.as_deref().unwrap_or("(未指定)")
+        );
+        for key in CONFIG_KEYS {
+            #[cfg(not(feature = "fastlink-regex"))]
+            if key.starts_with("re_") {
+                continue;
+            }
+            match self.values.iter().find(|(k, _, _)| k == key) {
+                Some((_, value, source)) => {
+                    let _ = writeln!(out, "{} = {}  # {}", key, value, source);
+                }
+                None => {
+                    let _ = writeln!(out, "{} = {}  # 内置默认值", key, builtin_value(key));
+                }
+            }
+        }
+        out
+    }
+}
+
+fn read_config_file(path: &Path) -> MyResult<ConfigFile> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        MyError::new(
+            ErrorCode::IoError,
+            format!("读取配置文件{}失败: {}", path.display(), e),
+        )
+    })?;
+    let file: ConfigFile = toml::from_str(&text).map_err(|e| {
+        MyError::new(
+            ErrorCode::InvalidInput,
+            format!("配置文件{}格式错误: {}", path.display(), e),
+        )
+    })?;
+
+    let tables = std::iter::once(("defaults".to_string(), &file.defaults)).chain(
+        file.profiles
+            .iter()
+            .map(|(name, table)| (format!("profiles.{}", name), table)),
+    );
+    for (section, table) in tables {
+        for (key, value) in table {
+            apply_value(LinkTaskArgsBuilder::default(), key, value).map_err(|e| {
+                MyError::new(
+                    e.code,
+                    format!("配置文件{} [{}]: {}", path.display(), section, e.msg),
+                )
+            })?;
+        }
+    }
+    Ok(file)
+}
+
+/// 将一个配置值设置到builder，键不存在或值的类型错误时返回错误
+fn apply_value(
+    builder: LinkTaskArgsBuilder,
+    key: &str,
+    value: &toml::Value,
+) -> MyResult<LinkTaskArgsBuilder> {
+    let as_bool = || {
+        value
+            .as_bool()
+            .ok_or_else(|| invalid_value(key, value, "true/false"))
+    };
+    let as_str = || {
+        value
+            .as_str()
+            .ok_or_else(|| invalid_value(key, value, "字符串"))
+    };
+
+    let builder = match key {
+        "make_dir" => builder.make_dir(as_bool()?),
+        "keep_extention" => builder.keep_extention(as_bool()?),
+        "only_file" => builder.only_file(as_bool()?),
+        "only_dir" => builder.only_dir(as_bool()?),
+        "only_managed" => builder.only_managed(as_bool()?),
+        "on_conflict" => builder.conflict(
+            as_str()?
+                .parse::<ConflictPolicy>()
+                .map_err(|e| MyError::new(ErrorCode::InvalidInput, e))?,
+        ),
+        "broken_src" => builder.broken_src(
+            as_str()?
+                .parse::<BrokenSrcPolicy>()
+                .map_err(|e| MyError::new(ErrorCode::InvalidInput, e))?,
+        ),
+        "link_kind" => builder.link_kind(
+            as_str()?
+                .parse::<LinkKind>()
+                .map_err(|e| MyError::new(ErrorCode::InvalidInput, e))?,
+        ),
+        "link_fallback" => builder.link_fallback(
+            as_str()?
+                .parse::<LinkFallback>()
+                .map_err(|e| MyError::new(ErrorCode::InvalidInput, e))?,
+        ),
+        #[cfg(feature = "fastlink-regex")]
+        "re_match" => builder.re_match_target(
+            as_str()?
+                .parse::<ReMatchTarget>()
+                .map_err(|e| MyError::new(ErrorCode::InvalidInput, e))?,
+        ),
+        #[cfg(feature = "fastlink-regex")]
+        "re_ignore_case" => builder.re_ignore_case(as_bool()?),
+        #[cfg(feature = "fastlink-regex")]
+        "re_max_depth" => builder.re_max_depth(
+            value
+                .as_integer()
+                .and_then(|depth| usize::try_from(depth).ok())
+                .ok_or_else(|| invalid_value(key, value, "非负整数"))?,
+        ),
+        #[cfg(feature = "fastlink-regex")]
+        "re_follow_links" => builder.re_follow_links(as_bool()?),
+        #[cfg(feature = "fastlink-regex")]
+        "re_no_check" => builder.re_no_check(as_bool()?),
+        #[cfg(feature = "fastlink-regex")]
+        "re_output_flatten" => builder.re_output_flatten(as_bool()?),
+        #[cfg(feature = "fastlink-regex")]
+        "re_dir_granularity" => builder.re_dir_granularity(
+            as_str()?
+                .parse::<ReDirGranularity>()
+                .map_err(|e| MyError::new(ErrorCode::InvalidInput, e))?,
+        ),
+        #[cfg(feature = "fastlink-regex")]
+        "re_stream" => builder.re_stream(as_bool()?),
+        // 未启用regex功能时，re相关的键被忽略，以便与完整版共用配置文件
+        #[cfg(not(feature = "fastlink-regex"))]
+        _ if key.starts_with("re_") && CONFIG_KEYS.contains(&key) => builder,
+        _ => {
+            return Err(MyError::new(
+                ErrorCode::InvalidInput,
+                format!("未知的配置项 '{}'，可选: {}", key, CONFIG_KEYS.join(", ")),
+            ))
+        }
+    };
+    Ok(builder)
+}
+
+fn invalid_value(key: &str, value: &toml::Value, expected: &str) -> MyError {
+    MyError::new(
+        ErrorCode::InvalidInput,
+        format!("配置项 {} 的值 {} 无效，应为{}", key, value, expected),
+    )
+}
+
+/// 内置默认值，与命令行参数的默认值一致
+fn builtin_value(key: &str) -> toml::Value {
+    match key {
+        "on_conflict" => toml::Value::String(ConflictPolicy::default().to_string()),
+        "broken_src" => toml::Value::String(BrokenSrcPolicy::default().to_string()),
+        "link_kind" => toml::Value::String(LinkKind::default().to_string()),
+        "link_fallback" => toml::Value::String(LinkFallback::default().to_string()),
+        #[cfg(feature = "fastlink-regex")]
+        "re_match" => toml::Value::String(ReMatchTarget::default().to_string()),
+        #[cfg(feature = "fastlink-regex")]
+        "re_max_depth" => toml::Value::Integer(DEFAULT_RE_MAX_DEPTH as i64),
+        #[cfg(feature = "fastlink-regex")]
+        "re_dir_granularity" => toml::Value::String(ReDirGranularity::default().to_string()),
+        _ => toml::Value::Boolean(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_configs(root: &Path) -> Vec<(&'static str, PathBuf)> {
+        let user = root.join("config.toml");
+        fs::write(
+            &user,
+            "[defaults]\nmake_dir = true\nonly_file = true\n\n[profiles.game]\non_conflict = \"overwrite\"\n",
+        )
+        .unwrap();
+        let project = root.join(PROJECT_CONFIG_NAME);
+        fs::write(
+            &project,
+            "[defaults]\nonly_file = false\n\n[profiles.game]\nbroken_src = \"allow\"\n",
+        )
+        .unwrap();
+        vec![
+            ("用户配置", user),
+            ("项目配置", project),
+            ("项目配置", root.join("missing.toml")),
+        ]
+    }
+
+    #[test]
+    fn test_config_precedence() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = Config::load_from(write_configs(tmp.path())).unwrap();
+
+        let args = config.resolve(None).unwrap().builder().build();
+        assert!(args.make_dir);
+        assert!(!args.only_file);
+        assert_eq!(args.link_options.conflict, ConflictPolicy::OverwriteBroken);
+
+        let resolved = config.resolve(Some("game")).unwrap();
+        let args = resolved.builder().build();
+        assert_eq!(args.link_options.conflict, ConflictPolicy::Overwrite);
+        assert_eq!(args.link_options.broken_src, BrokenSrcPolicy::Allow);
+
+        // 命令行参数优先
+        let cli = LinkTaskArgsBuilder::default().make_dir(false);
+        assert!(!cli.merge(resolved.builder()).build().make_dir);
+
+        let shown = resolved.show();
+        assert!(shown.contains("make_dir = true  # 用户配置"));
+        assert!(shown.contains("missing.toml (不存在)"));
+        assert!(shown.contains("only_dir = false  # 内置默认值"));
+
+        assert!(config.resolve(Some("nope")).is_err());
+    }
+
+    #[test]
+    fn test_config_invalid() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("bad.toml");
+        for text in [
+            "[defaults]\nmake_dirs = true\n",
+            "[defaults]\nmake_dir = 1\n",
+            "[profile.a]\nmake_dir = true\n",
+        ] {
+            fs::write(&path, text).unwrap();
+            let e = Config::load_from(vec![("用户配置", path.clone())]).unwrap_err();
+            assert_eq!(e.code, ErrorCode::InvalidInput, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_find_project_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        let sub = tmp.path().join("a/b");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(tmp.path().join(PROJECT_CONFIG_NAME), "").unwrap();
+        assert_eq!(
+            find_project_config(&sub),
+            Some(tmp.path().join(PROJECT_CONFIG_NAME))
+        );
+    }
+}
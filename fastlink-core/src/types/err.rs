@@ -9,6 +9,7 @@ pub enum ErrorCode {
     IoError = 3,
     PermissionDenied = 5,
     DirectoryNotEmpty = 6,
+    Unsupported = 7,
     FailToMakeDir = 100,
     FailAtMakeLink = 101,
     FailToGetPathParent = 102,
@@ -18,10 +19,15 @@ pub enum ErrorCode {
     TargetExistsAndNotLink = 106,
     FailToDelLink = 107,
     SkipExistingLink = 108,
+    VerifyFailed = 109,
 
     DuplicateTarget = 201,
     BrokenSymlink = 202,
     SrcEqDst = 203,
+    EnvVarNotFound = 204,
+    OutsideRoot = 205,
+    PreflightFailed = 206,
+    InsufficientSpace = 207,
 }
 
 impl fmt::Display for ErrorCode {
@@ -40,12 +46,18 @@ impl fmt::Display for ErrorCode {
             ErrorCode::TargetLinkExists => write!(f, "Target Link Already Exists"),
             ErrorCode::SkipExistingLink => write!(f, "SkipExistingLink"),
             ErrorCode::FailToDelLink => write!(f, "Fail To Del Link"),
+            ErrorCode::VerifyFailed => write!(f, "Verify Failed"),
             ErrorCode::TargetExistsAndNotLink => write!(f, "Target Exists And is Not a Link"),
             ErrorCode::DuplicateTarget => write!(f, "Duplicate Target"),
             ErrorCode::BrokenSymlink => write!(f, "Broken Symlink"),
             ErrorCode::SrcEqDst => write!(f, "InvalidInput: <SRC> is Equal to [DST]"),
+            ErrorCode::EnvVarNotFound => write!(f, "Env Var Not Found"),
+            ErrorCode::OutsideRoot => write!(f, "Outside Root"),
+            ErrorCode::PreflightFailed => write!(f, "Preflight Failed"),
+            ErrorCode::InsufficientSpace => write!(f, "Insufficient Space"),
             ErrorCode::PermissionDenied => write!(f, "PermissionDenied"),
             ErrorCode::DirectoryNotEmpty => write!(f, "DirectoryNotEmpty"),
+            ErrorCode::Unsupported => write!(f, "Unsupported"),
         }
     }
 }
@@ -0,0 +1,401 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::types::link_task::{report_preflight, LinkTask};
+use crate::types::link_task_args::{LinkTaskArgs, LinkTaskOpMode};
+use crate::utils::expand::expand_path;
+use crate::utils::func::display_paginated_paths;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+
+/// 批量任务：多个<SRC>链接到同一目录（`ln -t`形式）或来自列表文件的路径对，所有路径对作为一批处理
+///
+/// 创建时先解析、匹配并预检全部路径对，任一路径对无效则不创建任何链接；
+/// 之后统一确认一次，逐个创建并汇总结果
+#[derive(Debug)]
+pub struct LinkBatch {
+    pub tasks: Vec<LinkTask>,
+}
+
+/// 批量任务中的一个路径对，dst为None时按<SRC>名称在当前目录创建
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkPair {
+    pub src: String,
+    pub dst: Option<String>,
+    /// 在列表文件中的行号（从1开始），用于报告错误
+    pub line: Option<usize>,
+}
+
+impl LinkPair {
+    pub fn new(src: impl Into<String>, dst: Option<String>) -> Self {
+        LinkPair {
+            src: src.into(),
+            dst,
+            line: None,
+        }
+    }
+
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    /// 错误信息前缀，如`第3行: `
+    fn prefix(&self) -> String {
+        self.line
+            .map(|line| format!("第{}行: ", line))
+            .unwrap_or_default()
+    }
+}
+
+/// 为目录追加路径分隔符，使其作为带有目录倾向的[DST]
+pub fn dir_dst(dir: &str) -> String {
+    let mut dst = dir.to_string();
+    if !(dst.ends_with('/') || dst.ends_with('\\')) {
+        dst.push(std::path::MAIN_SEPARATOR);
+    }
+    dst
+}
+
+impl LinkBatch {
+    /// 以args为模板，为每个src构建LinkTask
+    ///
+    /// [DST]按带有目录倾向的target_dir处理，即在target_dir下使用<SRC>名称（见`parse_args_dst`）
+    pub fn try_new(args: LinkTaskArgs, srcs: &[String], target_dir: &str) -> MyResult<Self> {
+        let dst = dir_dst(target_dir);
+        let pairs = srcs
+            .iter()
+            .map(|src| LinkPair::new(src.clone(), Some(dst.clone())))
+            .collect();
+        Self::from_pairs(args, pairs)
+    }
+
+    /// 以args为模板，为每个路径对构建LinkTask（见`LinkTask::try_new_plan`）
+    ///
+    /// 任一路径对无效时逐个报告（带行号）并返回Err，不会处理任何路径；
+    /// 构建时不修改文件系统，[DST]父目录在全部路径对通过预检后、创建链接时才创建
+    pub fn from_pairs(args: LinkTaskArgs, pairs: Vec<LinkPair>) -> MyResult<Self> {
+        if pairs.is_empty() {
+            return Err(MyError::new(
+                ErrorCode::InvalidInput,
+                "批量模式至少需要一个<SRC>".into(),
+            ));
+        }
+
+        let mut tasks = Vec::with_capacity(pairs.len());
+        let mut errs = Vec::new();
+        for pair in &pairs {
+            let task_args = LinkTaskArgs {
+                src: pair.src.clone(),
+                dst: pair.dst.clone(),
+                ..args.clone()
+            };
+            match LinkTask::try_new_plan(task_args) {
+                Ok(task) => tasks.push(task),
+                Err(mut e) => {
+                    e.msg = format!("{}{}", pair.prefix(), e.msg);
+                    errs.push(e);
+                }
+            }
+        }
+        if !errs.is_empty() {
+            let count = errs.len();
+            errs.into_iter().for_each(|e| e.log());
+            return Err(MyError::new(
+                ErrorCode::InvalidInput,
+                format!("{}个路径对无效，未处理任何路径", count),
+            ));
+        }
+
+        // 不同<SRC>同名时会得到相同的[DST]
+        let mut seen: HashMap<&PathBuf, (&PathBuf, &LinkPair)> = HashMap::new();
+        for (task, pair) in tasks.iter().zip(&pairs) {
+            if let Some((prev, prev_pair)) = seen.insert(&task.dst_path, (&task.src_path, pair)) {
+                return Err(MyError::new(
+                    ErrorCode::DuplicateTarget,
+                    format!(
+                        "{}{}与{}{}对应同一链接路径 {}",
+                        prev_pair.prefix(),
+                        prev.display(),
+                        pair.prefix(),
+                        task.src_path.display(),
+                        task.dst_path.display()
+                    ),
+                ));
+            }
+        }
+        Ok(LinkBatch { tasks })
+    }
+
+    pub fn work(self) -> MyResult<()> {
+        match self.tasks[0].args.op_mode {
+            LinkTaskOpMode::Make => self.mklinks(),
+            _ => self.work_each(),
+        }
+    }
+
+    /// 批量创建链接，全部路径对只确认一次
+    fn mklinks(mut self) -> MyResult<()> {
+        #[cfg(feature = "fastlink-regex")]
+        self.tasks[0].check_stream_confirm()?;
+        for task in self.tasks.iter_mut() {
+            task.plan()?;
+        }
+        let mut problems = self.tasks[0].capability_problems();
+        for task in &self.tasks {
+            problems.extend(task.preflight_problems());
+        }
+        report_preflight(problems)?;
+        let pairs: Vec<(PathBuf, PathBuf)> = self
+            .tasks
+            .iter()
+            .flat_map(|task| task.planned_pairs())
+            .collect();
+        if !display_paginated_paths(&pairs, 10, no_check(&self.tasks[0].args))? {
+            return Ok(());
+        }
+
+        let (mut created, mut skipped, mut failed) = (0, 0, 0);
+        for task in &self.tasks {
+            match task.mklinks_planned(false) {
+                Ok(true) => created += 1,
+                Ok(false) => skipped += 1,
+                Err(e) => {
+                    e.log();
+                    failed += 1;
+                }
+            }
+        }
+        log::info!(
+            "批量创建完成: 共{}个<SRC>，成功{}，跳过{}，失败{}",
+            self.tasks.len(),
+            created,
+            skipped,
+            failed
+        );
+        if failed > 0 {
+            return Err(MyError::new(
+                ErrorCode::FailAtMakeLink,
+                format!("{}个<SRC>创建链接失败", failed),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 批量检查/删除，逐个处理并汇总失败数
+    fn work_each(self) -> MyResult<()> {
+        let total = self.tasks.len();
+        let failed = self
+            .tasks
+            .into_iter()
+            .filter_map(|task| task.work().err())
+            .inspect(|e| e.log())
+            .count();
+        log::info!("批量处理完成: 共{}个<SRC>，失败{}", total, failed);
+        if failed > 0 {
+            return Err(MyError::new(
+                ErrorCode::Unknown,
+                format!("{}个<SRC>处理失败", failed),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 读取路径对列表：每行为`SRC`或`SRC<TAB>DST`，空行会被忽略；nul为true时以NUL分隔（如`find -print0`）
+///
+/// 格式错误的行逐个报告行号，存在错误时返回Err
+pub fn read_link_pairs(mut reader: impl Read, nul: bool) -> MyResult<Vec<LinkPair>> {
+    let mut data = Vec::new();
+    reader
+        .read_to_end(&mut data)
+        .map_err(|e| MyError::new(ErrorCode::IoError, format!("读取路径列表失败: {}", e)))?;
+
+    let sep = if nul { b'\0' } else { b'\n' };
+    let mut pairs = Vec::new();
+    let mut errs = 0;
+    for (i, record) in data.split(|b| *b == sep).enumerate() {
+        let line = i + 1;
+        let record = match std::str::from_utf8(record) {
+            Ok(record) if nul => record,
+            Ok(record) => record.strip_suffix('\r').unwrap_or(record),
+            Err(_) => {
+                MyError::new(
+                    ErrorCode::InvalidInput,
+                    format!("第{}行: 不是有效的UTF-8", line),
+                )
+                .log();
+                errs += 1;
+                continue;
+            }
+        };
+        if record.trim().is_empty() {
+            continue;
+        }
+        let (src, dst) = match record.split_once('\t') {
+            Some((src, dst)) => (src, Some(dst)),
+            None => (record, None),
+        };
+        if src.trim().is_empty()
+            || dst.is_some_and(|dst| dst.trim().is_empty() || dst.contains('\t'))
+        {
+            MyError::new(
+                ErrorCode::InvalidInput,
+                format!("第{}行: 格式应为SRC或SRC<TAB>DST: {:?}", line, record),
+            )
+            .log();
+            errs += 1;
+            continue;
+        }
+        pairs.push(LinkPair::new(src, dst.map(String::from)).at_line(line));
+    }
+
+    if errs > 0 {
+        return Err(MyError::new(
+            ErrorCode::InvalidInput,
+            format!("路径列表中有{}行格式错误，未处理任何路径", errs),
+        ));
+    }
+    Ok(pairs)
+}
+
+/// 从文件读取路径对列表，source为`-`时读取标准输入，见`read_link_pairs`
+pub fn load_link_pairs(source: &str, nul: bool) -> MyResult<Vec<LinkPair>> {
+    if source == "-" {
+        return read_link_pairs(std::io::stdin().lock(), nul);
+    }
+    let path = expand_path(Path::new(source))?;
+    let file = std::fs::File::open(&path).map_err(|e| {
+        MyError::new(
+            ErrorCode::IoError,
+            format!("无法打开路径列表{}: {}", path.display(), e),
+        )
+    })?;
+    read_link_pairs(file, nul)
+}
+
+/// 是否跳过批量创建前的确认：指定了re_no_check，或标准输入不是终端（如在脚本中运行）
+fn no_check(args: &LinkTaskArgs) -> bool {
+    #[cfg(feature = "fastlink-regex")]
+    if args.re_no_check {
+        return true;
+    }
+    let _ = args;
+    !std::io::stdin().is_terminal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::link_task_args::LinkTaskArgsBuilder;
+    use std::fs;
+
+    #[test]
+    fn test_link_batch_target_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        fs::create_dir(&out).unwrap();
+        let srcs: Vec<String> = ["a.txt", "b.txt"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                fs::write(&path, name).unwrap();
+                path.to_string_lossy().into_owned()
+            })
+            .collect();
+        let args = || LinkTaskArgsBuilder::new(String::new()).build();
+
+        let batch = LinkBatch::try_new(args(), &srcs, out.to_str().unwrap()).unwrap();
+        assert_eq!(batch.tasks[1].dst_path, out.join("b.txt"));
+        batch.work().unwrap();
+        assert_eq!(
+            fs::read_link(out.join("a.txt")).unwrap(),
+            dir.path().join("a.txt")
+        );
+        assert!(out.join("b.txt").exists());
+
+        // 任一<SRC>无效则不处理
+        let missing = vec![srcs[0].clone(), "/no/such/src".to_string()];
+        assert!(LinkBatch::try_new(args(), &missing, out.to_str().unwrap()).is_err());
+        // 同名<SRC>对应同一[DST]
+        let same = vec![srcs[0].clone(), srcs[0].clone()];
+        let e = LinkBatch::try_new(args(), &same, out.to_str().unwrap()).unwrap_err();
+        assert_eq!(e.code, ErrorCode::DuplicateTarget);
+    }
+
+    #[test]
+    fn test_read_link_pairs() {
+        let input = "a.txt\nb dir/b.txt\tout/b\r\n\n  \nc\td\te\n";
+        assert!(read_link_pairs(input.as_bytes(), false).is_err());
+
+        let pairs = read_link_pairs("a.txt\nb dir/b.txt\tout/b\r\n\n".as_bytes(), false).unwrap();
+        assert_eq!(
+            pairs,
+            vec![
+                LinkPair::new("a.txt", None).at_line(1),
+                LinkPair::new("b dir/b.txt", Some("out/b".into())).at_line(2),
+            ]
+        );
+
+        let pairs = read_link_pairs("x\ny\0z\0".as_bytes(), true).unwrap();
+        assert_eq!(pairs[0].src, "x\ny");
+        assert_eq!(pairs[1], LinkPair::new("z", None).at_line(2));
+    }
+
+    #[test]
+    fn test_link_batch_reports_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        fs::write(&src, "a").unwrap();
+        let list = format!(
+            "{}\t{}\n/no/such/src\n",
+            src.display(),
+            dir.path().join("link").display()
+        );
+        let pairs = read_link_pairs(list.as_bytes(), false).unwrap();
+        let args = LinkTaskArgsBuilder::new(String::new()).build();
+        assert!(LinkBatch::from_pairs(args, pairs).is_err());
+        assert!(!dir.path().join("link").exists());
+
+        // 第2行无效时，第1行需要的[DST]父目录也不会被创建
+        let list = format!(
+            "{}	{}
+/no/such/src
+",
+            src.display(),
+            dir.path().join("new/link").display()
+        );
+        let pairs = read_link_pairs(list.as_bytes(), false).unwrap();
+        let args = LinkTaskArgsBuilder::new(String::new())
+            .make_dir(true)
+            .build();
+        assert!(LinkBatch::from_pairs(args, pairs).is_err());
+        assert!(!dir.path().join("new").exists());
+    }
+
+    #[test]
+    fn test_from_pairs_keeps_fs_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        fs::write(&src, "a").unwrap();
+        let out = dir.path().join("new/deep");
+        let pairs = vec![LinkPair::new(
+            src.to_string_lossy(),
+            Some(dir_dst(out.to_str().unwrap())),
+        )];
+        let args = LinkTaskArgsBuilder::new(String::new())
+            .make_dir(true)
+            .build();
+
+        let mut batch = LinkBatch::from_pairs(args, pairs).unwrap();
+        batch.tasks[0].plan().unwrap();
+        assert_eq!(
+            batch.tasks[0].planned_pairs(),
+            vec![(src.clone(), out.join("a.txt"))]
+        );
+        assert!(!dir.path().join("new").exists());
+
+        // 预检通过后创建链接时才创建父目录
+        batch.work().unwrap();
+        assert_eq!(fs::read_link(out.join("a.txt")).unwrap(), src);
+    }
+}
@@ -1,8 +1,21 @@
 use crate::types::err::{ErrorCode, MyError, MyResult};
-use crate::types::link_task_args::{LinkTaskArgs, LinkTaskOpMode};
+#[cfg(feature = "fastlink-regex")]
+use crate::types::link_task_args::ReDirGranularity;
+use crate::types::link_task_args::{LinkKind, LinkOptions, LinkTaskArgs, LinkTaskOpMode};
 use crate::types::link_task_pre::LinkTaskPre;
-use crate::utils::func::mklink_pre_check;
-use crate::utils::link::{del_exists_link, mklink};
+#[cfg(feature = "fastlink-regex")]
+use crate::types::re_stream::{duplicate_targets_error, ReMatch, ReWalker};
+use crate::utils::expand::expand_path;
+use crate::utils::link::{
+    copy_kind_of, del_link_of_with, is_managed_with, mklink_as, probe_symlink_capability,
+    probe_symlink_in, LinkOutcome,
+};
+use crate::utils::path::{
+    inspect_path_with, resolve_link_path_with, resolve_path_with, PathStatus,
+};
+use crate::utils::shortcut::{is_shortcut_path, read_shortcut_with, shortcut_path};
+use crate::utils::vfs::{std_fs, walk, Fs, SharedFs};
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 use std::path::{Path, PathBuf};
 
@@ -15,6 +28,8 @@ pub struct LinkTask {
     pub dst_path: PathBuf,                              // 规范化后的目标目录路径
     pub matched_paths: Option<Vec<(PathBuf, PathBuf)>>, // 匹配的源相对路径和目标相对路径
     pub dirs_to_create: Option<Vec<PathBuf>>,           // 需要创建的目标父目录相对路径
+    pub fs: SharedFs, // 创建/删除/检查链接与Re匹配遍历使用的文件系统
+    pub(crate) preflight_facts: Option<PreflightFacts>, // plan时收集的预检信息，见`plan`
 }
 
 impl LinkTask {
@@ -34,12 +49,18 @@ impl LinkTask {
 
     #[cfg(not(feature = "fastlink-regex"))]
     fn remove_links_with_dst(self) -> MyResult<()> {
-        del_exists_link(&self.dst_path, true, Some(false)).map(self.remove_links_log())
+        del_link_of_with(
+            self.fs.as_ref(),
+            Some(&self.src_path),
+            &self.dst_path,
+            self.args.only_managed,
+        )
+        .map(self.remove_links_log())
     }
 
     #[cfg(feature = "fastlink-regex")]
     fn remove_links_with_dst(mut self) -> MyResult<()> {
-        if self.args.re_pattern.is_some() {
+        if !self.args.re_patterns.is_empty() {
             self.apply_re(None)?;
 
             // 错误数据与跳过的路径
@@ -47,9 +68,9 @@ impl LinkTask {
             let mut skip = Vec::new();
 
             // 删除链接并记录数据
-            for (_src, dst) in self.matched_paths.as_deref().unwrap() {
-                let dst = self.dst_path.join(dst);
-                match del_exists_link(&dst, true, Some(false)) {
+            for (src, dst) in self.matched_paths.as_deref().unwrap() {
+                let (src, dst) = (self.src_path.join(src), self.dst_path.join(dst));
+                match del_link_of_with(self.fs.as_ref(), Some(&src), &dst, self.args.only_managed) {
                     Ok(b) => {
                         if !b {
                             skip.push(dst)
@@ -62,14 +83,26 @@ impl LinkTask {
             self.remove_links_summary_log(skip, errs);
             Ok(())
         } else {
-            del_exists_link(&self.dst_path, true, Some(false)).map(self.remove_links_log())
+            del_link_of_with(
+                self.fs.as_ref(),
+                Some(&self.src_path),
+                &self.dst_path,
+                self.args.only_managed,
+            )
+            .map(self.remove_links_log())
         }
     }
 
     pub fn remove_links(self) -> MyResult<()> {
         // 没有传入dst，使用src，（不用apply re后的）
         if self.args.dst.is_none() {
-            del_exists_link(&self.src_path, true, Some(false)).map(self.remove_links_log())
+            del_link_of_with(
+                self.fs.as_ref(),
+                None,
+                &self.src_path,
+                self.args.only_managed,
+            )
+            .map(self.remove_links_log())
         // 有dst用dst
         } else {
             self.remove_links_with_dst()
@@ -116,50 +149,316 @@ impl LinkTask {
 
     #[cfg(not(feature = "fastlink-regex"))]
     fn check_links_with_dst(self) -> MyResult<()> {
-        check_link(&self.dst_path)
+        self.check_one(Some(&self.src_path), &self.dst_path)
     }
 
     #[cfg(feature = "fastlink-regex")]
     fn check_links_with_dst(mut self) -> MyResult<()> {
-        if self.args.re_pattern.is_some() {
+        if !self.args.re_patterns.is_empty() {
             self.apply_re(None)?;
-            for (_src, dst) in self.matched_paths.unwrap() {
-                check_link(&self.dst_path.join(dst))?;
+            for (src, dst) in self.matched_paths.as_deref().unwrap() {
+                self.check_one(Some(&self.src_path.join(src)), &self.dst_path.join(dst))?;
             }
             Ok(())
         } else {
-            check_link(&self.dst_path)
+            self.check_one(Some(&self.src_path), &self.dst_path)
         }
     }
 
     pub fn check_links(self) -> MyResult<()> {
         // 没有传入dst，使用src
         if self.args.dst.is_none() {
-            check_link(&self.src_path)
+            self.check_one(None, &self.src_path)
         // 有dst用dst
         } else {
             self.check_links_with_dst()
         }
     }
 
-    #[cfg(not(feature = "fastlink-regex"))]
+    /// 检查单个路径，only_managed时跳过不是由fastlink创建的链接
+    fn check_one(&self, src: Option<&Path>, dst: &Path) -> MyResult<()> {
+        let fs = self.fs.as_ref();
+        if self.args.only_managed && !is_managed_with(fs, dst) {
+            log::debug!("不是由fastlink创建的链接，已跳过: {}", dst.display());
+            return Ok(());
+        }
+        match src {
+            Some(src) => check_link_of(fs, src, dst),
+            None => check_link(fs, dst),
+        }
+    }
+
     pub fn mklinks(&mut self) -> Result<bool, MyError> {
-        self._mklink()
+        #[cfg(feature = "fastlink-regex")]
+        self.check_stream_confirm()?;
+        self.plan()?;
+        self.preflight()?;
+        self.mklinks_planned(true)
+    }
+
+    /// 创建链接前的预检（plan之后、任何修改之前），一次性报告所有问题：
+    /// 能否创建符号链接（如Windows下的管理员权限/开发者模式），每个[DST]父目录是否可写，
+    /// 以及以复制创建时目标文件系统的剩余空间是否足够
+    pub fn preflight(&self) -> MyResult<()> {
+        let mut problems = self.capability_problems();
+        problems.extend(self.preflight_problems());
+        report_preflight(problems)
+    }
+
+    /// 能否创建符号链接，只在以符号链接创建且没有回退类型时检查（快捷方式等不需要权限，有回退类型时逐个回退）
+    pub(crate) fn capability_problems(&self) -> Vec<MyError> {
+        let options = &self.args.link_options;
+        if options.kind != LinkKind::Symlink {
+            return Vec::new();
+        }
+        match probe_symlink_capability(self.fs.as_ref()) {
+            Err(e) if !options.fallback.is_empty() => {
+                log::warn!("{}，将依次回退为: {}", e.msg, options.fallback);
+                Vec::new()
+            }
+            res => res.err().into_iter().collect(),
+        }
+    }
+
+    /// 检查每个[DST]的父目录：需要存在（或由make_dir创建），且能在其中（或其最近的已存在上级目录中）创建符号链接；
+    /// 使用`plan`时收集的信息，未经plan时逐个检查计划的路径对
+    pub fn preflight_problems(&self) -> Vec<MyError> {
+        let fs = self.fs.as_ref();
+        let options = &self.args.link_options;
+        let collected;
+        let facts = match &self.preflight_facts {
+            Some(facts) => facts,
+            None => {
+                collected = self.collect_preflight_facts();
+                &collected
+            }
+        };
+
+        let mut problems = Vec::new();
+        let mut probed = BTreeSet::new();
+        for parent in &facts.parents {
+            let Some(existing) = parent.ancestors().find(|dir| fs.exists(dir)) else {
+                continue;
+            };
+            if existing != parent.as_path() && !self.args.make_dir {
+                problems.push(MyError::new(
+                    ErrorCode::ParentNotExist,
+                    format!("{}，可使用--make-dir自动创建", parent.display()),
+                ));
+                continue;
+            }
+            if options.kind == LinkKind::Symlink
+                && options.fallback.is_empty()
+                && probed.insert(existing.to_path_buf())
+            {
+                problems.extend(probe_symlink_in(fs, existing).err());
+            }
+        }
+        problems.extend(self.space_problems(&facts.copy_sizes));
+        problems
     }
 
+    /// 检查复制需要的空间：按目标文件系统汇总后与其剩余空间比较。
+    /// 以复制创建时空间不足是预检问题；复制只是回退类型时不一定发生，只给出警告
+    fn space_problems(&self, copy_sizes: &BTreeMap<PathBuf, u64>) -> Vec<MyError> {
+        let fs = self.fs.as_ref();
+        // 设备号 -> (用于查询剩余空间的已存在目录, 需要的字节数)
+        let mut needed: BTreeMap<u64, (PathBuf, u64)> = BTreeMap::new();
+        for (parent, &size) in copy_sizes.iter().filter(|(_, size)| **size > 0) {
+            let Some(existing) = parent.ancestors().find(|dir| fs.exists(dir)) else {
+                continue;
+            };
+            let Ok(id) = fs.file_id(existing) else {
+                continue;
+            };
+            needed
+                .entry(id.dev)
+                .or_insert_with(|| (existing.to_path_buf(), 0))
+                .1 += size;
+        }
+
+        let mut problems = Vec::new();
+        for (dir, size) in needed.into_values() {
+            let available = match fs.available_space(&dir) {
+                Ok(available) => available,
+                Err(e) => {
+                    log::debug!("无法获取{}的剩余空间，跳过检查: {}", dir.display(), e);
+                    continue;
+                }
+            };
+            if size <= available {
+                continue;
+            }
+            let msg = format!(
+                "复制需要{}字节，{}所在的文件系统只剩{}字节",
+                size,
+                dir.display(),
+                available
+            );
+            if self.args.link_options.kind == LinkKind::Copy {
+                problems.push(MyError::new(ErrorCode::InsufficientSpace, msg));
+            } else {
+                log::warn!("{}，回退为复制时可能失败", msg);
+            }
+        }
+        problems
+    }
+
+    /// 创建链接前的准备：应用re匹配（若有）并检查within，之后可由planned_pairs得到将创建的路径对
+    ///
+    /// 检查within时顺带收集预检需要的信息，预检不再遍历一次路径对
+    pub fn plan(&mut self) -> MyResult<()> {
+        #[cfg(feature = "fastlink-regex")]
+        if !self.args.re_patterns.is_empty() && !self.args.re_stream {
+            self.apply_re(None)?;
+        }
+        let root = self.within_root()?;
+        if self.is_streaming() {
+            if let Some(root) = &root {
+                self.check_pair_within(root, &self.src_path, &self.dst_path)?;
+            }
+            self.preflight_facts = Some(self.stream_preflight_facts());
+            return Ok(());
+        }
+        let mut facts = PreflightFacts::default();
+        for (src, dst) in self.planned_pairs() {
+            if let Some(root) = &root {
+                self.check_pair_within(root, &src, &dst)?;
+            }
+            facts.add(self.fs.as_ref(), &self.args.link_options, &src, &dst);
+        }
+        if let Some(root) = &root {
+            self.check_dirs_within(root)?;
+        }
+        self.preflight_facts = Some(facts);
+        Ok(())
+    }
+
+    /// 是否流式Re匹配，见`plan_stream`
     #[cfg(feature = "fastlink-regex")]
-    pub fn mklinks(&mut self) -> Result<bool, MyError> {
-        match &self.args.re_pattern {
-            None => self._mklink(),
-            Some(_) => {
-                self.apply_re(None)?;
-                self._mklinks_re()
+    fn is_streaming(&self) -> bool {
+        !self.args.re_patterns.is_empty() && self.args.re_stream
+    }
+
+    #[cfg(not(feature = "fastlink-regex"))]
+    fn is_streaming(&self) -> bool {
+        false
+    }
+
+    /// 遍历计划的路径对收集预检信息，流式Re匹配时见`stream_preflight_facts`
+    fn collect_preflight_facts(&self) -> PreflightFacts {
+        if self.is_streaming() {
+            return self.stream_preflight_facts();
+        }
+        let mut facts = PreflightFacts::default();
+        for (src, dst) in self.planned_pairs() {
+            facts.add(self.fs.as_ref(), &self.args.link_options, &src, &dst);
+        }
+        facts
+    }
+
+    /// 流式Re匹配时预检不遍历<SRC>：不创建目录时Re的最大深度为1，产出的[DST]都直接位于[DST]下，
+    /// 创建目录时其余父目录在创建链接前按需创建，因此只检查[DST]自身；复制需要的空间无法预先统计
+    fn stream_preflight_facts(&self) -> PreflightFacts {
+        PreflightFacts {
+            parents: BTreeSet::from([self.dst_path.clone()]),
+            copy_sizes: BTreeMap::new(),
+        }
+    }
+
+    /// 按plan的结果创建链接，confirm为false时不再进行Re匹配后的分页检查（已由调用者确认）
+    pub fn mklinks_planned(&self, confirm: bool) -> Result<bool, MyError> {
+        #[cfg(feature = "fastlink-regex")]
+        if !self.args.re_patterns.is_empty() {
+            if self.args.re_stream {
+                return self._mklinks_re_stream();
+            }
+            return self._mklinks_re(confirm);
+        }
+        let _ = confirm;
+        self._mklink()
+    }
+
+    /// 将创建的(<SRC>, [DST])绝对路径对，Re匹配时为所有匹配的路径对
+    pub fn planned_pairs(&self) -> Vec<(PathBuf, PathBuf)> {
+        match self.matched_paths.as_ref() {
+            Some(paths) => paths
+                .iter()
+                .map(|(src, dst)| (self.src_path.join(src), self.dst_path.join(dst)))
+                .collect(),
+            None => vec![(self.src_path.clone(), self.dst_path.clone())],
+        }
+    }
+
+    /// 逐个产出将创建的路径对：流式Re匹配（re_stream）时见`plan_stream`，否则同`planned_pairs`
+    pub fn iter_planned_pairs(
+        &self,
+    ) -> MyResult<Box<dyn Iterator<Item = (PathBuf, PathBuf)> + '_>> {
+        #[cfg(feature = "fastlink-regex")]
+        if !self.args.re_patterns.is_empty() && self.args.re_stream {
+            return Ok(Box::new(self.plan_stream()?));
+        }
+        Ok(Box::new(self.planned_pairs().into_iter()))
+    }
+
+    /// 指定了within时，检查所有计划创建的链接路径、链接目标与需要创建的目录都位于根目录内
+    ///
+    /// 路径逐段解析`..`与已存在的符号链接（链接路径自身除外）后再比较，
+    /// 避免通过`..`或指向外部的父目录符号链接逃逸；`plan`在收集预检信息的同一遍中完成同样的检查
+    pub fn check_within(&self) -> MyResult<()> {
+        let Some(root) = self.within_root()? else {
+            return Ok(());
+        };
+        for (src, dst) in self.planned_pairs() {
+            self.check_pair_within(&root, &src, &dst)?;
+        }
+        self.check_dirs_within(&root)
+    }
+
+    /// Re匹配且make_dir时，检查[DST]与需要创建的目录都位于已解析的根目录root内
+    fn check_dirs_within(&self, root: &Path) -> MyResult<()> {
+        if self.matched_paths.is_none() || !self.args.make_dir {
+            return Ok(());
+        }
+        let dirs = self.dirs_to_create.iter().flatten();
+        for dir in
+            std::iter::once(self.dst_path.clone()).chain(dirs.map(|dir| self.dst_path.join(dir)))
+        {
+            let resolved = resolve_path_with(self.fs.as_ref(), &dir);
+            if !resolved.starts_with(root) {
+                return Err(outside_root("目录", &dir, &resolved, root));
             }
         }
+        Ok(())
+    }
+
+    /// 解析后的within根目录，未指定时为None
+    pub(crate) fn within_root(&self) -> MyResult<Option<PathBuf>> {
+        match self.args.within.as_deref() {
+            Some(root) => Ok(Some(resolve_path_with(
+                self.fs.as_ref(),
+                &expand_path(root)?,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// 检查一个路径对的链接路径与链接目标都位于已解析的根目录root内
+    pub(crate) fn check_pair_within(&self, root: &Path, src: &Path, dst: &Path) -> MyResult<()> {
+        let fs = self.fs.as_ref();
+        let resolved = resolve_link_path_with(fs, dst);
+        if !resolved.starts_with(root) {
+            return Err(outside_root("链接路径", dst, &resolved, root));
+        }
+        let resolved = resolve_path_with(fs, src);
+        if !resolved.starts_with(root) {
+            return Err(outside_root("链接目标", src, &resolved, root));
+        }
+        Ok(())
     }
 
     #[cfg(feature = "fastlink-regex")]
-    fn _mklinks_re(&self) -> Result<bool, MyError> {
+    fn _mklinks_re(&self, confirm: bool) -> Result<bool, MyError> {
         if self
             .matched_paths
             .as_ref()
@@ -171,13 +470,13 @@ impl LinkTask {
 
         if let Some(paths) = self.matched_paths.as_ref() {
             // src输入是文件时
-            if self.src_path.is_file() {
+            if is_file(self.fs.as_ref(), &self.src_path) {
                 log::info!(
                     "\n\tsrc: {}\n\tdst: {}",
                     self.src_path.display(),
                     self.dst_path.display()
                 );
-            } else {
+            } else if confirm {
                 // Re匹配后、创建连接前的检查：按页展示需要建立符号链接的路径对
                 // 返回Ok(false)则取消创建
                 if !crate::utils::func::display_paginated_paths(paths, 10, self.args.re_no_check)? {
@@ -189,12 +488,16 @@ impl LinkTask {
             let mut create_dir_cnt: usize = 0;
             if self.args.make_dir {
                 if let Some(dirs) = self.dirs_to_create.as_ref() {
-                    for dir in dirs.iter().filter(|dir| !dir.exists()) {
+                    // [DST]自身也可能不存在（如展平模式或shallowest粒度）
+                    let full_dirs = std::iter::once(self.dst_path.clone())
+                        .chain(dirs.iter().map(|dir| self.dst_path.join(dir)));
+                    for full_dir in full_dirs.filter(|dir| !self.fs.exists(dir)) {
                         if create_dir_cnt == 0 {
                             log::info!("创建符号链接需要目录中");
                         }
-                        let full_dir = self.dst_path.join(dir);
-                        crate::utils::fs::mkdirs(&full_dir)?;
+                        self.fs.create_dir_all(&full_dir).map_err(|e| {
+                            MyError::new(ErrorCode::FailToMakeDir, format!("{}", e))
+                        })?;
                         create_dir_cnt += 1;
                         log::info!("已创建目录: {}", full_dir.display());
                     }
@@ -207,6 +510,7 @@ impl LinkTask {
             }
 
             log::info!("开始创建符号链接");
+            let mut tally = KindTally::default();
             for (i, (src, dst)) in paths.iter().enumerate() {
                 let src = &self.src_path.join(src);
                 let dst = &self.dst_path.join(dst);
@@ -217,17 +521,15 @@ impl LinkTask {
                     src.display(),
                     dst.display()
                 );
-                mklink(
+                tally.add(mklink_as(
+                    self.fs.as_ref(),
                     src,
                     dst,
-                    Some(self.args.overwrite_links),
-                    Some(self.args.overwrite_broken_link),
-                    Some(self.args.skip_exist_links),
-                    Some(self.args.skip_broken_src_links),
-                    Some(self.args.allow_broken_src),
-                )?;
+                    self.args.link_options,
+                )?);
             }
             log::info!("符号链接创建完成！");
+            tally.log(self.args.link_options.kind);
 
             Ok(true)
         } else {
@@ -239,35 +541,50 @@ impl LinkTask {
     }
 
     fn _mklink(&self) -> Result<bool, MyError> {
-        if self.args.only_dir && self.src_path.is_file() {
+        if self.args.only_dir && is_file(self.fs.as_ref(), &self.src_path) {
             log::warn!("only_dir: {} is FILE", &self.src_path.display());
             Ok(false)
-        } else if self.args.only_file && self.src_path.is_dir() {
+        } else if self.args.only_file && is_dir(self.fs.as_ref(), &self.src_path) {
             log::warn!("only_file: {} is DIR", &self.src_path.display());
             Ok(false)
         } else {
+            self.make_dst_parent()?;
             log::debug!(
                 "符号链接创建中\n\tsrc: {}\n\tdst: {}",
                 &self.src_path.display(),
                 &self.dst_path.display()
             );
-            mklink(
+            let outcome = mklink_as(
+                self.fs.as_ref(),
                 &self.src_path,
                 &self.dst_path,
-                Some(self.args.overwrite_links),
-                Some(self.args.overwrite_broken_link),
-                Some(self.args.skip_exist_links),
-                Some(self.args.skip_broken_src_links),
-                Some(self.args.allow_broken_src),
-            )
-            .inspect(|b| {
-                if *b {
-                    log::info!("符号链接创建成功");
-                } else {
-                    log::info!("已跳过创建符号链接");
-                }
-            })
+                self.args.link_options,
+            )?;
+            match outcome {
+                LinkOutcome::Created(kind) => log::info!("{}创建成功", kind.label()),
+                LinkOutcome::Exists(kind) => log::info!("{}已存在，无需创建", kind.label()),
+                LinkOutcome::Skipped => log::info!("已跳过创建符号链接"),
+            }
+            Ok(outcome != LinkOutcome::Skipped)
+        }
+    }
+
+    /// make_dir时创建不存在的[DST]父目录，在plan（检查within）与预检之后调用
+    fn make_dst_parent(&self) -> MyResult<()> {
+        let Some(parent) = self.dst_path.parent() else {
+            return Ok(());
+        };
+        if !self.args.make_dir || self.fs.exists(parent) {
+            return Ok(());
         }
+        self.fs.create_dir_all(parent).map_err(|e| {
+            MyError::new(
+                ErrorCode::FailToMakeDir,
+                format!("[DST]父目录: {} 创建失败: {}", parent.display(), e),
+            )
+        })?;
+        log::info!("[DST]父目录不存在，已创建: {}", parent.display());
+        Ok(())
     }
 
     #[cfg(feature = "fastlink-regex")]
@@ -288,132 +605,96 @@ impl LinkTask {
 
     #[cfg(feature = "fastlink-regex")]
     /// 应用re检查，更新 matched_paths 和 dirs_to_create
-    /// 使用了参数only_file， only_dir， re_output_flatten
+    /// 使用了参数only_file， only_dir， re_output_flatten， re_dir_granularity， re_meta_filter
     fn _apply_re(&mut self) -> Result<(), MyError> {
-        // todo 优化regex Option检查
-        // ---------------------------
-        if self.args.re_pattern.is_none() {
+        if self.args.re_patterns.is_empty() {
             return Ok(());
         }
-        let re = self.args.re_pattern.as_ref().unwrap();
-        // ---------------------------
-        log::info!("Re: {}", re);
+        let re = build_re_set(&self.args.re_patterns, self.args.re_ignore_case)?;
+        log::info!(
+            "Re: {} (匹配目标: {}{})",
+            re.patterns().join(" | "),
+            self.args.re_match_target,
+            if self.args.re_ignore_case {
+                ", 忽略大小写"
+            } else {
+                ""
+            }
+        );
+
+        let max_depth = get_re_max_depth(self.args.make_dir, self.args.re_max_depth);
+        let granularity = self.args.re_dir_granularity;
+
+        // 遍历与匹配见ReWalker，re_max_depth、re_follow_links、元数据过滤等参数于此使用
+        // 直接兼容src_path是单文件或目录
+        let items: Vec<ReMatch> = ReWalker::new(self, re, max_depth).collect();
+        // deepest粒度下只为最深层的匹配目录创建符号链接，较浅层的匹配目录作为普通目录创建
+        let deepest = match granularity {
+            ReDirGranularity::Deepest => items
+                .iter()
+                .filter(|item| item.is_dir)
+                .map(|item| item.depth)
+                .max(),
+            _ => None,
+        };
 
         let mut matched_paths: Vec<(PathBuf, PathBuf)> = Vec::new();
         let mut dirs_to_create: std::collections::HashSet<PathBuf> =
             std::collections::HashSet::new();
         let mut target_paths: std::collections::HashMap<PathBuf, Vec<PathBuf>> =
             std::collections::HashMap::new();
-        let mut matched_paths_dir: Vec<(PathBuf, PathBuf)> = Vec::new();
-        let mut max_observed_depth: usize = 0;
-
-        let max_depth = get_re_max_depth(self.args.make_dir, self.args.re_max_depth);
-        // 构建路径遍历器，re_max_depth、re_follow_links相关参数于此使用
-        // 直接兼容src_path是单文件或目录
-        let walker = walkdir::WalkDir::new(&self.src_path)
-            .max_depth(max_depth)
-            .follow_links(self.args.re_follow_links);
-
-        for entry in walker.into_iter().filter_map(|e| e.ok()) {
-            let path = entry.path();
-            let depth = entry.depth();
-
-            let is_file = entry.file_type().is_file();
-            let is_dir = entry.file_type().is_dir();
-
-            // 处理 only_file 和 only_dir 参数
-            if (self.args.only_file && !is_file) || (self.args.only_dir && !is_dir) {
+        // 上一个创建符号链接的目录，其子树中的匹配项已包含在目录链接中（与plan_stream一致）
+        let mut linked_dir: Option<PathBuf> = None;
+        for item in items {
+            // 使用相对路径节省内存空间，使用时再拼接
+            let ReMatch {
+                path,
+                relative: relative_path,
+                target: target_path,
+                depth,
+                is_dir,
+            } = item;
+            if linked_dir
+                .as_ref()
+                .is_some_and(|dir| relative_path.starts_with(dir))
+            {
                 continue;
             }
-
-            let path_str = path.to_string_lossy();
-            if re.is_match(&path_str) {
-                if let Ok(relative_path) = path.strip_prefix(&self.src_path) {
-                    // 使用相对路径节省内存空间，使用时再拼接
-                    let target_path = if self.args.re_output_flatten {
-                        // 展平模式：仅使用文件名
-                        if let Some(file_name) = path.file_name() {
-                            PathBuf::from(file_name)
-                        } else {
-                            log::warn!("无法解析文件名称，已跳过: {}", path.display());
-                            continue;
-                        }
-                    } else {
-                        // 镜像模式：保留相对路径
-                        relative_path.to_path_buf()
-                    };
-                    // dst完全路径
-                    let full_dst = self.dst_path.join(&target_path);
-
-                    if is_file {
-                        // 添加创建文件夹
-                        if let Some(parent) = target_path.parent() {
-                            dirs_to_create.insert(parent.to_path_buf());
-                        }
-
-                        // 文件直接加入 matched_paths
-                        matched_paths.push((relative_path.to_path_buf(), target_path.clone()));
-                        // 收集目标路径以检查重复
-                        target_paths
-                            .entry(full_dst.clone())
-                            .or_default()
-                            .push(path.to_path_buf());
-                    } else if is_dir {
-                        // 发现更深的层级，清空之前的符号链接记录
-                        if depth > max_observed_depth {
-                            // log::debug!("matched_paths_dir clear before {:?}", matched_paths_dir);
-                            // 发现更深的层级，将之前的 matched_paths_dir 转移到 dirs_to_create
-                            for (_, target) in &matched_paths_dir {
-                                dirs_to_create.insert(target.clone());
-                            }
-                            // log::debug!("dirs_to_create clear after {:?}", dirs_to_create);
-                            // 清空 matched_paths_dir 并更新 max_observed_depth
-                            matched_paths_dir.clear();
-                            max_observed_depth = depth;
-                        }
-
-                        // 当前层级是最深层，为目录创建符号链接
-                        if depth == max_observed_depth {
-                            matched_paths_dir
-                                .push((relative_path.to_path_buf(), target_path.clone()));
-                            target_paths
-                                .entry(full_dst.clone())
-                                .or_default()
-                                .push(path.to_path_buf());
-                        }
-                    }
-                }
+            if is_dir && deepest.is_some_and(|deepest| depth != deepest) {
+                dirs_to_create.insert(target_path);
+                continue;
             }
+            if is_dir {
+                linked_dir = Some(relative_path.clone());
+            }
+            // 添加创建文件夹
+            if let Some(parent) = target_path.parent() {
+                dirs_to_create.insert(parent.to_path_buf());
+            }
+            // 收集目标路径以检查重复
+            target_paths
+                .entry(self.dst_path.join(&target_path))
+                .or_default()
+                .push(path);
+            matched_paths.push((relative_path, target_path));
         }
 
         dirs_to_create.remove(&PathBuf::new());
 
-        // 将最深层目录的符号链接添加到 matched_paths
-        matched_paths.extend(matched_paths_dir);
         // 处理展平模式下的重复目标路径
         if self.args.re_output_flatten {
-            let duplicates: Vec<_> = target_paths
+            let mut duplicates: Vec<_> = target_paths
                 .iter()
                 .filter(|(_, paths)| paths.len() > 1)
                 .collect();
             if !duplicates.is_empty() {
-                let mut error_msg = String::from("检测到重复目标路径，无法创建链接：\n");
-                for (target_path, src_paths) in duplicates {
-                    error_msg.push_str(&format!(
-                        "目标路径 '{}' 对应以下源路径：\n{}\n",
-                        target_path.display(),
-                        src_paths
-                            .iter()
-                            .map(|p| format!("  - {}", p.display()))
-                            .collect::<Vec<_>>()
-                            .join("\n")
-                    ));
-                }
-                return Err(MyError::new(ErrorCode::DuplicateTarget, error_msg));
+                duplicates.sort();
+                return Err(duplicate_targets_error(duplicates));
             }
         }
 
         self.matched_paths = Some(matched_paths);
+        self.preflight_facts = None;
         self.dirs_to_create = Some(dirs_to_create.into_iter().collect());
         Ok(())
     }
@@ -445,6 +726,8 @@ impl TryFrom<LinkTaskPre> for LinkTask {
             dst_path: task_pre.dst_path.unwrap(),
             matched_paths: None,
             dirs_to_create: None,
+            fs: std_fs(),
+            preflight_facts: None,
         })
     }
 }
@@ -456,43 +739,216 @@ impl LinkTask {
         let task = LinkTask::try_from(task_pre)?;
         Ok(task)
     }
+
+    /// 见`LinkTaskPre::try_new_plan`，得到的任务只用于`plan`与`planned_pairs`
+    pub fn try_new_plan(args: LinkTaskArgs) -> MyResult<Self> {
+        LinkTask::try_from(LinkTaskPre::try_new_plan(args)?)
+    }
+
+    /// 替换创建/删除/检查链接使用的文件系统，如使用MemFs预览或测试
+    pub fn with_fs(mut self, fs: SharedFs) -> Self {
+        self.fs = fs;
+        self
+    }
 }
 
-fn check_link(src: &Path) -> MyResult<()> {
-    match mklink_pre_check(src) {
-        Ok(_) => (),
-        Err(e) if e.code == ErrorCode::TargetExistsAndNotLink => {
-            let filetype = if src.is_dir() {
-                "DIR "
-            } else if src.is_file() {
-                "FILE"
-            } else {
-                "UNKOWN"
-            };
-            log::info!("{:7} {}", filetype, src.display(),);
+fn outside_root(kind: &str, path: &Path, resolved: &Path, root: &Path) -> MyError {
+    MyError::new(
+        ErrorCode::OutsideRoot,
+        format!(
+            "{}{}解析为{}，不在限定的根目录{}内",
+            kind,
+            path.display(),
+            resolved.display(),
+            root.display()
+        ),
+    )
+}
+
+/// plan时收集的预检信息，见[`LinkTask::preflight_problems`]
+#[derive(Debug, Default)]
+pub(crate) struct PreflightFacts {
+    /// 所有[DST]的父目录
+    parents: BTreeSet<PathBuf>,
+    /// 可能复制的字节数，按[DST]父目录汇总；[DST]已存在（已完成或按冲突策略处理）的不计
+    copy_sizes: BTreeMap<PathBuf, u64>,
+}
+
+impl PreflightFacts {
+    fn add(&mut self, fs: &dyn Fs, options: &LinkOptions, src: &Path, dst: &Path) {
+        let Some(parent) = dst.parent() else {
+            return;
+        };
+        let may_copy =
+            options.kind == LinkKind::Copy || options.fallback.iter().any(|k| k == LinkKind::Copy);
+        if may_copy && fs.symlink_metadata(dst).is_err() {
+            *self.copy_sizes.entry(parent.to_path_buf()).or_default() += copy_size(fs, src);
         }
-        Err(e) if e.code == ErrorCode::BrokenSymlink => {
+        if !self.parents.contains(parent) {
+            self.parents.insert(parent.to_path_buf());
+        }
+    }
+}
+
+/// 复制src需要的字节数：目录递归统计其中的文件（符号链接按链接本身复制，不计）
+fn copy_size(fs: &dyn Fs, src: &Path) -> u64 {
+    walk(fs, src)
+        .filter_map(Result::ok)
+        .filter(|entry| entry.metadata.is_file() && !entry.path_is_symlink)
+        .map(|entry| entry.metadata.len)
+        .sum()
+}
+
+/// 输出预检发现的所有问题，存在问题时返回Err
+pub fn report_preflight(problems: Vec<MyError>) -> MyResult<()> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let count = problems.len();
+    problems.into_iter().for_each(|e| e.log());
+    Err(MyError::new(
+        ErrorCode::PreflightFailed,
+        format!("预检发现{}个问题，未做任何修改", count),
+    ))
+}
+
+fn check_link(fs: &dyn Fs, src: &Path) -> MyResult<()> {
+    match inspect_path_with(fs, src) {
+        PathStatus::File if is_shortcut_path(src) => check_shortcut(fs, src),
+        PathStatus::File => match fs.metadata(src).ok().and_then(|m| m.nlink) {
+            Some(n) if n > 1 => log::info!("HardLink {} (共{}个硬链接)", src.display(), n),
+            _ => log::info!("{:7} {}", "FILE", src.display()),
+        },
+        PathStatus::Dir => log::info!("{:7} {}", "DIR ", src.display()),
+        PathStatus::BrokenSymlink { .. } => {
             log::warn!("SymLink(损坏) {}", src.display())
         }
-        Err(e) if e.code == ErrorCode::FileNotExist => {
+        // 以--link-kind shortcut创建时[DST]会被追加.lnk扩展名
+        PathStatus::Missing if fs.exists(&shortcut_path(src)) => {
+            check_shortcut(fs, &shortcut_path(src))
+        }
+        PathStatus::Missing => {
             log::warn!("不存在 {}", src.display())
         }
-        Err(e) if e.code == ErrorCode::TargetLinkExists => {
-            let target = std::fs::read_link(src);
-            match target {
-                Ok(dst) => log::info!("SymLink {:7} 指向 {}", src.display(), dst.display()),
-                Err(e) => log::error!("SymLink {} 指向未知，获取时出错：{}", src.display(), e),
-            };
+        PathStatus::Symlink { target, .. } => {
+            log::info!("SymLink {:7} 指向 {}", src.display(), target.display())
+        }
+        PathStatus::Inaccessible(e) => {
+            log::warn!("错误：检查 {} 时发生未知错误: {}", src.display(), e)
         }
-        Err(e) => log::warn!("错误：检查 {} 时发生未知错误: {}", src.display(), e),
     };
     Ok(())
 }
 
+/// 已知<SRC>时检查[DST]：文件/目录是<SRC>的硬链接或副本时输出其类型，其他情况同`check_link`
+fn check_link_of(fs: &dyn Fs, src: &Path, dst: &Path) -> MyResult<()> {
+    if let PathStatus::File | PathStatus::Dir = inspect_path_with(fs, dst) {
+        match copy_kind_of(fs, src, dst) {
+            Some(LinkKind::Hardlink) => {
+                log::info!("HardLink {} 与 {} 为同一文件", dst.display(), src.display());
+                return Ok(());
+            }
+            Some(_) => {
+                log::info!("Copy    {} 与 {} 内容一致", dst.display(), src.display());
+                return Ok(());
+            }
+            None => (),
+        }
+    }
+    check_link(fs, dst)
+}
+
+/// 统计新创建的各类型链接、已存在与跳过的数量，出现回退时输出各类型的数量
+#[cfg(feature = "fastlink-regex")]
+#[derive(Default)]
+pub(crate) struct KindTally {
+    created: BTreeMap<LinkKind, usize>,
+    /// 已存在且指向<SRC>，无需创建
+    existing: usize,
+    skipped: usize,
+}
+
+#[cfg(feature = "fastlink-regex")]
+impl KindTally {
+    pub fn add(&mut self, outcome: LinkOutcome) {
+        match outcome {
+            LinkOutcome::Created(kind) => *self.created.entry(kind).or_default() += 1,
+            LinkOutcome::Exists(_) => self.existing += 1,
+            LinkOutcome::Skipped => self.skipped += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.created.values().sum::<usize>() + self.existing + self.skipped
+    }
+
+    pub fn log(&self, primary: LinkKind) {
+        log::info!(
+            "成功创建{}，已存在{}，跳过{}",
+            self.created.values().sum::<usize>(),
+            self.existing,
+            self.skipped
+        );
+        if self.created.keys().any(|kind| *kind != primary) {
+            let counts: Vec<String> = self
+                .created
+                .iter()
+                .map(|(kind, n)| format!("{}{}", kind.label(), n))
+                .collect();
+            log::warn!("部分链接已回退为其他类型: {}", counts.join("，"));
+        }
+    }
+}
+
+/// 输出快捷方式的目标，无法解析时按普通文件输出
+fn check_shortcut(fs: &dyn Fs, path: &Path) {
+    match read_shortcut_with(fs, path) {
+        Ok(shortcut) => {
+            let broken = if fs.exists(Path::new(&shortcut.target)) {
+                ""
+            } else {
+                "(目标不存在)"
+            };
+            log::info!(
+                "Shortcut{} {} 指向 {}",
+                broken,
+                path.display(),
+                shortcut.target
+            )
+        }
+        Err(e) => {
+            log::info!("{:7} {}", "FILE", path.display());
+            log::debug!("无法解析为快捷方式: {}", e);
+        }
+    }
+}
+
+/// 跟随符号链接后是否为文件
+fn is_file(fs: &dyn Fs, path: &Path) -> bool {
+    fs.metadata(path).is_ok_and(|m| m.is_file())
+}
+
+/// 跟随符号链接后是否为目录
+fn is_dir(fs: &dyn Fs, path: &Path) -> bool {
+    fs.metadata(path).is_ok_and(|m| m.is_dir())
+}
+
+/// 将多个正则表达式组合为一个RegexSet，任一模式匹配即视为匹配
+#[cfg(feature = "fastlink-regex")]
+pub(crate) fn build_re_set(
+    patterns: &[regex::Regex],
+    ignore_case: bool,
+) -> MyResult<regex::RegexSet> {
+    regex::RegexSetBuilder::new(patterns.iter().map(|re| re.as_str()))
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| MyError::new(ErrorCode::InvalidInput, format!("无效的正则表达式: {}", e)))
+}
+
 // todo: 尽可能早完成，不放到task内
 /// 根据make-dir参数、默认depth以及传入depth获取应有的depth
 #[cfg(feature = "fastlink-regex")]
-fn get_re_max_depth(make_dir: bool, re_max_depth: usize) -> usize {
+pub(crate) fn get_re_max_depth(make_dir: bool, re_max_depth: usize) -> usize {
     if make_dir {
         re_max_depth
     } else {
@@ -503,3 +959,771 @@ fn get_re_max_depth(make_dir: bool, re_max_depth: usize) -> usize {
         1
     }
 }
+
+#[cfg(all(test, feature = "fastlink-regex"))]
+mod tests {
+    use super::*;
+    use crate::types::link_task_args::{LinkTaskArgsBuilder, ReMatchTarget};
+    use crate::types::meta_filter::MetaFilter;
+    use std::collections::HashSet;
+    use std::fs;
+    use tempfile::tempdir;
+
+    // 测试目录树：
+    // src/
+    //   a/
+    //     x.txt
+    //     b/
+    //       y.txt
+    //   c/
+    //     z.txt
+    fn make_tree(root: &Path) -> PathBuf {
+        let src = root.join("src");
+        fs::create_dir_all(src.join("a").join("b")).unwrap();
+        fs::create_dir_all(src.join("c")).unwrap();
+        fs::write(src.join("a").join("x.txt"), b"x").unwrap();
+        fs::write(src.join("a").join("b").join("y.txt"), b"y").unwrap();
+        fs::write(src.join("c").join("z.txt"), b"z").unwrap();
+        src
+    }
+
+    /// 对测试目录树应用re，返回(创建符号链接的源相对路径, 需要创建的目录)
+    fn apply(
+        config: impl FnOnce(LinkTaskArgsBuilder) -> LinkTaskArgsBuilder,
+    ) -> (HashSet<PathBuf>, HashSet<PathBuf>) {
+        apply_prepared(|_| {}, config)
+    }
+
+    /// 同apply，但在应用re前先通过prepare修改测试目录树(参数为src路径)
+    fn apply_prepared(
+        prepare: impl FnOnce(&Path),
+        config: impl FnOnce(LinkTaskArgsBuilder) -> LinkTaskArgsBuilder,
+    ) -> (HashSet<PathBuf>, HashSet<PathBuf>) {
+        let dir = tempdir().unwrap();
+        let src = make_tree(dir.path());
+        prepare(&src);
+        let builder = LinkTaskArgsBuilder::new(src.to_str().unwrap().to_string())
+            .dst(dir.path().join("dst").to_str().unwrap())
+            .make_dir(true);
+        let mut task = LinkTask::try_new(config(builder).build()).unwrap();
+        task.apply_re(None).unwrap();
+        let matched = task
+            .matched_paths
+            .unwrap()
+            .into_iter()
+            .map(|(src, _)| src)
+            .collect();
+        let dirs = task.dirs_to_create.unwrap().into_iter().collect();
+        (matched, dirs)
+    }
+
+    /// 对测试目录树以`.*`与给定粒度应用re
+    fn apply_with(granularity: ReDirGranularity) -> (HashSet<PathBuf>, HashSet<PathBuf>) {
+        apply(|b| {
+            b.re_pattern(regex::Regex::new(".*").unwrap())
+                .re_dir_granularity(granularity)
+        })
+    }
+
+    /// 以files粒度应用给定的re，只返回匹配的文件
+    fn matched_files(
+        patterns: &[&str],
+        config: impl FnOnce(LinkTaskArgsBuilder) -> LinkTaskArgsBuilder,
+    ) -> HashSet<PathBuf> {
+        let patterns = patterns
+            .iter()
+            .map(|p| regex::Regex::new(p).unwrap())
+            .collect();
+        apply(|b| {
+            config(
+                b.re_patterns(patterns)
+                    .re_dir_granularity(ReDirGranularity::Files),
+            )
+        })
+        .0
+    }
+
+    fn set(paths: &[&str]) -> HashSet<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_granularity_parse() {
+        assert_eq!(
+            "Shallowest".parse::<ReDirGranularity>().unwrap(),
+            ReDirGranularity::Shallowest
+        );
+        assert!("middle".parse::<ReDirGranularity>().is_err());
+        assert_eq!(ReDirGranularity::default(), ReDirGranularity::Deepest);
+    }
+
+    #[test]
+    /// deepest: 只有最深层的匹配目录a/b创建符号链接，a、c作为普通目录创建，
+    /// a/b下的文件已包含在目录链接中，不再单独链接
+    fn test_granularity_deepest() {
+        let (matched, dirs) = apply_with(ReDirGranularity::Deepest);
+        assert_eq!(matched, set(&["a/x.txt", "c/z.txt", "a/b"]));
+        assert!(!dirs.contains(Path::new("a/b")));
+        assert!(dirs.contains(Path::new("a")));
+        assert!(dirs.contains(Path::new("c")));
+    }
+
+    #[test]
+    /// shallowest: 首个匹配的目录a、c创建符号链接，其子树被跳过
+    fn test_granularity_shallowest() {
+        let (matched, dirs) = apply_with(ReDirGranularity::Shallowest);
+        assert_eq!(matched, set(&["a", "c"]));
+        assert!(dirs.is_empty());
+    }
+
+    #[test]
+    /// files: 不为目录创建符号链接，所有目录作为普通目录创建
+    fn test_granularity_files() {
+        let (matched, dirs) = apply_with(ReDirGranularity::Files);
+        assert_eq!(matched, set(&["a/x.txt", "a/b/y.txt", "c/z.txt"]));
+        assert_eq!(dirs, set(&["a", "a/b", "c"]));
+    }
+
+    /// 以`.*`与给定粒度流式应用re，返回产出的源相对路径
+    fn stream_with(
+        granularity: ReDirGranularity,
+        prepare: impl FnOnce(&Path),
+        flatten: bool,
+    ) -> MyResult<HashSet<PathBuf>> {
+        let dir = tempdir().unwrap();
+        let src = make_tree(dir.path());
+        prepare(&src);
+        let args = LinkTaskArgsBuilder::new(src.to_str().unwrap().to_string())
+            .dst(dir.path().join("dst").to_str().unwrap())
+            .make_dir(true)
+            .re_pattern(regex::Regex::new(".*").unwrap())
+            .re_dir_granularity(granularity)
+            .re_output_flatten(flatten)
+            .re_stream(true)
+            .build();
+        let task = LinkTask::try_new(args).unwrap();
+        let pairs = task.iter_planned_pairs()?;
+        Ok(pairs
+            .map(|(path, _)| path.strip_prefix(&task.src_path).unwrap().to_path_buf())
+            .collect())
+    }
+
+    #[test]
+    /// deepest下目录链接的子树不再单独产出
+    fn test_plan_stream() {
+        let streamed = stream_with(ReDirGranularity::Deepest, |_| {}, false).unwrap();
+        assert_eq!(streamed, set(&["a/x.txt", "c/z.txt", "a/b"]));
+
+        // 展平模式下的重复名称在产出前报告
+        let duplicate = |src: &Path| fs::write(src.join("c").join("x.txt"), b"x").unwrap();
+        let e = stream_with(ReDirGranularity::Files, duplicate, true).unwrap_err();
+        assert_eq!(e.code, ErrorCode::DuplicateTarget);
+        assert!(e.msg.contains("x.txt"));
+        let streamed = stream_with(ReDirGranularity::Files, |_| {}, true).unwrap();
+        assert_eq!(streamed.len(), 3);
+    }
+
+    #[test]
+    /// 各粒度与展平模式下，流式匹配与收集式匹配得到相同的路径对（含顺序）
+    fn test_plan_stream_matches_collected() {
+        let dir = tempdir().unwrap();
+        let src = make_tree(dir.path());
+        let re = regex::Regex::new(r"^a|\.txt$").unwrap();
+        let granularities = [
+            ReDirGranularity::Deepest,
+            ReDirGranularity::Shallowest,
+            ReDirGranularity::Files,
+        ];
+        for (granularity, flatten) in granularities
+            .into_iter()
+            .flat_map(|g| [(g, false), (g, true)])
+        {
+            let task = |stream: bool| {
+                let args = LinkTaskArgsBuilder::new(src.to_str().unwrap().to_string())
+                    .dst(dir.path().join("dst").to_str().unwrap())
+                    .make_dir(true)
+                    .re_pattern(re.clone())
+                    .re_dir_granularity(granularity)
+                    .re_output_flatten(flatten)
+                    .re_stream(stream)
+                    .build();
+                let mut task = LinkTask::try_new_plan(args).unwrap();
+                task.plan().unwrap();
+                task
+            };
+            let (collected, streamed) = (task(false), task(true));
+            assert_eq!(
+                collected.planned_pairs(),
+                streamed.iter_planned_pairs().unwrap().collect::<Vec<_>>(),
+                "{:?} flatten={}",
+                granularity,
+                flatten
+            );
+        }
+    }
+
+    #[test]
+    fn test_mklinks_stream() {
+        let dir = tempdir().unwrap();
+        let src = make_tree(dir.path());
+        let dst = dir.path().join("dst");
+        let args = |no_check: bool| {
+            LinkTaskArgsBuilder::new(src.to_str().unwrap().to_string())
+                .dst(dst.to_str().unwrap())
+                .make_dir(true)
+                .re_pattern(regex::Regex::new(r"\.txt$").unwrap())
+                .re_dir_granularity(ReDirGranularity::Files)
+                .re_stream(true)
+                .re_no_check(no_check)
+                .build()
+        };
+        // 流式匹配无法分页确认，需要同时指定re_no_check
+        let mut task = LinkTask::try_new(args(false)).unwrap();
+        assert_eq!(task.mklinks().unwrap_err().code, ErrorCode::InvalidInput);
+        assert!(!dst.exists());
+
+        let mut task = LinkTask::try_new(args(true)).unwrap();
+        assert!(task.mklinks().unwrap());
+        assert!(task.matched_paths.is_none());
+        for file in ["a/x.txt", "a/b/y.txt", "c/z.txt"] {
+            assert_eq!(fs::read_link(dst.join(file)).unwrap(), src.join(file));
+        }
+        // 重复执行时已存在的链接视为已完成
+        assert!(task.mklinks().unwrap());
+    }
+
+    #[test]
+    /// relative(默认): 匹配相对<SRC>的路径，`^a`可以匹配a下的内容
+    fn test_match_target_relative() {
+        assert_eq!(
+            matched_files(&["^a"], |b| b),
+            set(&["a/x.txt", "a/b/y.txt"])
+        );
+    }
+
+    #[test]
+    /// absolute: 匹配绝对路径，`^a`不会匹配任何内容
+    fn test_match_target_absolute() {
+        let matched = matched_files(&["^a"], |b| b.re_match_target(ReMatchTarget::Absolute));
+        assert!(matched.is_empty());
+    }
+
+    #[test]
+    /// name: 只匹配名称
+    fn test_match_target_name() {
+        assert_eq!(
+            matched_files(&["^y"], |b| b.re_match_target(ReMatchTarget::Name)),
+            set(&["a/b/y.txt"])
+        );
+    }
+
+    #[test]
+    /// 多个模式之间为或关系
+    fn test_multiple_patterns() {
+        assert_eq!(
+            matched_files(&[r"x\.txt$", r"z\.txt$"], |b| b),
+            set(&["a/x.txt", "c/z.txt"])
+        );
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        assert!(matched_files(&[r"X\.TXT$"], |b| b).is_empty());
+        assert_eq!(
+            matched_files(&[r"X\.TXT$"], |b| b.re_ignore_case(true)),
+            set(&["a/x.txt"])
+        );
+    }
+
+    /// 以`.*`与files粒度应用给定的元数据过滤条件，返回匹配的文件
+    fn filtered_files(prepare: impl FnOnce(&Path), filter: MetaFilter) -> HashSet<PathBuf> {
+        apply_prepared(prepare, |b| {
+            b.re_pattern(regex::Regex::new(".*").unwrap())
+                .re_dir_granularity(ReDirGranularity::Files)
+                .re_meta_filter(filter)
+        })
+        .0
+    }
+
+    #[test]
+    fn test_filter_size() {
+        let prepare = |src: &Path| fs::write(src.join("a").join("x.txt"), [0u8; 2048]).unwrap();
+        let filter = MetaFilter {
+            min_size: Some(1024),
+            ..Default::default()
+        };
+        assert_eq!(filtered_files(prepare, filter), set(&["a/x.txt"]));
+
+        let filter = MetaFilter {
+            max_size: Some(1024),
+            ..Default::default()
+        };
+        assert_eq!(
+            filtered_files(prepare, filter),
+            set(&["a/b/y.txt", "c/z.txt"])
+        );
+    }
+
+    #[test]
+    /// 大小条件只作用于文件，设置后目录不会被匹配
+    fn test_filter_size_excludes_dirs() {
+        let (matched, _) = apply(|b| {
+            b.re_pattern(regex::Regex::new(".*").unwrap())
+                .re_meta_filter(MetaFilter {
+                    min_size: Some(0),
+                    ..Default::default()
+                })
+        });
+        assert_eq!(matched, set(&["a/x.txt", "a/b/y.txt", "c/z.txt"]));
+    }
+
+    #[test]
+    fn test_filter_modified_time() {
+        let prepare = |src: &Path| {
+            let old = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1 << 30);
+            fs::File::options()
+                .write(true)
+                .open(src.join("c").join("z.txt"))
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        };
+        let boundary = crate::types::meta_filter::parse_time("2010-01-01").unwrap();
+        let filter = MetaFilter {
+            older: Some(boundary),
+            ..Default::default()
+        };
+        assert_eq!(filtered_files(prepare, filter), set(&["c/z.txt"]));
+
+        let filter = MetaFilter {
+            newer: Some(boundary),
+            ..Default::default()
+        };
+        assert_eq!(
+            filtered_files(prepare, filter),
+            set(&["a/x.txt", "a/b/y.txt"])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_filter_hidden_and_executable() {
+        use std::os::unix::fs::PermissionsExt;
+        let prepare = |src: &Path| {
+            fs::write(src.join("a").join(".hidden"), b"h").unwrap();
+            let y = src.join("a").join("b").join("y.txt");
+            fs::set_permissions(&y, fs::Permissions::from_mode(0o755)).unwrap();
+        };
+        let filter = MetaFilter {
+            hidden: true,
+            ..Default::default()
+        };
+        assert_eq!(filtered_files(prepare, filter), set(&["a/.hidden"]));
+
+        let filter = MetaFilter {
+            executable: true,
+            ..Default::default()
+        };
+        assert_eq!(filtered_files(prepare, filter), set(&["a/b/y.txt"]));
+    }
+
+    #[test]
+    /// 在MemFs上创建链接，第3个链接注入失败：前两个已创建，其余未执行
+    fn test_mklinks_on_mem_fs() {
+        use crate::utils::vfs::{FsOp, MemFs};
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        for name in ["1.txt", "2.txt", "3.txt", "4.txt"] {
+            mem.add_file(Path::new("/src").join(name), 1);
+        }
+        mem.add_dir("/dst").fail_nth(FsOp::Symlink, 3);
+
+        let args = LinkTaskArgsBuilder::new("/src".into())
+            .re_pattern(regex::Regex::new(".*").unwrap())
+            .re_no_check(true)
+            .build();
+        let matched = ["1.txt", "2.txt", "3.txt", "4.txt"]
+            .iter()
+            .map(|name| (PathBuf::from(name), PathBuf::from(name)))
+            .collect();
+        let mut task = LinkTask {
+            args,
+            src_path: PathBuf::from("/src"),
+            dst_path: PathBuf::from("/dst"),
+            matched_paths: Some(matched),
+            dirs_to_create: Some(Vec::new()),
+            fs: std_fs(),
+            preflight_facts: None,
+        }
+        .with_fs(mem.clone());
+
+        let e = task.mklinks().unwrap_err();
+        assert_eq!(e.code, ErrorCode::FailAtMakeLink);
+        assert_eq!(mem.journal().len(), 2);
+        assert!(mem.exists(Path::new("/dst/1.txt")));
+        assert!(mem.exists(Path::new("/dst/2.txt")));
+        assert!(!mem.exists(Path::new("/dst/3.txt")));
+    }
+
+    #[test]
+    /// Re匹配同样通过fs遍历，在MemFs上预览不会访问真实文件系统
+    fn test_plan_re_on_mem_fs() {
+        use crate::utils::vfs::MemFs;
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        mem.add_file("/src/a.txt", 1)
+            .add_file("/src/sub/b.txt", 1)
+            .add_file("/src/sub/c.log", 1);
+        let args = LinkTaskArgsBuilder::new("/src".into())
+            .re_pattern(regex::Regex::new(r"\.txt$").unwrap())
+            .make_dir(true)
+            .build();
+        let mut task = LinkTask {
+            args,
+            src_path: PathBuf::from("/src"),
+            dst_path: PathBuf::from("/dst"),
+            matched_paths: None,
+            dirs_to_create: None,
+            fs: mem.clone(),
+            preflight_facts: None,
+        };
+        task.plan().unwrap();
+        assert_eq!(
+            task.planned_pairs(),
+            [
+                (PathBuf::from("/src/a.txt"), PathBuf::from("/dst/a.txt")),
+                (
+                    PathBuf::from("/src/sub/b.txt"),
+                    PathBuf::from("/dst/sub/b.txt")
+                ),
+            ]
+        );
+        assert!(mem.journal().is_empty());
+    }
+
+    #[test]
+    /// --symlink不跟随符号链接时匹配链接本身；--one-file-system不进入其他设备上的目录
+    fn test_plan_re_symlink_and_one_file_system() {
+        use crate::types::meta_filter::MetaFilter;
+        use crate::utils::vfs::MemFs;
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        mem.add_file("/src/a.txt", 1)
+            .add_symlink("a.txt", "/src/link.txt")
+            .add_symlink("/missing", "/src/broken.txt")
+            .add_file("/src/mnt/b.txt", 1)
+            .set_device("/src/mnt", 1);
+        let plan = |filter: MetaFilter| {
+            let args = LinkTaskArgsBuilder::new("/src".into())
+                .re_pattern(regex::Regex::new(r"\.txt$").unwrap())
+                .re_meta_filter(filter)
+                .make_dir(true)
+                .build();
+            let mut task = LinkTask {
+                args,
+                src_path: PathBuf::from("/src"),
+                dst_path: PathBuf::from("/dst"),
+                matched_paths: None,
+                dirs_to_create: None,
+                fs: mem.clone(),
+                preflight_facts: None,
+            };
+            task.plan().unwrap();
+            task.planned_pairs()
+                .into_iter()
+                .map(|(src, _)| src)
+                .collect::<Vec<_>>()
+        };
+
+        let symlink = MetaFilter {
+            symlink: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            plan(symlink),
+            [
+                PathBuf::from("/src/broken.txt"),
+                PathBuf::from("/src/link.txt")
+            ]
+        );
+        let one_fs = MetaFilter {
+            one_file_system: true,
+            ..Default::default()
+        };
+        assert_eq!(plan(one_fs), [PathBuf::from("/src/a.txt")]);
+        assert_eq!(
+            plan(MetaFilter::default()),
+            [PathBuf::from("/src/a.txt"), PathBuf::from("/src/mnt/b.txt")]
+        );
+    }
+
+    #[test]
+    /// --only-managed：rm只删除fastlink创建的链接，其他方式创建的同名链接保留
+    fn test_rm_only_managed_on_mem_fs() {
+        use crate::types::link_task_args::LinkTaskOpMode;
+        use crate::utils::vfs::MemFs;
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        for name in ["1.txt", "2.txt", "3.txt"] {
+            mem.add_file(Path::new("/src").join(name), 1);
+        }
+        mem.add_dir("/dst");
+        let task = |names: &[&str], only_managed| {
+            let args = LinkTaskArgsBuilder::new("/src".into())
+                .dst("/dst")
+                .re_pattern(regex::Regex::new(".*").unwrap())
+                .re_no_check(true)
+                .op_mode(LinkTaskOpMode::Remove)
+                .only_managed(only_managed)
+                .build();
+            LinkTask {
+                args,
+                src_path: PathBuf::from("/src"),
+                dst_path: PathBuf::from("/dst"),
+                matched_paths: Some(
+                    names
+                        .iter()
+                        .map(|name| (PathBuf::from(name), PathBuf::from(name)))
+                        .collect(),
+                ),
+                dirs_to_create: Some(Vec::new()),
+                fs: std_fs(),
+                preflight_facts: None,
+            }
+            .with_fs(mem.clone())
+        };
+
+        assert!(task(&["1.txt", "2.txt"], false).mklinks().unwrap());
+        mem.add_symlink("/src/3.txt", "/dst/3.txt");
+        assert!(is_managed_with(&*mem, Path::new("/dst/1.txt")));
+        assert!(!is_managed_with(&*mem, Path::new("/dst/3.txt")));
+
+        let all = ["1.txt", "2.txt", "3.txt"];
+        task(&all, true).work().unwrap();
+        assert!(!mem.exists(Path::new("/dst/1.txt")));
+        assert!(!mem.exists(Path::new("/dst/2.txt")));
+        assert!(mem.exists(Path::new("/dst/3.txt")));
+
+        task(&all, false).work().unwrap();
+        assert!(!mem.exists(Path::new("/dst/3.txt")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    /// 使用BrokenSrcPolicy::Allow为不存在的<SRC>预先创建链接
+    fn test_work_dangling_src() {
+        use crate::types::link_task_args::BrokenSrcPolicy;
+
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("later/mounted");
+        let dst = dir.path().join("link");
+        let builder = || {
+            LinkTaskArgsBuilder::new(src.to_str().unwrap().to_string()).dst(dst.to_str().unwrap())
+        };
+
+        assert!(LinkTask::try_new(builder().build()).is_err());
+
+        let args = builder().broken_src(BrokenSrcPolicy::Allow).build();
+        LinkTask::try_new(args).unwrap().work().unwrap();
+        assert_eq!(fs::read_link(&dst).unwrap(), src);
+        assert!(!dst.exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_mklinks_within_root() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(root.join("data")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(root.join("data/a.txt"), b"a").unwrap();
+        fs::write(outside.join("b.txt"), b"b").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+
+        let make = |src: &Path, dst: &Path| {
+            let args = LinkTaskArgsBuilder::new(src.to_str().unwrap().to_string())
+                .dst(dst.to_str().unwrap())
+                .within(&root)
+                .build();
+            LinkTask::try_new(args).unwrap().mklinks()
+        };
+        let code = |res: MyResult<bool>| res.unwrap_err().code;
+
+        // 父目录是指向外部的符号链接
+        let src = root.join("data/a.txt");
+        assert_eq!(
+            code(make(&src, &root.join("escape/a.txt"))),
+            ErrorCode::OutsideRoot
+        );
+        // `..`逃逸
+        assert_eq!(
+            code(make(&src, &root.join("data/../../a.txt"))),
+            ErrorCode::OutsideRoot
+        );
+        // 链接目标在根目录外
+        assert_eq!(
+            code(make(&outside.join("b.txt"), &root.join("b.txt"))),
+            ErrorCode::OutsideRoot
+        );
+        assert!(!outside.join("a.txt").exists());
+
+        // 需要创建的父目录在根目录外时，不会在检查前创建
+        let args = LinkTaskArgsBuilder::new(src.to_str().unwrap().to_string())
+            .dst(root.join("escape/new/a.txt").to_str().unwrap())
+            .within(&root)
+            .make_dir(true)
+            .build();
+        let res = LinkTask::try_new(args).unwrap().mklinks();
+        assert_eq!(code(res), ErrorCode::OutsideRoot);
+        assert!(!outside.join("new").exists());
+
+        assert!(make(&src, &root.join("a.txt")).unwrap());
+        assert_eq!(fs::read_link(root.join("a.txt")).unwrap(), src);
+    }
+
+    #[test]
+    /// 预检一次性报告所有[DST]父目录的问题，且不做任何修改
+    fn test_preflight_reports_all_problems() {
+        use crate::utils::vfs::MemFs;
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        mem.add_file("/src/1.txt", 1)
+            .add_file("/src/2.txt", 1)
+            .add_file("/dst/file", 1);
+        let matched = vec![
+            (PathBuf::from("1.txt"), PathBuf::from("missing/1.txt")),
+            (PathBuf::from("2.txt"), PathBuf::from("file/2.txt")),
+        ];
+        let task = |make_dir| {
+            let args = LinkTaskArgsBuilder::new("/src".into())
+                .re_pattern(regex::Regex::new(".*").unwrap())
+                .re_no_check(true)
+                .make_dir(make_dir)
+                .build();
+            LinkTask {
+                args,
+                src_path: PathBuf::from("/src"),
+                dst_path: PathBuf::from("/dst"),
+                matched_paths: Some(matched.clone()),
+                dirs_to_create: Some(vec![PathBuf::from("missing")]),
+                fs: std_fs(),
+                preflight_facts: None,
+            }
+            .with_fs(mem.clone())
+        };
+
+        assert_eq!(task(false).preflight_problems().len(), 2);
+        let problems = task(true).preflight_problems();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].msg.contains("/dst/file"));
+
+        let e = task(true).mklinks().unwrap_err();
+        assert_eq!(e.code, ErrorCode::PreflightFailed);
+        assert!(mem.journal().is_empty());
+    }
+
+    #[test]
+    /// 流式Re匹配时预检不遍历<SRC>，检查产出的路径对共同的父目录[DST]，而不只是[DST]的父目录
+    fn test_preflight_stream_pairs() {
+        use crate::utils::vfs::MemFs;
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        mem.add_file("/src/1.txt", 1).add_dir("/dst");
+        let task = |make_dir| {
+            let args = LinkTaskArgsBuilder::new("/src".into())
+                .re_pattern(regex::Regex::new(r"\.txt$").unwrap())
+                .re_no_check(true)
+                .re_stream(true)
+                .make_dir(make_dir)
+                .build();
+            LinkTask {
+                args,
+                src_path: PathBuf::from("/src"),
+                dst_path: PathBuf::from("/dst/out"),
+                matched_paths: None,
+                dirs_to_create: None,
+                fs: std_fs(),
+                preflight_facts: None,
+            }
+            .with_fs(mem.clone())
+        };
+
+        // /dst/out/1.txt的父目录不存在（只检查[DST]时其父目录/dst存在）
+        let problems = task(false).preflight_problems();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].code, ErrorCode::ParentNotExist);
+        assert!(problems[0].msg.contains("/dst/out"));
+        assert!(task(true).preflight_problems().is_empty());
+        // plan不遍历<SRC>，只记录[DST]
+        let mut planned = task(true);
+        planned.plan().unwrap();
+        let facts = planned.preflight_facts.as_ref().unwrap();
+        assert_eq!(facts.parents, BTreeSet::from([PathBuf::from("/dst/out")]));
+    }
+
+    #[test]
+    /// 以复制创建时检查目标文件系统的剩余空间，复制只是回退类型时不视为问题
+    fn test_preflight_space_for_copy() {
+        use crate::types::link_task_args::LinkKind;
+        use crate::utils::vfs::MemFs;
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        mem.add_file("/src/a/1.txt", 60)
+            .add_file("/src/a/2.txt", 40)
+            .add_file("/src/b.txt", 30)
+            .add_dir("/dst")
+            .set_device("/dst", 1)
+            .set_available_space(1, 120);
+        let task = |src: &str, dst: &str, kind, fallback: &str| {
+            let args = LinkTaskArgsBuilder::new(src.into())
+                .link_kind(kind)
+                .link_fallback(fallback.parse().unwrap())
+                .build();
+            LinkTask {
+                args,
+                src_path: PathBuf::from(src),
+                dst_path: PathBuf::from(dst),
+                matched_paths: None,
+                dirs_to_create: None,
+                fs: std_fs(),
+                preflight_facts: None,
+            }
+            .with_fs(mem.clone())
+        };
+
+        assert!(task("/src/a", "/dst/a", LinkKind::Copy, "none")
+            .preflight_problems()
+            .is_empty());
+        let problems = task("/src", "/dst/src", LinkKind::Copy, "none").preflight_problems();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].code, ErrorCode::InsufficientSpace);
+        assert!(problems[0].msg.contains("130"));
+        // 只作为回退类型时只警告
+        assert!(task("/src", "/dst/src", LinkKind::Symlink, "copy")
+            .preflight_problems()
+            .is_empty());
+        // [DST]已存在时不计入
+        mem.add_dir("/dst/src");
+        assert!(task("/src", "/dst/src", LinkKind::Copy, "none")
+            .preflight_problems()
+            .is_empty());
+        assert!(mem.journal().is_empty());
+
+        // 预检使用plan时收集的信息，不再遍历：收集后删除的文件仍计入
+        let mut planned = task("/src", "/dst/new", LinkKind::Copy, "none");
+        planned.plan().unwrap();
+        mem.remove_file(Path::new("/src/b.txt")).unwrap();
+        assert_eq!(planned.preflight_problems().len(), 1);
+        assert!(task("/src", "/dst/new", LinkKind::Copy, "none")
+            .preflight_problems()
+            .is_empty());
+    }
+}
@@ -1,33 +1,49 @@
+#[cfg(feature = "fastlink-regex")]
+use crate::types::meta_filter::MetaFilter;
+
 #[cfg(feature = "fastlink-regex")]
 pub const DEFAULT_RE_MAX_DEPTH: usize = 4;
 
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct LinkTaskArgs {
     pub src: String,         // 原始源路径
     pub dst: Option<String>, // 原始目标路径
     pub op_mode: LinkTaskOpMode,
     #[cfg(feature = "fastlink-regex")]
-    pub re_pattern: Option<regex::Regex>, // 正则表达式模式
+    #[cfg_attr(feature = "serde", serde(with = "regex_serde"))]
+    pub re_patterns: Vec<regex::Regex>, // 正则表达式模式，多个模式之间为或关系
+    #[cfg(feature = "fastlink-regex")]
+    pub re_match_target: ReMatchTarget, // 正则表达式匹配的路径形式
+    #[cfg(feature = "fastlink-regex")]
+    pub re_ignore_case: bool, // 正则表达式匹配时忽略大小写
     #[cfg(feature = "fastlink-regex")]
     pub re_max_depth: usize, // 正则表达式模式最大深度
     #[cfg(feature = "fastlink-regex")]
     pub re_follow_links: bool, // re匹配过程中深入读取符号链接进行匹配
-    pub keep_extention: bool,        // 是否自动保留<SRC>的文件拓展名到[DST]
-    pub make_dir: bool,              // 是否自动创建不存在的目录
-    pub only_file: bool,             // 只处理文件
-    pub only_dir: bool,              // 只处理目录
-    pub overwrite_links: bool,       // 覆盖同名已存在的符号链接
-    pub overwrite_broken_link: bool, // 覆盖同名已存在的损坏的符号链接
-    pub skip_exist_links: bool,      // 跳过同名已存在的符号链接
-    pub skip_broken_src_links: bool, // 跳过src中损坏的符号链接
+    pub keep_extention: bool,      // 是否自动保留<SRC>的文件拓展名到[DST]
+    pub make_dir: bool,            // 是否自动创建不存在的目录
+    pub only_file: bool,           // 只处理文件
+    pub only_dir: bool,            // 只处理目录
+    pub only_managed: bool,        // check/rm只处理fastlink创建的链接
+    pub link_options: LinkOptions, // [DST]已存在符号链接、<SRC>损坏时的处理方式
+    pub within: Option<std::path::PathBuf>, // 所有链接路径与目标路径都必须位于该根目录内
     #[cfg(feature = "fastlink-regex")]
     pub re_no_check: bool, // 跳过用户Re检查
     #[cfg(feature = "fastlink-regex")]
     pub re_output_flatten: bool, // 展平输出路径
-    pub allow_broken_src: bool,
+    #[cfg(feature = "fastlink-regex")]
+    pub re_dir_granularity: ReDirGranularity, // re匹配时为目录创建符号链接的粒度
+    #[cfg(feature = "fastlink-regex")]
+    pub re_meta_filter: MetaFilter, // re匹配过程中基于元数据的过滤条件
+    #[cfg(feature = "fastlink-regex")]
+    pub re_stream: bool, // 流式处理：边遍历边创建，不收集匹配结果
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum LinkTaskOpMode {
     #[default]
     Make,
@@ -35,6 +51,414 @@ pub enum LinkTaskOpMode {
     Remove,
 }
 
+/// re匹配时，正则表达式所匹配的路径形式
+#[cfg(feature = "fastlink-regex")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ReMatchTarget {
+    /// 只匹配文件/目录名称
+    Name,
+    /// 匹配相对<SRC>的路径（<SRC>自身则使用其名称），结果不受<SRC>所在位置影响
+    #[default]
+    Relative,
+    /// 匹配完整的绝对路径
+    Absolute,
+}
+
+#[cfg(feature = "fastlink-regex")]
+impl std::str::FromStr for ReMatchTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "name" => Ok(Self::Name),
+            "relative" => Ok(Self::Relative),
+            "absolute" => Ok(Self::Absolute),
+            _ => Err(format!(
+                "无效的匹配目标 '{}'，可选值: name, relative, absolute",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "fastlink-regex")]
+impl std::fmt::Display for ReMatchTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Name => write!(f, "name"),
+            Self::Relative => write!(f, "relative"),
+            Self::Absolute => write!(f, "absolute"),
+        }
+    }
+}
+
+/// re匹配时为目录创建符号链接的粒度
+#[cfg(feature = "fastlink-regex")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ReDirGranularity {
+    /// 只为匹配到的最深层目录创建符号链接，较浅层的匹配目录作为普通目录创建
+    #[default]
+    Deepest,
+    /// 为首个匹配到的目录创建符号链接，并跳过其子树（<SRC>自身不参与匹配）
+    Shallowest,
+    /// 从不为目录创建符号链接，只为文件创建，目录均作为普通目录创建
+    Files,
+}
+
+#[cfg(feature = "fastlink-regex")]
+impl std::str::FromStr for ReDirGranularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "deepest" => Ok(Self::Deepest),
+            "shallowest" => Ok(Self::Shallowest),
+            "files" => Ok(Self::Files),
+            _ => Err(format!(
+                "无效的目录粒度 '{}'，可选值: deepest, shallowest, files",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "fastlink-regex")]
+impl std::fmt::Display for ReDirGranularity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Deepest => write!(f, "deepest"),
+            Self::Shallowest => write!(f, "shallowest"),
+            Self::Files => write!(f, "files"),
+        }
+    }
+}
+
+/// [DST]处已存在符号链接时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ConflictPolicy {
+    /// 报错，不做修改
+    Error,
+    /// 跳过已存在的符号链接（无论是否损坏）
+    Skip,
+    /// 覆盖已存在的符号链接
+    Overwrite,
+    /// 只覆盖损坏的符号链接，有效的符号链接则报错
+    #[default]
+    OverwriteBroken,
+}
+
+impl ConflictPolicy {
+    /// 已存在的符号链接（broken表示是否损坏）是否应被覆盖
+    pub fn overwrite(&self, broken: bool) -> bool {
+        match self {
+            Self::Overwrite => true,
+            Self::OverwriteBroken => broken,
+            Self::Error | Self::Skip => false,
+        }
+    }
+}
+
+impl std::str::FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('_', "-").as_str() {
+            "error" => Ok(Self::Error),
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "overwrite-broken" => Ok(Self::OverwriteBroken),
+            _ => Err(format!(
+                "无效的冲突处理方式 '{}'，可选值: error, skip, overwrite, overwrite-broken",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Skip => write!(f, "skip"),
+            Self::Overwrite => write!(f, "overwrite"),
+            Self::OverwriteBroken => write!(f, "overwrite-broken"),
+        }
+    }
+}
+
+/// <SRC>为损坏的符号链接或不存在时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum BrokenSrcPolicy {
+    /// 报错
+    Error,
+    /// 跳过损坏的符号链接，<SRC>不存在时仍报错
+    #[default]
+    Skip,
+    /// 仍然创建指向它的符号链接（悬空链接），如为稍后挂载的磁盘预先创建链接
+    Allow,
+}
+
+impl std::str::FromStr for BrokenSrcPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "skip" => Ok(Self::Skip),
+            "allow" => Ok(Self::Allow),
+            _ => Err(format!(
+                "无效的损坏src处理方式 '{}'，可选值: error, skip, allow",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for BrokenSrcPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Skip => write!(f, "skip"),
+            Self::Allow => write!(f, "allow"),
+        }
+    }
+}
+
+/// 符号链接目标的类型，Windows下决定创建文件符号链接还是目录符号链接
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum TargetKind {
+    /// 由<SRC>的类型决定，<SRC>不存在时在Windows下报错
+    #[default]
+    Auto,
+    File,
+    Dir,
+}
+
+impl std::str::FromStr for TargetKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "file" => Ok(Self::File),
+            "dir" => Ok(Self::Dir),
+            _ => Err(format!("无效的目标类型 '{}'，可选值: auto, file, dir", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::File => write!(f, "file"),
+            Self::Dir => write!(f, "dir"),
+        }
+    }
+}
+
+/// 创建的链接类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum LinkKind {
+    /// 符号链接
+    #[default]
+    Symlink,
+    /// Windows快捷方式(.lnk)，普通文件，不需要创建符号链接的权限，[DST]没有.lnk扩展名时自动追加
+    Shortcut,
+    /// 硬链接，只支持文件，且需要与<SRC>位于同一文件系统
+    Hardlink,
+    /// 复制<SRC>（目录递归复制），不再与<SRC>同步
+    Copy,
+}
+
+impl LinkKind {
+    /// 用于日志的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Symlink => "符号链接",
+            Self::Shortcut => "快捷方式",
+            Self::Hardlink => "硬链接",
+            Self::Copy => "副本",
+        }
+    }
+}
+
+impl std::str::FromStr for LinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "symlink" => Ok(Self::Symlink),
+            "shortcut" | "lnk" => Ok(Self::Shortcut),
+            "hardlink" | "hard" => Ok(Self::Hardlink),
+            "copy" => Ok(Self::Copy),
+            _ => Err(format!(
+                "无效的链接类型 '{}'，可选值: symlink, shortcut, hardlink, copy",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Symlink => write!(f, "symlink"),
+            Self::Shortcut => write!(f, "shortcut"),
+            Self::Hardlink => write!(f, "hardlink"),
+            Self::Copy => write!(f, "copy"),
+        }
+    }
+}
+
+/// 以`kind`创建失败（如没有创建符号链接的权限）时，依次尝试的其他链接类型，如`hardlink,copy`
+///
+/// 为保持[`LinkOptions`]可复制，以定长数组保存，每种类型最多出现一次
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct LinkFallback {
+    kinds: [Option<LinkKind>; 4],
+}
+
+impl LinkFallback {
+    /// 不回退
+    pub const NONE: Self = LinkFallback { kinds: [None; 4] };
+
+    pub fn new(kinds: &[LinkKind]) -> Result<Self, String> {
+        let mut fallback = Self::NONE;
+        for (i, kind) in kinds.iter().enumerate() {
+            if kinds[..i].contains(kind) {
+                return Err(format!("回退链接类型重复: {}", kind));
+            }
+            // 4种类型各出现一次时恰好填满
+            fallback.kinds[i] = Some(*kind);
+        }
+        Ok(fallback)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = LinkKind> + '_ {
+        self.kinds.iter().map_while(|kind| *kind)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds[0].is_none()
+    }
+}
+
+impl std::str::FromStr for LinkFallback {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if matches!(s.trim().to_lowercase().as_str(), "" | "none") {
+            return Ok(Self::default());
+        }
+        let kinds = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<LinkKind>, _>>()?;
+        Self::new(&kinds)
+    }
+}
+
+impl std::fmt::Display for LinkFallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+        let kinds: Vec<String> = self.iter().map(|kind| kind.to_string()).collect();
+        write!(f, "{}", kinds.join(","))
+    }
+}
+
+impl TryFrom<String> for LinkFallback {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<LinkFallback> for String {
+    fn from(value: LinkFallback) -> Self {
+        value.to_string()
+    }
+}
+
+/// 创建单个符号链接时的选项，见[`crate::utils::link::mklink`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LinkOptions {
+    pub conflict: ConflictPolicy,
+    pub broken_src: BrokenSrcPolicy,
+    pub target_kind: TargetKind,
+    pub kind: LinkKind,
+    pub fallback: LinkFallback,
+}
+
+impl LinkOptions {
+    pub fn new(conflict: ConflictPolicy, broken_src: BrokenSrcPolicy) -> Self {
+        LinkOptions {
+            conflict,
+            broken_src,
+            target_kind: TargetKind::Auto,
+            kind: LinkKind::Symlink,
+            fallback: LinkFallback::default(),
+        }
+    }
+
+    pub fn with_target_kind(mut self, target_kind: TargetKind) -> Self {
+        self.target_kind = target_kind;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: LinkKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_fallback(mut self, fallback: LinkFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// 依次尝试的链接类型：kind及其后的回退类型（与kind相同的回退类型被忽略）
+    pub fn kind_chain(&self) -> impl Iterator<Item = LinkKind> + '_ {
+        std::iter::once(self.kind).chain(self.fallback.iter().filter(|k| *k != self.kind))
+    }
+}
+
+/// 以源字符串形式序列化正则表达式
+#[cfg(all(feature = "serde", feature = "fastlink-regex"))]
+mod regex_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(patterns: &[regex::Regex], s: S) -> Result<S::Ok, S::Error> {
+        let sources: Vec<&str> = patterns.iter().map(|re| re.as_str()).collect();
+        sources.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<regex::Regex>, D::Error> {
+        Vec::<String>::deserialize(d)?
+            .iter()
+            .map(|source| regex::Regex::new(source).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
 // Builder 结构体
 #[derive(Default)]
 pub struct LinkTaskArgsBuilder {
@@ -42,7 +466,11 @@ pub struct LinkTaskArgsBuilder {
     dst: Option<String>,
     op_mode: Option<LinkTaskOpMode>,
     #[cfg(feature = "fastlink-regex")]
-    re_pattern: Option<regex::Regex>,
+    re_patterns: Vec<regex::Regex>,
+    #[cfg(feature = "fastlink-regex")]
+    re_match_target: Option<ReMatchTarget>,
+    #[cfg(feature = "fastlink-regex")]
+    re_ignore_case: Option<bool>,
     #[cfg(feature = "fastlink-regex")]
     re_max_depth: Option<usize>,
     #[cfg(feature = "fastlink-regex")]
@@ -51,15 +479,23 @@ pub struct LinkTaskArgsBuilder {
     make_dir: Option<bool>,
     only_file: Option<bool>,
     only_dir: Option<bool>,
-    overwrite_links: Option<bool>,
-    overwrite_broken_link: Option<bool>,
-    skip_exist_links: Option<bool>,
-    skip_broken_src_links: Option<bool>,
+    only_managed: Option<bool>,
+    conflict: Option<ConflictPolicy>,
+    broken_src: Option<BrokenSrcPolicy>,
+    target_kind: Option<TargetKind>,
+    link_kind: Option<LinkKind>,
+    link_fallback: Option<LinkFallback>,
+    within: Option<std::path::PathBuf>,
     #[cfg(feature = "fastlink-regex")]
     re_no_check: Option<bool>,
     #[cfg(feature = "fastlink-regex")]
     re_output_flatten: Option<bool>,
-    allow_broken_src: Option<bool>,
+    #[cfg(feature = "fastlink-regex")]
+    re_dir_granularity: Option<ReDirGranularity>,
+    #[cfg(feature = "fastlink-regex")]
+    re_meta_filter: Option<MetaFilter>,
+    #[cfg(feature = "fastlink-regex")]
+    re_stream: Option<bool>,
 }
 
 /// 链式设置参数以创建LinkTaskArgs
@@ -80,7 +516,11 @@ impl LinkTaskArgsBuilder {
             dst: self.dst,
             op_mode: self.op_mode.unwrap_or_default(),
             #[cfg(feature = "fastlink-regex")]
-            re_pattern: self.re_pattern,
+            re_patterns: self.re_patterns,
+            #[cfg(feature = "fastlink-regex")]
+            re_match_target: self.re_match_target.unwrap_or_default(),
+            #[cfg(feature = "fastlink-regex")]
+            re_ignore_case: self.re_ignore_case.unwrap_or(false),
             #[cfg(feature = "fastlink-regex")]
             re_max_depth: self.re_max_depth.unwrap_or(DEFAULT_RE_MAX_DEPTH),
             #[cfg(feature = "fastlink-regex")]
@@ -89,15 +529,75 @@ impl LinkTaskArgsBuilder {
             make_dir: self.make_dir.unwrap_or(false),
             only_file: self.only_file.unwrap_or(false),
             only_dir: self.only_dir.unwrap_or(false),
-            overwrite_links: self.overwrite_links.unwrap_or(false),
-            overwrite_broken_link: self.overwrite_broken_link.unwrap_or(false),
-            skip_exist_links: self.skip_exist_links.unwrap_or(false),
-            skip_broken_src_links: self.skip_broken_src_links.unwrap_or(false),
+            only_managed: self.only_managed.unwrap_or(false),
+            link_options: LinkOptions::new(
+                self.conflict.unwrap_or_default(),
+                self.broken_src.unwrap_or_default(),
+            )
+            .with_target_kind(self.target_kind.unwrap_or_default())
+            .with_kind(self.link_kind.unwrap_or_default())
+            .with_fallback(self.link_fallback.unwrap_or_default()),
+            within: self.within,
             #[cfg(feature = "fastlink-regex")]
             re_no_check: self.re_no_check.unwrap_or(false),
             #[cfg(feature = "fastlink-regex")]
             re_output_flatten: self.re_output_flatten.unwrap_or(false),
-            allow_broken_src: self.allow_broken_src.unwrap_or(false),
+            #[cfg(feature = "fastlink-regex")]
+            re_dir_granularity: self.re_dir_granularity.unwrap_or_default(),
+            #[cfg(feature = "fastlink-regex")]
+            re_meta_filter: self.re_meta_filter.unwrap_or_default(),
+            #[cfg(feature = "fastlink-regex")]
+            re_stream: self.re_stream.unwrap_or(false),
+        }
+    }
+
+    /// 合并另一个Builder：self中已设置的参数优先，未设置的参数取自fallback
+    ///
+    /// 用于按优先级叠加多个来源的参数，如命令行 > 项目配置 > 用户配置 > 内置默认值
+    pub fn merge(self, fallback: LinkTaskArgsBuilder) -> Self {
+        LinkTaskArgsBuilder {
+            src: if self.src.is_empty() {
+                fallback.src
+            } else {
+                self.src
+            },
+            dst: self.dst.or(fallback.dst),
+            op_mode: self.op_mode.or(fallback.op_mode),
+            #[cfg(feature = "fastlink-regex")]
+            re_patterns: if self.re_patterns.is_empty() {
+                fallback.re_patterns
+            } else {
+                self.re_patterns
+            },
+            #[cfg(feature = "fastlink-regex")]
+            re_match_target: self.re_match_target.or(fallback.re_match_target),
+            #[cfg(feature = "fastlink-regex")]
+            re_ignore_case: self.re_ignore_case.or(fallback.re_ignore_case),
+            #[cfg(feature = "fastlink-regex")]
+            re_max_depth: self.re_max_depth.or(fallback.re_max_depth),
+            #[cfg(feature = "fastlink-regex")]
+            re_follow_links: self.re_follow_links.or(fallback.re_follow_links),
+            keep_extention: self.keep_extention.or(fallback.keep_extention),
+            make_dir: self.make_dir.or(fallback.make_dir),
+            only_file: self.only_file.or(fallback.only_file),
+            only_dir: self.only_dir.or(fallback.only_dir),
+            only_managed: self.only_managed.or(fallback.only_managed),
+            conflict: self.conflict.or(fallback.conflict),
+            broken_src: self.broken_src.or(fallback.broken_src),
+            target_kind: self.target_kind.or(fallback.target_kind),
+            link_kind: self.link_kind.or(fallback.link_kind),
+            link_fallback: self.link_fallback.or(fallback.link_fallback),
+            within: self.within.or(fallback.within),
+            #[cfg(feature = "fastlink-regex")]
+            re_no_check: self.re_no_check.or(fallback.re_no_check),
+            #[cfg(feature = "fastlink-regex")]
+            re_output_flatten: self.re_output_flatten.or(fallback.re_output_flatten),
+            #[cfg(feature = "fastlink-regex")]
+            re_dir_granularity: self.re_dir_granularity.or(fallback.re_dir_granularity),
+            #[cfg(feature = "fastlink-regex")]
+            re_meta_filter: self.re_meta_filter.or(fallback.re_meta_filter),
+            #[cfg(feature = "fastlink-regex")]
+            re_stream: self.re_stream.or(fallback.re_stream),
         }
     }
 
@@ -106,9 +606,33 @@ impl LinkTaskArgsBuilder {
         self
     }
 
+    pub fn op_mode(mut self, value: LinkTaskOpMode) -> Self {
+        self.op_mode = Some(value);
+        self
+    }
+
+    /// 追加一个正则表达式模式，多个模式之间为或关系
     #[cfg(feature = "fastlink-regex")]
     pub fn re_pattern(mut self, value: regex::Regex) -> Self {
-        self.re_pattern = Some(value);
+        self.re_patterns.push(value);
+        self
+    }
+
+    #[cfg(feature = "fastlink-regex")]
+    pub fn re_patterns(mut self, value: Vec<regex::Regex>) -> Self {
+        self.re_patterns = value;
+        self
+    }
+
+    #[cfg(feature = "fastlink-regex")]
+    pub fn re_match_target(mut self, value: ReMatchTarget) -> Self {
+        self.re_match_target = Some(value);
+        self
+    }
+
+    #[cfg(feature = "fastlink-regex")]
+    pub fn re_ignore_case(mut self, value: bool) -> Self {
+        self.re_ignore_case = Some(value);
         self
     }
 
@@ -118,6 +642,12 @@ impl LinkTaskArgsBuilder {
         self
     }
 
+    #[cfg(feature = "fastlink-regex")]
+    pub fn re_follow_links(mut self, value: bool) -> Self {
+        self.re_follow_links = Some(value);
+        self
+    }
+
     pub fn keep_extention(mut self, value: bool) -> Self {
         self.keep_extention = Some(value);
         self
@@ -138,26 +668,50 @@ impl LinkTaskArgsBuilder {
         self
     }
 
-    pub fn overwrite_links(mut self, value: bool) -> Self {
-        self.overwrite_links = Some(value);
+    pub fn only_managed(mut self, value: bool) -> Self {
+        self.only_managed = Some(value);
+        self
+    }
+
+    pub fn conflict(mut self, value: ConflictPolicy) -> Self {
+        self.conflict = Some(value);
+        self
+    }
+
+    pub fn broken_src(mut self, value: BrokenSrcPolicy) -> Self {
+        self.broken_src = Some(value);
+        self
+    }
+
+    pub fn target_kind(mut self, value: TargetKind) -> Self {
+        self.target_kind = Some(value);
         self
     }
 
-    pub fn overwrite_broken_link(mut self, value: bool) -> Self {
-        self.overwrite_broken_link = Some(value);
+    pub fn link_kind(mut self, value: LinkKind) -> Self {
+        self.link_kind = Some(value);
         self
     }
 
-    pub fn skip_exist_links(mut self, value: bool) -> Self {
-        self.skip_exist_links = Some(value);
+    pub fn link_fallback(mut self, value: LinkFallback) -> Self {
+        self.link_fallback = Some(value);
         self
     }
 
-    pub fn skip_broken_src_links(mut self, value: bool) -> Self {
-        self.skip_broken_src_links = Some(value);
+    /// 限定根目录，超出该目录的链接路径或目标路径将被拒绝
+    pub fn within(mut self, value: impl Into<std::path::PathBuf>) -> Self {
+        self.within = Some(value.into());
         self
     }
 
+    pub fn link_options(self, value: LinkOptions) -> Self {
+        self.conflict(value.conflict)
+            .broken_src(value.broken_src)
+            .target_kind(value.target_kind)
+            .link_kind(value.kind)
+            .link_fallback(value.fallback)
+    }
+
     #[cfg(feature = "fastlink-regex")]
     pub fn re_no_check(mut self, value: bool) -> Self {
         self.re_no_check = Some(value);
@@ -169,6 +723,24 @@ impl LinkTaskArgsBuilder {
         self.re_output_flatten = Some(value);
         self
     }
+
+    #[cfg(feature = "fastlink-regex")]
+    pub fn re_dir_granularity(mut self, value: ReDirGranularity) -> Self {
+        self.re_dir_granularity = Some(value);
+        self
+    }
+
+    #[cfg(feature = "fastlink-regex")]
+    pub fn re_meta_filter(mut self, value: MetaFilter) -> Self {
+        self.re_meta_filter = Some(value);
+        self
+    }
+
+    #[cfg(feature = "fastlink-regex")]
+    pub fn re_stream(mut self, value: bool) -> Self {
+        self.re_stream = Some(value);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -187,11 +759,8 @@ mod tests {
         assert!(!args.make_dir);
         assert!(!args.only_file);
         assert!(!args.only_dir);
-        assert!(!args.overwrite_links);
-        assert!(!args.overwrite_broken_link);
-        assert!(!args.skip_exist_links);
-        assert!(!args.skip_broken_src_links);
-        assert!(!args.allow_broken_src);
+        assert_eq!(args.link_options.conflict, ConflictPolicy::OverwriteBroken);
+        assert_eq!(args.link_options.broken_src, BrokenSrcPolicy::Skip);
     }
 
     #[test]
@@ -204,10 +773,8 @@ mod tests {
             .make_dir(true)
             .only_file(true)
             .only_dir(false)
-            .overwrite_links(true)
-            .overwrite_broken_link(true)
-            .skip_exist_links(true)
-            .skip_broken_src_links(true)
+            .conflict(ConflictPolicy::Overwrite)
+            .broken_src(BrokenSrcPolicy::Allow)
             .build();
         assert_eq!(args.src, src);
         assert_eq!(args.dst, Some(dst));
@@ -215,10 +782,29 @@ mod tests {
         assert!(args.make_dir);
         assert!(args.only_file);
         assert!(!args.only_dir);
-        assert!(args.overwrite_links);
-        assert!(args.overwrite_broken_link);
-        assert!(args.skip_exist_links);
-        assert!(args.skip_broken_src_links);
+        assert_eq!(
+            args.link_options,
+            LinkOptions::new(ConflictPolicy::Overwrite, BrokenSrcPolicy::Allow)
+        );
+    }
+
+    #[test]
+    fn test_link_task_args_builder_merge() {
+        let cli =
+            LinkTaskArgsBuilder::new(String::from("/tmp/source")).conflict(ConflictPolicy::Error);
+        let project = LinkTaskArgsBuilder::default()
+            .conflict(ConflictPolicy::Overwrite)
+            .broken_src(BrokenSrcPolicy::Allow);
+        let user = LinkTaskArgsBuilder::default()
+            .broken_src(BrokenSrcPolicy::Error)
+            .make_dir(true);
+
+        let args = cli.merge(project.merge(user)).build();
+        assert_eq!(args.src, "/tmp/source");
+        assert_eq!(args.link_options.conflict, ConflictPolicy::Error);
+        assert_eq!(args.link_options.broken_src, BrokenSrcPolicy::Allow);
+        assert!(args.make_dir);
+        assert!(!args.only_file);
     }
 
     #[test]
@@ -230,4 +816,38 @@ mod tests {
         let args = LinkTaskArgsBuilder::new(src.clone()).build();
         assert_eq!(args.src, src);
     }
+
+    #[test]
+    fn test_link_policy_from_str() {
+        assert_eq!(
+            "overwrite-broken".parse::<ConflictPolicy>(),
+            Ok(ConflictPolicy::OverwriteBroken)
+        );
+        assert_eq!("Skip".parse::<ConflictPolicy>(), Ok(ConflictPolicy::Skip));
+        assert!("replace".parse::<ConflictPolicy>().is_err());
+        assert_eq!(
+            "allow".parse::<BrokenSrcPolicy>(),
+            Ok(BrokenSrcPolicy::Allow)
+        );
+        assert!(ConflictPolicy::OverwriteBroken.overwrite(true));
+        assert!(!ConflictPolicy::OverwriteBroken.overwrite(false));
+    }
+
+    #[test]
+    fn test_link_fallback_from_str() {
+        let fallback = "hardlink, copy".parse::<LinkFallback>().unwrap();
+        assert_eq!(
+            fallback.iter().collect::<Vec<_>>(),
+            [LinkKind::Hardlink, LinkKind::Copy]
+        );
+        assert_eq!(fallback.to_string(), "hardlink,copy");
+        assert!("none".parse::<LinkFallback>().unwrap().is_empty());
+        assert!("copy,copy".parse::<LinkFallback>().is_err());
+        assert!("junction".parse::<LinkFallback>().is_err());
+
+        // 与kind相同的回退类型被忽略
+        let options = LinkOptions::default().with_fallback(fallback);
+        let chain: Vec<_> = options.with_kind(LinkKind::Copy).kind_chain().collect();
+        assert_eq!(chain, [LinkKind::Copy, LinkKind::Hardlink]);
+    }
 }
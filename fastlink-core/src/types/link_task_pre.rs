@@ -1,5 +1,7 @@
 use crate::types::err::{ErrorCode, MyError, MyResult};
-use crate::types::link_task_args::{LinkTaskArgs, LinkTaskOpMode};
+use crate::types::link_task_args::{BrokenSrcPolicy, LinkTaskArgs, LinkTaskOpMode};
+use crate::utils::expand::expand_env;
+use crate::utils::path::{inspect_path, PathStatus};
 use path_clean::PathClean;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
@@ -25,6 +27,17 @@ impl LinkTaskPre {
         task_pre.parse()?;
         Ok(task_pre)
     }
+
+    /// 只解析src、dst，不在文件系统上做任何修改（不创建[DST]父目录，也不要求其存在），用于导出计划
+    pub fn try_new_plan(args: LinkTaskArgs) -> MyResult<Self> {
+        let src_path = check_src(&args)?;
+        let dst_path = crate::utils::path::canonicalize_path(parse_args_dst(&args)?);
+        Ok(LinkTaskPre {
+            args,
+            src_path: Some(src_path),
+            dst_path: Some(dst_path),
+        })
+    }
 }
 
 impl LinkTaskPre {
@@ -37,24 +50,35 @@ impl LinkTaskPre {
     }
 }
 
-/// 解析、规范、验证src,
-/// 不存在，或是损坏的符号链接都将返回Err
+/// 解析、规范、验证src（先展开`~`与环境变量）,
+/// 不存在，或是损坏的符号链接都将返回Err，除非使用`BrokenSrcPolicy::Allow`
 pub fn check_src(task_args: &LinkTaskArgs) -> MyResult<PathBuf> {
-    let s = &task_args.src;
+    let s = &expand_env(&task_args.src)?;
     // 验证存在、或是有效的符号链接
     let src = Path::new(s);
-    let res = crate::utils::func::mklink_pre_check(src);
-    match res {
-        Ok(_) => Ok(()),
-        Err(e) if e.code == ErrorCode::TargetLinkExists => Ok(()),
-        Err(e) if e.code == ErrorCode::TargetExistsAndNotLink => Ok(()),
-        Err(e) if e.code == ErrorCode::FileNotExist => Err(e),
-        Err(mut e) if e.code == ErrorCode::BrokenSymlink => {
-            e.msg = format!("\n损坏的符号链接不可以作为src: {}", e.msg);
-            Err(e)
-        }
-        Err(e) => Err(e),
+    let status = inspect_path(src);
+    // 允许悬空链接时，不存在的src无法canonicalize，只转为绝对路径
+    if task_args.link_options.broken_src == BrokenSrcPolicy::Allow
+        && (status.is_missing() || matches!(status, PathStatus::BrokenSymlink { .. }))
+    {
+        return Ok(crate::utils::path::canonicalize_path(src));
+    }
+    match status {
+        PathStatus::Missing => Err(MyError::new(
+            ErrorCode::FileNotExist,
+            format!(
+                "{}\n若要预先创建指向它的链接，请使用--broken-src allow",
+                src.display()
+            ),
+        )),
+        PathStatus::BrokenSymlink { .. } => Err(MyError::new(
+            ErrorCode::BrokenSymlink,
+            format!("\n损坏的符号链接不可以作为src: {}", src.display()),
+        )),
+        PathStatus::Inaccessible(e) => Err(e),
+        _ => Ok(()),
     }?;
+
     let src_abs_res = dunce::canonicalize(s);
     if let Err(e) = src_abs_res {
         Err(MyError::new(
@@ -93,14 +117,15 @@ pub fn check_dst(task_args: &LinkTaskArgs) -> MyResult<PathBuf> {
 /// 3. 给出dst，且带有目录倾向
 ///    在后续追加src名称
 ///
-/// 必要时追加拓展名
+/// 必要时追加拓展名。src、dst中的`~`与环境变量会先被展开
 pub fn parse_args_dst(task_args: &LinkTaskArgs) -> MyResult<PathBuf> {
-    let src_path = Path::new(&task_args.src).clean();
+    let src_path = Path::new(&expand_env(&task_args.src)?).clean();
     let log = task_args.op_mode == LinkTaskOpMode::Make;
 
     let mut final_dst = match &task_args.dst {
         None => default_dst_name(&src_path, log),
         Some(d) => {
+            let d = expand_env(d)?;
             let dst_path = Path::new(&d);
             let is_dst_dir_intended = d.ends_with('/') || d.ends_with('\\');
 
@@ -175,13 +200,14 @@ fn default_dst_name(src: &Path, log: bool) -> PathBuf {
 
 /// 返回规范化后的dst绝对路径
 /// 若其父目录不存在且make_dir为false，则将返回Err
+///
+/// 不在文件系统上做任何修改：不存在的父目录在检查within与预检之后才创建，见`LinkTask::mklinks`
 pub fn validate_dst(task_args: &LinkTaskArgs, dst: &Path) -> Result<PathBuf, MyError> {
     log::debug!("validate_dst/dst: {}", dst.display());
 
     let dst_parent_option = dst.parent();
     // 参数--md不为true时，若dst父目录不存在，或其本身是目录且不存在，则报错返回
     handle_validate_dst_parent_not_exist(task_args.make_dir, dst_parent_option)?;
-    // 接下来保证dst_parent存在
 
     // 规范化dst路径
     canonicalize_dst(dst)
@@ -194,18 +220,24 @@ fn canonicalize_dst(dst: &Path) -> Result<PathBuf, MyError> {
         OsStr::new("unnamed-fastlink")
     });
     let dst_parent = dst.parent().unwrap();
+    // 父目录尚未创建时，规范化其最近的已存在上级目录，再拼接其余部分
+    let existing = dst_parent
+        .ancestors()
+        .find(|dir| dir.exists())
+        .unwrap_or(dst_parent);
+    let rest = dst_parent.strip_prefix(existing).unwrap_or(Path::new(""));
     // 规范化
-    let dst_parent = dst.parent().unwrap().canonicalize().map_err(|e| {
+    let existing = existing.canonicalize().map_err(|e| {
         MyError::new(
             ErrorCode::IoError,
             format!("规范化dst父目录时出错: {} {e}", dst_parent.display()),
         )
     })?;
-    let dst_path = dst_parent.join(dst_name);
+    let dst_path = existing.join(rest).join(dst_name);
     Ok(dst_path)
 }
 
-/// validate_dst函数辅助函数，为dst创建父目录,
+/// validate_dst函数辅助函数，
 /// 参数--md不为true时，若dst父目录不存在，则报错
 fn handle_validate_dst_parent_not_exist(
     make_dir: bool,
@@ -214,8 +246,8 @@ fn handle_validate_dst_parent_not_exist(
     if let Some(parent) = dst_parent_option {
         if !parent.exists() {
             if make_dir {
-                // 创建目录并处理错误
-                handle_validate_dst_mkdirs(parent)
+                // 稍后由LinkTask创建
+                Ok(())
             } else {
                 // 不允许创建目录则直接报错
                 Err(MyError::new(
@@ -234,38 +266,6 @@ fn handle_validate_dst_parent_not_exist(
     }
 }
 
-/// 为validate_dst函数（handle_validate_dst_parent_not_exist函数）处理创建目录及相关错误
-fn handle_validate_dst_mkdirs(dst_parent: &Path) -> Result<(), MyError> {
-    match crate::utils::fs::mkdirs(dst_parent) {
-        Ok(_) => {
-            log::info!("[DST]父目录不存在，已创建: {}", dst_parent.display());
-            Ok(())
-        }
-        Err(e) => Err(MyError::new(
-            ErrorCode::Unknown,
-            format!(
-                "[DST]父目录: {} 创建失败\n\tErrorMsg: {}",
-                dst_parent.display(),
-                e
-            ),
-        )),
-    }
-}
-
-/// 为检查src是否是损坏符号链接进行错误处理
-pub fn handle_mklink_pre_check_error_for_src(res: Result<(), MyError>) -> Result<(), MyError> {
-    if let Some(e) = res.err() {
-        match e.code {
-            ErrorCode::FileNotExist => Ok(()),
-            ErrorCode::TargetExistsAndNotLink => Ok(()),
-            ErrorCode::TargetLinkExists => Ok(()),
-            _ => Err(e),
-        }
-    } else {
-        Ok(())
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use crate::types::link_task_args::LinkTaskArgsBuilder;
@@ -362,4 +362,36 @@ mod tests {
         let dst = parse_args_dst(&args).unwrap();
         assert_eq!(dst, dst_dir.join("srcdir"));
     }
+
+    #[test]
+    /// 7. src、dst中的环境变量被展开，dst的目录倾向在展开后判断
+    fn test_expand_env_in_src_dst() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), b"test").unwrap();
+        std::env::set_var("FASTLINK_TEST_PRE_DIR", dir.path());
+        let args = LinkTaskArgsBuilder::new("$FASTLINK_TEST_PRE_DIR/a.txt".into())
+            .dst("${FASTLINK_TEST_PRE_UNSET:-$FASTLINK_TEST_PRE_DIR/out/}")
+            .build();
+        let dst = parse_args_dst(&args).unwrap();
+        assert_eq!(dst, dir.path().join("out").join("a.txt"));
+        assert!(crate::types::link_task_pre::check_src(&args).is_ok());
+    }
+
+    #[test]
+    /// 8. 父目录不存在且允许创建时，解析dst不创建任何目录
+    fn test_check_dst_makes_no_dirs() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), b"test").unwrap();
+        let dst = dir.path().join("out").join("sub").join("a.txt");
+        let args = LinkTaskArgsBuilder::new(dir.path().join("a.txt").to_str().unwrap().into())
+            .dst(dst.to_str().unwrap())
+            .make_dir(true)
+            .build();
+        let checked = crate::types::link_task_pre::check_dst(&args).unwrap();
+        assert_eq!(
+            checked,
+            dir.path().canonicalize().unwrap().join("out/sub/a.txt")
+        );
+        assert!(!dir.path().join("out").exists());
+    }
 }
@@ -0,0 +1,194 @@
+use crate::utils::vfs::{FsMetadata, WalkEntry};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// re匹配过程中基于元数据的过滤条件，各条件之间为与关系
+///
+/// 大小与可执行条件只作用于文件，设置后目录不会被匹配（但仍会被遍历）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct MetaFilter {
+    pub min_size: Option<u64>,     // 文件大小下限（字节，含）
+    pub max_size: Option<u64>,     // 文件大小上限（字节，含）
+    pub newer: Option<SystemTime>, // 修改时间晚于
+    pub older: Option<SystemTime>, // 修改时间早于
+    pub hidden: bool,              // 只处理隐藏的文件/目录
+    pub executable: bool,          // 只处理可执行文件
+    pub symlink: bool,             // 只处理符号链接
+    pub one_file_system: bool,     // 遍历时不跨越文件系统
+}
+
+impl MetaFilter {
+    /// 是否没有设置任何需要读取元数据的过滤条件
+    pub fn is_empty(&self) -> bool {
+        self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.newer.is_none()
+            && self.older.is_none()
+            && !self.hidden
+            && !self.executable
+            && !self.symlink
+    }
+
+    /// 判断遍历得到的路径是否满足过滤条件，无法读取修改时间的路径视为不满足时间条件
+    pub fn matches(&self, entry: &WalkEntry) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        if self.symlink && !entry.path_is_symlink {
+            return false;
+        }
+        if self.hidden && !is_hidden(entry) {
+            return false;
+        }
+
+        let metadata = &entry.metadata;
+
+        let file_only = self.min_size.is_some() || self.max_size.is_some() || self.executable;
+        if file_only && !metadata.is_file() {
+            return false;
+        }
+        if self.min_size.is_some_and(|min| metadata.len < min)
+            || self.max_size.is_some_and(|max| metadata.len > max)
+        {
+            return false;
+        }
+        if self.executable && !is_executable(&entry.path, metadata) {
+            return false;
+        }
+
+        if self.newer.is_some() || self.older.is_some() {
+            let Some(modified) = metadata.modified else {
+                return false;
+            };
+            if self.newer.is_some_and(|t| modified <= t)
+                || self.older.is_some_and(|t| modified >= t)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(windows)]
+fn is_hidden(entry: &WalkEntry) -> bool {
+    entry.metadata.hidden
+}
+
+#[cfg(not(windows))]
+fn is_hidden(entry: &WalkEntry) -> bool {
+    entry.file_name().to_string_lossy().starts_with('.')
+}
+
+#[cfg(unix)]
+fn is_executable(_path: &Path, metadata: &FsMetadata) -> bool {
+    metadata.mode & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path, _metadata: &FsMetadata) -> bool {
+    const EXECUTABLE_EXTS: [&str; 5] = ["exe", "com", "bat", "cmd", "ps1"];
+    path.extension()
+        .is_some_and(|ext| EXECUTABLE_EXTS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+/// 解析文件大小，如`1024`、`10K`、`1.5M`、`2G`，单位按1024进位，不区分大小写，可带`B`/`iB`后缀
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let lower = s.to_lowercase();
+    let num_end = lower
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(lower.len());
+    let (num, unit) = lower.split_at(num_end);
+    let num: f64 = num
+        .parse()
+        .map_err(|_| format!("无效的大小 '{}'，示例: 1024, 10K, 1.5M, 2G", s))?;
+    let multiplier: u64 = match unit.trim().trim_end_matches("ib").trim_end_matches('b') {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        "t" => 1 << 40,
+        _ => return Err(format!("无效的大小单位 '{}'，可选: K, M, G, T", unit)),
+    };
+    Ok((num * multiplier as f64) as u64)
+}
+
+/// 解析时间点，支持本地时间`2024-01-31`、`2024-01-31 12:00[:00]`，
+/// 或相对当前时间的时长`30m`、`12h`、`7d`、`2w`
+pub fn parse_time(s: &str) -> Result<SystemTime, String> {
+    let s = s.trim();
+    if let Some(duration) = parse_duration(s) {
+        return SystemTime::now()
+            .checked_sub(duration)
+            .ok_or_else(|| format!("时长过大 '{}'", s));
+    }
+
+    use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+    let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| d.and_time(Default::default()))
+        })
+        .map_err(|_| {
+            format!(
+                "无效的时间 '{}'，示例: 2024-01-31, \"2024-01-31 12:00:00\", 7d, 12h",
+                s
+            )
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(SystemTime::from)
+        .ok_or_else(|| format!("本地时区中不存在该时间 '{}'", s))
+}
+
+/// 解析`30s`、`30m`、`12h`、`7d`、`2w`形式的时长
+fn parse_duration(s: &str) -> Option<Duration> {
+    let unit = s.chars().last()?;
+    let secs = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let num: u64 = s[..s.len() - 1].parse().ok()?;
+    Some(Duration::from_secs(num.checked_mul(secs)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("10K").unwrap(), 10 * 1024);
+        assert_eq!(parse_size("1.5m").unwrap(), 1024 * 1024 * 3 / 2);
+        assert_eq!(parse_size("2GiB").unwrap(), 2 << 30);
+        assert_eq!(parse_size("3 MB").unwrap(), 3 << 20);
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("10X").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let day = parse_time("2024-01-31").unwrap();
+        let noon = parse_time("2024-01-31 12:00").unwrap();
+        assert_eq!(
+            noon.duration_since(day).unwrap(),
+            Duration::from_secs(12 * 3600)
+        );
+        assert_eq!(parse_time("2024-01-31T12:00:00").unwrap(), noon);
+
+        let week_ago = parse_time("7d").unwrap();
+        let elapsed = SystemTime::now().duration_since(week_ago).unwrap();
+        assert!(elapsed >= Duration::from_secs(7 * 24 * 3600));
+        assert!(parse_time("yesterday").is_err());
+    }
+}
@@ -1,4 +1,13 @@
+#[cfg(feature = "fastlink-config")]
+pub mod config;
 pub mod err;
+pub mod link_batch;
 pub mod link_task;
 pub mod link_task_args;
 pub mod link_task_pre;
+#[cfg(feature = "fastlink-regex")]
+pub mod meta_filter;
+#[cfg(feature = "fastlink-regex")]
+pub mod re_stream;
+#[cfg(feature = "fastlink-config")]
+pub mod saved_task;
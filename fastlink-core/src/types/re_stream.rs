@@ -0,0 +1,327 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::types::link_task::KindTally;
+use crate::types::link_task::{build_re_set, get_re_max_depth, LinkTask};
+use crate::types::link_task_args::{ReDirGranularity, ReMatchTarget};
+use crate::utils::link::mklink_as;
+use crate::utils::vfs::{walk, Walk};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Re遍历中的一个匹配项
+#[derive(Debug)]
+pub(crate) struct ReMatch {
+    /// 源路径（绝对路径）
+    pub path: PathBuf,
+    /// 相对<SRC>的路径
+    pub relative: PathBuf,
+    /// 相对[DST]的路径，展平模式下只有名称
+    pub target: PathBuf,
+    pub depth: usize,
+    pub is_dir: bool,
+}
+
+/// 按LinkTask的Re参数遍历<SRC>，逐个产出匹配项，自身不保存任何匹配结果
+///
+/// only_file/only_dir、元数据过滤与目录粒度（files不匹配目录、shallowest跳过<SRC>自身及已匹配目录的子树）在此处理
+pub(crate) struct ReWalker<'a> {
+    task: &'a LinkTask,
+    re: regex::RegexSet,
+    it: Walk<'a>,
+}
+
+impl<'a> ReWalker<'a> {
+    /// 通过task.fs遍历，MemFs上同样可以预览Re匹配结果
+    pub fn new(task: &'a LinkTask, re: regex::RegexSet, max_depth: usize) -> Self {
+        let it = walk(task.fs.as_ref(), &task.src_path)
+            .max_depth(max_depth)
+            .follow_links(task.args.re_follow_links)
+            .same_file_system(task.args.re_meta_filter.one_file_system);
+        ReWalker { task, re, it }
+    }
+
+    /// 不再遍历上一个产出的目录的子树
+    pub fn skip_current_dir(&mut self) {
+        self.it.skip_current_dir();
+    }
+}
+
+impl Iterator for ReWalker<'_> {
+    type Item = ReMatch;
+
+    fn next(&mut self) -> Option<ReMatch> {
+        let args = &self.task.args;
+        let granularity = args.re_dir_granularity;
+        loop {
+            let entry = match self.it.next()? {
+                Ok(entry) => entry,
+                Err(e) => {
+                    log::debug!("Re遍历出错，已跳过: {}", e);
+                    continue;
+                }
+            };
+            let path = entry.path.as_path();
+            let depth = entry.depth;
+            let is_file = entry.metadata.is_file();
+            let is_dir = entry.metadata.is_dir();
+
+            // 处理 only_file 和 only_dir 参数，以及元数据过滤条件
+            if (args.only_file && !is_file)
+                || (args.only_dir && !is_dir)
+                || !args.re_meta_filter.matches(&entry)
+            {
+                continue;
+            }
+            // files粒度下目录不参与匹配；shallowest粒度下<SRC>自身不参与匹配
+            if is_dir
+                && (granularity == ReDirGranularity::Files
+                    || (granularity == ReDirGranularity::Shallowest && depth == 0))
+            {
+                continue;
+            }
+            // 未跟随的符号链接只在--symlink时作为链接对象（为符号链接本身创建链接），其他类型不创建链接
+            let link_candidate = entry.path_is_symlink && args.re_meta_filter.symlink;
+            if !(is_file || is_dir || link_candidate) {
+                continue;
+            }
+
+            let path_str = match args.re_match_target {
+                ReMatchTarget::Absolute => path.to_string_lossy(),
+                ReMatchTarget::Relative if depth > 0 => path
+                    .strip_prefix(&self.task.src_path)
+                    .unwrap_or(path)
+                    .to_string_lossy(),
+                // Name，或Relative下的<SRC>自身
+                _ => entry.file_name().to_string_lossy(),
+            };
+            if !self.re.is_match(&path_str) {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(&self.task.src_path) else {
+                continue;
+            };
+            let target = if args.re_output_flatten {
+                // 展平模式：仅使用文件名
+                match path.file_name() {
+                    Some(name) => PathBuf::from(name),
+                    None => {
+                        log::warn!("无法解析文件名称，已跳过: {}", path.display());
+                        continue;
+                    }
+                }
+            } else {
+                // 镜像模式：保留相对路径
+                relative.to_path_buf()
+            };
+            let item = ReMatch {
+                path: path.to_path_buf(),
+                relative: relative.to_path_buf(),
+                target,
+                depth,
+                is_dir,
+            };
+            // 首个匹配的目录直接创建符号链接，其子树不再遍历
+            if is_dir && granularity == ReDirGranularity::Shallowest {
+                self.it.skip_current_dir();
+            }
+            return Some(item);
+        }
+    }
+}
+
+/// 流式的Re匹配结果，逐个产出将创建的(<SRC>, [DST])绝对路径对，见[`LinkTask::plan_stream`]
+pub struct PlanStream<'a> {
+    walker: ReWalker<'a>,
+    dst_path: &'a Path,
+    /// deepest粒度下匹配目录的最大深度，只有该深度的目录创建符号链接
+    deepest: Option<usize>,
+}
+
+impl Iterator for PlanStream<'_> {
+    type Item = (PathBuf, PathBuf);
+
+    fn next(&mut self) -> Option<(PathBuf, PathBuf)> {
+        loop {
+            let item = self.walker.next()?;
+            if item.is_dir {
+                if let Some(deepest) = self.deepest {
+                    if item.depth != deepest {
+                        continue;
+                    }
+                    // 目录链接已包含其中的内容，不再单独链接子树中的文件
+                    self.walker.skip_current_dir();
+                }
+            }
+            return Some((item.path, self.dst_path.join(item.target)));
+        }
+    }
+}
+
+/// 布隆过滤器的位数，固定占用8MiB
+const BLOOM_BITS: u64 = 1 << 26;
+/// 每个元素使用的哈希函数个数
+const BLOOM_HASHES: u64 = 4;
+
+/// 固定大小的布隆过滤器，用于展平模式下的重复名称检测，内存占用不随路径数量增长
+///
+/// 只会误报不会漏报：报告可能重复的名称再由一次遍历精确确认
+struct BloomFilter {
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    fn new() -> Self {
+        BloomFilter {
+            bits: vec![0; (BLOOM_BITS / 64) as usize],
+        }
+    }
+
+    /// 插入item，返回其是否可能已经插入过
+    fn insert(&mut self, item: &Path) -> bool {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let h1 = hasher.finish();
+        // 双重哈希：第二个哈希为奇数，保证各位置不同
+        h1.hash(&mut hasher);
+        let h2 = hasher.finish() | 1;
+
+        let mut seen = true;
+        for i in 0..BLOOM_HASHES {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % BLOOM_BITS;
+            let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+            if self.bits[word] & mask == 0 {
+                seen = false;
+                self.bits[word] |= mask;
+            }
+        }
+        seen
+    }
+}
+
+/// 由重复的目标路径及其对应的源路径构建错误
+pub(crate) fn duplicate_targets_error<'a>(
+    duplicates: impl IntoIterator<Item = (&'a PathBuf, &'a Vec<PathBuf>)>,
+) -> MyError {
+    let mut error_msg = String::from("检测到重复目标路径，无法创建链接：\n");
+    for (target_path, src_paths) in duplicates {
+        error_msg.push_str(&format!(
+            "目标路径 '{}' 对应以下源路径：\n{}\n",
+            target_path.display(),
+            src_paths
+                .iter()
+                .map(|p| format!("  - {}", p.display()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+    MyError::new(ErrorCode::DuplicateTarget, error_msg)
+}
+
+impl LinkTask {
+    /// 流式应用Re匹配：返回逐个产出路径对的迭代器，不收集匹配结果，内存占用不随目录树大小增长
+    ///
+    /// deepest粒度、展平模式或指定within时，产出之前额外遍历一次<SRC>，同时确定匹配目录的最大深度、
+    /// 检查每个路径对是否位于within内、以固定大小的布隆过滤器检查展平后的重复名称
+    /// （有疑似重复时再遍历一次精确确认）。deepest粒度下这一遍尚不知道最大深度，检查的是
+    /// 将产出路径对的超集（较浅的匹配目录与目录链接子树中的匹配项），只会更严格；
+    /// 产出时目录链接的子树不再单独产出
+    pub fn plan_stream(&self) -> MyResult<PlanStream<'_>> {
+        let re = build_re_set(&self.args.re_patterns, self.args.re_ignore_case)?;
+        let max_depth = get_re_max_depth(self.args.make_dir, self.args.re_max_depth);
+        let stream = |deepest: Option<usize>| PlanStream {
+            walker: ReWalker::new(self, re.clone(), max_depth),
+            dst_path: &self.dst_path,
+            deepest,
+        };
+
+        let want_deepest = self.args.re_dir_granularity == ReDirGranularity::Deepest;
+        let root = self.within_root()?;
+        if !want_deepest && root.is_none() && !self.args.re_output_flatten {
+            return Ok(stream(None));
+        }
+        let mut deepest = None;
+        let mut filter = self.args.re_output_flatten.then(BloomFilter::new);
+        let mut candidates: HashSet<PathBuf> = HashSet::new();
+        for item in ReWalker::new(self, re.clone(), max_depth) {
+            if want_deepest && item.is_dir {
+                deepest = deepest.max(Some(item.depth));
+            }
+            let dst = self.dst_path.join(&item.target);
+            if let Some(root) = root.as_deref() {
+                self.check_pair_within(root, &item.path, &dst)?;
+            }
+            if let Some(filter) = filter.as_mut() {
+                if filter.insert(&dst) {
+                    candidates.insert(dst);
+                }
+            }
+        }
+        if !candidates.is_empty() {
+            log::debug!(
+                "展平模式下有{}个疑似重复的名称，再次遍历确认",
+                candidates.len()
+            );
+            let mut sources: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+            for (src, dst) in stream(deepest).filter(|(_, dst)| candidates.contains(dst)) {
+                sources.entry(dst).or_default().push(src);
+            }
+            let mut duplicates: Vec<_> = sources
+                .iter()
+                .filter(|(_, paths)| paths.len() > 1)
+                .collect();
+            if !duplicates.is_empty() {
+                duplicates.sort();
+                return Err(duplicate_targets_error(duplicates));
+            }
+        }
+        Ok(stream(deepest))
+    }
+
+    /// 流式Re匹配不收集匹配结果，无法在创建前分页确认，因此创建链接时需要同时指定re_no_check
+    pub(crate) fn check_stream_confirm(&self) -> MyResult<()> {
+        if self.args.re_stream && !self.args.re_patterns.is_empty() && !self.args.re_no_check {
+            return Err(MyError::new(
+                ErrorCode::InvalidInput,
+                "--re-stream无法在创建前分页确认匹配结果，请同时指定--re-no-check".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 边遍历边创建链接，不分页确认（已由check_stream_confirm要求re_no_check），
+    /// 需要的父目录在创建链接前按需创建（需要make_dir）
+    pub(crate) fn _mklinks_re_stream(&self) -> MyResult<bool> {
+        let stream = self.plan_stream()?;
+        log::info!("开始流式创建符号链接");
+        let mut last_parent: Option<PathBuf> = None;
+        let mut tally = KindTally::default();
+        for (src, dst) in stream {
+            if let Some(parent) = dst.parent() {
+                // 同一目录下的路径通常连续产出，只在目录变化时检查
+                if last_parent.as_deref() != Some(parent) {
+                    if self.args.make_dir && !self.fs.exists(parent) {
+                        self.fs.create_dir_all(parent).map_err(|e| {
+                            MyError::new(ErrorCode::FailToMakeDir, format!("{}", e))
+                        })?;
+                        log::info!("已创建目录: {}", parent.display());
+                    }
+                    last_parent = Some(parent.to_path_buf());
+                }
+            }
+            tally.add(mklink_as(
+                self.fs.as_ref(),
+                &src,
+                &dst,
+                self.args.link_options,
+            )?);
+        }
+        if tally.total() == 0 {
+            log::warn!("当前Re匹配后的路径为空");
+            return Ok(false);
+        }
+        log::info!("流式创建完成");
+        tally.log(self.args.link_options.kind);
+        Ok(true)
+    }
+}
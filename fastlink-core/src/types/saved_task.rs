@@ -0,0 +1,187 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::types::link_task_args::LinkTaskArgs;
+use crate::utils::expand::{expand_env, expand_path};
+use crate::utils::path::canonicalize_path;
+use std::path::{Path, PathBuf};
+
+/// 保存任务的目录：`<config_dir>/fastlink/tasks`，每个任务一个`<NAME>.toml`
+pub fn user_tasks_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("fastlink").join("tasks"))
+}
+
+/// 已保存的任务，用于`fastlink save`与`fastlink run`
+#[derive(Debug)]
+pub struct TaskStore {
+    dir: PathBuf,
+}
+
+impl TaskStore {
+    /// 使用用户配置目录下的任务目录
+    pub fn open() -> MyResult<Self> {
+        user_tasks_dir().map(Self::at).ok_or_else(|| {
+            MyError::new(
+                ErrorCode::FailToGetPathParent,
+                "无法确定用户配置目录".into(),
+            )
+        })
+    }
+
+    /// 使用指定的任务目录
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        TaskStore { dir: dir.into() }
+    }
+
+    pub fn path(&self, name: &str) -> MyResult<PathBuf> {
+        validate_name(name)?;
+        Ok(self.dir.join(format!("{}.toml", name)))
+    }
+
+    /// 保存任务，同名任务将被覆盖
+    ///
+    /// src、dst与within会先展开`~`与环境变量并转为绝对路径，以便在其他目录下重放
+    pub fn save(&self, name: &str, args: &LinkTaskArgs) -> MyResult<PathBuf> {
+        let path = self.path(name)?;
+        let mut args = args.clone();
+        args.src = absolutize(&args.src)?;
+        args.dst = args.dst.as_deref().map(absolutize).transpose()?;
+        if let Some(root) = args.within.as_deref() {
+            args.within = Some(canonicalize_path(expand_path(root)?));
+        }
+
+        let text = toml::to_string_pretty(&args)
+            .map_err(|e| MyError::new(ErrorCode::InvalidInput, format!("无法序列化任务: {}", e)))?;
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(&path, text))
+            .map_err(|e| {
+                MyError::new(
+                    ErrorCode::IoError,
+                    format!("保存任务{}失败: {}", path.display(), e),
+                )
+            })?;
+        Ok(path)
+    }
+
+    /// 读取已保存的任务
+    pub fn load(&self, name: &str) -> MyResult<LinkTaskArgs> {
+        let path = self.path(name)?;
+        if !path.is_file() {
+            let saved = self.list()?;
+            return Err(MyError::new(
+                ErrorCode::FileNotExist,
+                format!(
+                    "任务 '{}' 不存在，已保存的任务: {}",
+                    name,
+                    if saved.is_empty() {
+                        "(无)".to_string()
+                    } else {
+                        saved.join(", ")
+                    }
+                ),
+            ));
+        }
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            MyError::new(
+                ErrorCode::IoError,
+                format!("读取任务{}失败: {}", path.display(), e),
+            )
+        })?;
+        toml::from_str(&text).map_err(|e| {
+            MyError::new(
+                ErrorCode::InvalidInput,
+                format!("任务文件{}格式错误: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// 已保存任务的名称，按名称排序
+    pub fn list(&self) -> MyResult<Vec<String>> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| {
+            MyError::new(
+                ErrorCode::IoError,
+                format!("读取任务目录{}失败: {}", self.dir.display(), e),
+            )
+        })?;
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// 任务名只允许字母、数字、`-`、`_`与`.`，避免写到任务目录之外
+fn validate_name(name: &str) -> MyResult<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(MyError::new(
+            ErrorCode::InvalidInput,
+            format!("无效的任务名 '{}'，只允许字母、数字、-、_与.", name),
+        ))
+    }
+}
+
+/// 展开并转为绝对路径，保留结尾的路径分隔符（[DST]的目录倾向）
+fn absolutize(s: &str) -> MyResult<String> {
+    let expanded = expand_env(s)?;
+    let mut abs = canonicalize_path(Path::new(&expanded))
+        .to_string_lossy()
+        .into_owned();
+    if (expanded.ends_with('/') || expanded.ends_with('\\'))
+        && !abs.ends_with(std::path::MAIN_SEPARATOR)
+    {
+        abs.push(std::path::MAIN_SEPARATOR);
+    }
+    Ok(abs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::link_task_args::{ConflictPolicy, LinkTaskArgsBuilder};
+
+    #[test]
+    fn test_task_store_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = TaskStore::at(tmp.path().join("tasks"));
+        assert!(store.list().unwrap().is_empty());
+
+        let builder = LinkTaskArgsBuilder::new("/data/src".into())
+            .dst("out/")
+            .make_dir(true)
+            .conflict(ConflictPolicy::Skip);
+        #[cfg(feature = "fastlink-regex")]
+        let builder = builder
+            .re_pattern(regex::Regex::new(r"\.mp4$").unwrap())
+            .re_max_depth(2);
+        store.save("videos", &builder.build()).unwrap();
+
+        let args = store.load("videos").unwrap();
+        assert_eq!(args.src, canonicalize_path("/data/src").to_string_lossy());
+        assert!(args.dst.unwrap().ends_with(std::path::MAIN_SEPARATOR));
+        assert!(args.make_dir);
+        assert_eq!(args.link_options.conflict, ConflictPolicy::Skip);
+        #[cfg(feature = "fastlink-regex")]
+        {
+            assert_eq!(args.re_patterns[0].as_str(), r"\.mp4$");
+            assert_eq!(args.re_max_depth, 2);
+        }
+        assert_eq!(store.list().unwrap(), vec!["videos"]);
+
+        assert_eq!(
+            store.load("nope").unwrap_err().code,
+            ErrorCode::FileNotExist
+        );
+        assert!(store.save("../evil", &LinkTaskArgs::default()).is_err());
+    }
+}
@@ -0,0 +1,466 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::utils::expand::expand_path;
+use crate::utils::fs::{file_hash_with, get_temp_path_with, io_error};
+use crate::utils::path::canonicalize_path;
+use crate::utils::vfs::{Fs, StdFs};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 每组重复文件中保留哪一份作为原件
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupeKeep {
+    /// 修改时间最早的
+    #[default]
+    Oldest,
+    /// 修改时间最新的
+    Newest,
+    /// 路径最短的
+    Shortest,
+    /// 按传入目录的顺序，最先遍历到的
+    First,
+}
+
+impl std::str::FromStr for DedupeKeep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "oldest" => Ok(Self::Oldest),
+            "newest" => Ok(Self::Newest),
+            "shortest" => Ok(Self::Shortest),
+            "first" => Ok(Self::First),
+            _ => Err(format!(
+                "无效的保留规则 '{}'，可选: oldest, newest, shortest, first",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for DedupeKeep {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Oldest => write!(f, "oldest"),
+            Self::Newest => write!(f, "newest"),
+            Self::Shortest => write!(f, "shortest"),
+            Self::First => write!(f, "first"),
+        }
+    }
+}
+
+/// 用什么链接替换重复文件
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupeLinkKind {
+    /// 优先硬链接，跨卷等无法创建硬链接时使用符号链接
+    #[default]
+    Auto,
+    /// 只使用硬链接，无法创建时报错
+    Hard,
+    /// 只使用符号链接
+    Symlink,
+}
+
+impl std::str::FromStr for DedupeLinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "hard" => Ok(Self::Hard),
+            "symlink" | "sym" => Ok(Self::Symlink),
+            _ => Err(format!("无效的链接类型 '{}'，可选: auto, hard, symlink", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for DedupeLinkKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Hard => write!(f, "hard"),
+            Self::Symlink => write!(f, "symlink"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DedupeOptions {
+    pub keep: DedupeKeep,
+    pub link: DedupeLinkKind,
+    pub dry_run: bool,
+}
+
+/// 一组内容相同的文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DupGroup {
+    pub size: u64,
+    /// 保留的原件
+    pub keep: PathBuf,
+    /// 被（dry_run时为将被）替换为链接的副本
+    pub duplicates: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub struct DedupeReport {
+    pub groups: Vec<DupGroup>,
+    /// 已（dry_run时为可）节省的字节数
+    pub saved: u64,
+}
+
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    /// 遍历顺序，用于DedupeKeep::First
+    order: usize,
+}
+
+/// 查找`dirs`下内容相同的文件，将每组中除原件外的副本替换为指向原件的链接
+///
+/// 先按大小分组，再比较SHA-256；空文件不处理。
+/// 符号链接与已有多个硬链接的文件视为已是链接，不会被处理。
+/// 链接先在副本同目录下以临时名称创建，再重命名覆盖副本，中断时副本不会丢失
+pub fn dedupe(dirs: &[PathBuf], options: DedupeOptions) -> MyResult<DedupeReport> {
+    dedupe_with(&StdFs, dirs, options)
+}
+
+/// 同`dedupe`，通过fs访问文件系统
+pub fn dedupe_with(
+    fs: &dyn Fs,
+    dirs: &[PathBuf],
+    options: DedupeOptions,
+) -> MyResult<DedupeReport> {
+    let candidates = collect_candidates(fs, dirs)?;
+    let groups = group_duplicates(fs, candidates, options.keep)?;
+
+    let mut report = DedupeReport::default();
+    let mut failed = 0;
+    for mut group in groups {
+        let mut replaced = Vec::new();
+        for dup in std::mem::take(&mut group.duplicates) {
+            if options.dry_run {
+                log::info!("将替换: {} -> {}", dup.display(), group.keep.display());
+                replaced.push(dup);
+                continue;
+            }
+            match replace_with_link(fs, &group.keep, &dup, options.link) {
+                Ok(kind) => {
+                    log::info!(
+                        "已替换为{}: {} -> {}",
+                        kind,
+                        dup.display(),
+                        group.keep.display()
+                    );
+                    replaced.push(dup);
+                }
+                Err(e) => {
+                    e.log();
+                    failed += 1;
+                }
+            }
+        }
+        report.saved += group.size * replaced.len() as u64;
+        if !replaced.is_empty() {
+            group.duplicates = replaced;
+            report.groups.push(group);
+        }
+    }
+
+    if failed > 0 {
+        return Err(MyError::new(
+            ErrorCode::IoError,
+            format!(
+                "{}个重复文件替换失败，已节省{}",
+                failed,
+                format_size(report.saved)
+            ),
+        ));
+    }
+    Ok(report)
+}
+
+/// 收集所有目录下的普通文件（不跟随符号链接），同一文件只收集一次
+fn collect_candidates(fs: &dyn Fs, dirs: &[PathBuf]) -> MyResult<Vec<Candidate>> {
+    let mut seen = BTreeSet::new();
+    let mut candidates = Vec::new();
+    for dir in dirs {
+        let root = canonicalize_path(expand_path(dir)?);
+        if !fs.metadata(&root).is_ok_and(|m| m.is_dir()) {
+            return Err(MyError::new(
+                ErrorCode::FileNotExist,
+                format!("目录不存在 {}", root.display()),
+            ));
+        }
+        let mut files = Vec::new();
+        collect_files(fs, &root, &mut files)?;
+        files.sort();
+        for path in files {
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+            let metadata = fs
+                .symlink_metadata(&path)
+                .map_err(|e| io_error("读取元数据", &path, e))?;
+            if metadata.len == 0 {
+                continue;
+            }
+            // Windows下读取不到硬链接数，不做判断
+            if metadata.nlink.is_some_and(|n| n > 1) {
+                log::debug!("已是硬链接，已跳过: {}", path.display());
+                continue;
+            }
+            candidates.push(Candidate {
+                path,
+                size: metadata.len,
+                modified: metadata.modified.unwrap_or(SystemTime::UNIX_EPOCH),
+                order: candidates.len(),
+            });
+        }
+    }
+    Ok(candidates)
+}
+
+fn collect_files(fs: &dyn Fs, dir: &Path, files: &mut Vec<PathBuf>) -> MyResult<()> {
+    for path in fs.read_dir(dir).map_err(|e| io_error("读取目录", dir, e))? {
+        let metadata = fs
+            .symlink_metadata(&path)
+            .map_err(|e| io_error("读取元数据", &path, e))?;
+        if metadata.is_symlink() {
+            log::debug!("符号链接，已跳过: {}", path.display());
+        } else if metadata.is_dir() {
+            collect_files(fs, &path, files)?;
+        } else if metadata.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 按大小、再按SHA-256分组，每组按keep规则选出原件，结果按原件路径排序
+fn group_duplicates(
+    fs: &dyn Fs,
+    candidates: Vec<Candidate>,
+    keep: DedupeKeep,
+) -> MyResult<Vec<DupGroup>> {
+    let mut by_size: HashMap<u64, Vec<Candidate>> = HashMap::new();
+    for candidate in candidates {
+        by_size.entry(candidate.size).or_default().push(candidate);
+    }
+
+    let mut groups = Vec::new();
+    for (size, same_size) in by_size.into_iter().filter(|(_, v)| v.len() > 1) {
+        let mut by_hash: BTreeMap<[u8; 32], Vec<Candidate>> = BTreeMap::new();
+        for candidate in same_size {
+            by_hash
+                .entry(file_hash_with(fs, &candidate.path)?)
+                .or_default()
+                .push(candidate);
+        }
+        for (_, mut same) in by_hash.into_iter().filter(|(_, v)| v.len() > 1) {
+            same.sort_by(|a, b| match keep {
+                DedupeKeep::Oldest => a.modified.cmp(&b.modified).then(a.order.cmp(&b.order)),
+                DedupeKeep::Newest => b.modified.cmp(&a.modified).then(a.order.cmp(&b.order)),
+                DedupeKeep::Shortest => {
+                    let len = |c: &Candidate| c.path.as_os_str().len();
+                    len(a).cmp(&len(b)).then(a.order.cmp(&b.order))
+                }
+                DedupeKeep::First => a.order.cmp(&b.order),
+            });
+            let mut paths = same.into_iter().map(|c| c.path);
+            let keep = paths.next().unwrap();
+            let mut duplicates: Vec<PathBuf> = paths.collect();
+            duplicates.sort();
+            groups.push(DupGroup {
+                size,
+                keep,
+                duplicates,
+            });
+        }
+    }
+    groups.sort_by(|a, b| a.keep.cmp(&b.keep));
+    Ok(groups)
+}
+
+/// 用指向keep的链接替换dup，返回使用的链接类型
+fn replace_with_link(
+    fs: &dyn Fs,
+    keep: &Path,
+    dup: &Path,
+    kind: DedupeLinkKind,
+) -> MyResult<&'static str> {
+    let temp = get_temp_path_with(fs, dup, "dedupe");
+    let res = match kind {
+        DedupeLinkKind::Hard => fs.hard_link(keep, &temp).map(|_| "硬链接"),
+        DedupeLinkKind::Symlink => fs.symlink(keep, &temp, false).map(|_| "符号链接"),
+        DedupeLinkKind::Auto => fs.hard_link(keep, &temp).map(|_| "硬链接").or_else(|e| {
+            log::debug!("无法创建硬链接 {}: {}，改用符号链接", dup.display(), e);
+            fs.symlink(keep, &temp, false).map(|_| "符号链接")
+        }),
+    };
+    let link_kind = res.map_err(|e| {
+        MyError::new(
+            ErrorCode::FailAtMakeLink,
+            format!(
+                "无法创建指向{}的链接 {}: {}",
+                keep.display(),
+                dup.display(),
+                e
+            ),
+        )
+    })?;
+
+    // 覆盖重命名直接替换副本，不存在路径缺失的时刻
+    fs.rename(&temp, dup).map_err(|e| {
+        let _ = fs.remove_file(&temp);
+        io_error("替换重复文件", dup, e)
+    })?;
+    Ok(link_kind)
+}
+
+/// 以1024进位格式化字节数，如`1.5M`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}B", bytes)
+    } else {
+        format!("{:.1}{}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn make_dups(root: &Path) -> (PathBuf, PathBuf) {
+        let (a, b) = (root.join("a"), root.join("b"));
+        fs::create_dir_all(a.join("sub")).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        fs::write(a.join("x.bin"), "same content").unwrap();
+        fs::write(a.join("sub/x copy.bin"), "same content").unwrap();
+        fs::write(b.join("x.bin"), "same content").unwrap();
+        // 大小相同、内容不同
+        fs::write(b.join("y.bin"), "diff content").unwrap();
+        fs::write(b.join("empty"), "").unwrap();
+        (a, b)
+    }
+
+    fn is_hard_linked(path: &Path) -> bool {
+        StdFs
+            .symlink_metadata(path)
+            .unwrap()
+            .nlink
+            .is_some_and(|n| n > 1)
+    }
+
+    #[test]
+    fn test_dedupe_dry_run_and_replace() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (a, b) = make_dups(tmp.path());
+        let options = DedupeOptions {
+            keep: DedupeKeep::First,
+            ..Default::default()
+        };
+
+        let report = dedupe(
+            &[a.clone(), b.clone()],
+            DedupeOptions {
+                dry_run: true,
+                ..options
+            },
+        )
+        .unwrap();
+        assert_eq!(report.saved, 2 * 12);
+        assert_eq!(
+            report.groups,
+            vec![DupGroup {
+                size: 12,
+                keep: a.join("sub/x copy.bin"),
+                duplicates: vec![a.join("x.bin"), b.join("x.bin")],
+            }]
+        );
+        assert!(!is_hard_linked(&a.join("x.bin")));
+
+        let report = dedupe(&[a.clone(), b.clone()], options).unwrap();
+        assert_eq!(report.saved, 2 * 12);
+        assert_eq!(fs::read_to_string(b.join("x.bin")).unwrap(), "same content");
+        assert_eq!(fs::read_to_string(b.join("y.bin")).unwrap(), "diff content");
+        #[cfg(unix)]
+        {
+            assert!(is_hard_linked(&b.join("x.bin")));
+            // 已是链接的文件不再处理
+            let report = dedupe(&[a, b], options).unwrap();
+            assert!(report.groups.is_empty());
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_dedupe_symlink_and_keep_rule() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (a, b) = make_dups(tmp.path());
+        let options = DedupeOptions {
+            keep: DedupeKeep::Shortest,
+            link: DedupeLinkKind::Symlink,
+            dry_run: false,
+        };
+
+        let report = dedupe(&[a.clone(), b.clone()], options).unwrap();
+        assert_eq!(report.groups[0].keep, a.join("x.bin"));
+        assert_eq!(fs::read_link(b.join("x.bin")).unwrap(), a.join("x.bin"));
+        assert_eq!(
+            fs::read_link(a.join("sub/x copy.bin")).unwrap(),
+            a.join("x.bin")
+        );
+        assert_eq!(fs::read_dir(&b).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(12), "12B");
+        assert_eq!(format_size(1536), "1.5K");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0G");
+    }
+
+    #[test]
+    fn test_dedupe_on_mem_fs() {
+        use crate::utils::vfs::{FsOp, MemFs};
+
+        let fs = MemFs::new();
+        fs.add_file_with("/a/x.bin", "same content")
+            .add_file_with("/b/x.bin", "same content")
+            .add_file_with("/b/y.bin", "same content")
+            .add_file_with("/b/z.bin", "diff content");
+        // 第二个副本创建硬链接失败，改用符号链接
+        fs.fail_nth(FsOp::HardLink, 2);
+        let options = DedupeOptions {
+            keep: DedupeKeep::First,
+            ..Default::default()
+        };
+
+        let report =
+            dedupe_with(&fs, &[PathBuf::from("/a"), PathBuf::from("/b")], options).unwrap();
+        assert_eq!(report.saved, 2 * 12);
+        assert_eq!(
+            fs.symlink_metadata(Path::new("/b/x.bin")).unwrap().nlink,
+            Some(2)
+        );
+        assert_eq!(
+            fs.read_link(Path::new("/b/y.bin")).unwrap(),
+            Path::new("/a/x.bin")
+        );
+        assert_eq!(
+            fs.read_dir(Path::new("/b")).unwrap().len(),
+            3,
+            "不留下临时链接"
+        );
+        assert!(fs.journal().iter().all(|op| !op.starts_with("remove")));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// 展开字符串中的`~`与环境变量占位符
+///
+/// - `~`：仅在开头且后面为路径分隔符或结尾时展开为用户主目录
+/// - `%VAR%`、`$VAR`、`${VAR}`：替换为环境变量的值，变量不存在时返回EnvVarNotFound
+/// - `${VAR:-default}`：变量不存在或为空时使用default（default中的占位符同样会被展开）
+/// - `%%`、`$$`：转义为`%`、`$`
+///
+/// 不构成占位符的`%`、`$`（如`50%`）原样保留
+pub fn expand_env(s: &str) -> MyResult<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    if let Some(after) = s.strip_prefix('~') {
+        if after.is_empty() || after.starts_with(['/', '\\']) {
+            out.push_str(&home_dir()?);
+            rest = after;
+        }
+    }
+
+    let chars: Vec<char> = rest.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('%', Some('%')) | ('$', Some('$')) => {
+                out.push(chars[i]);
+                i += 2;
+            }
+            ('%', _) => {
+                let name_len = var_name_len(&chars[i + 1..], true);
+                let end = i + 1 + name_len;
+                if name_len > 0 && chars.get(end) == Some(&'%') {
+                    let name: String = chars[i + 1..end].iter().collect();
+                    out.push_str(&get_var(&name)?);
+                    i = end + 1;
+                } else {
+                    out.push('%');
+                    i += 1;
+                }
+            }
+            ('$', Some('{')) => {
+                let close = find_closing_brace(&chars, i + 2).ok_or_else(|| {
+                    MyError::new(
+                        ErrorCode::InvalidInput,
+                        format!("'{}'中的'${{'没有对应的'}}'", s),
+                    )
+                })?;
+                let inner: String = chars[i + 2..close].iter().collect();
+                out.push_str(&expand_braced(&inner)?);
+                i = close + 1;
+            }
+            ('$', _) => {
+                let name_len = var_name_len(&chars[i + 1..], false);
+                if name_len > 0 {
+                    let name: String = chars[i + 1..i + 1 + name_len].iter().collect();
+                    out.push_str(&get_var(&name)?);
+                    i += 1 + name_len;
+                } else {
+                    out.push('$');
+                    i += 1;
+                }
+            }
+            (c, _) => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// `expand_env`的Path版本，无法以UTF-8表示的路径原样返回
+pub fn expand_path(path: &Path) -> MyResult<PathBuf> {
+    match path.to_str() {
+        Some(s) => expand_env(s).map(PathBuf::from),
+        None => Ok(path.to_path_buf()),
+    }
+}
+
+/// 展开`${...}`的内部，支持`VAR`与`VAR:-default`
+fn expand_braced(inner: &str) -> MyResult<String> {
+    let (name, default) = match inner.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (inner, None),
+    };
+    let chars: Vec<char> = name.chars().collect();
+    if chars.is_empty() || var_name_len(&chars, false) != chars.len() {
+        return Err(MyError::new(
+            ErrorCode::InvalidInput,
+            format!("无效的环境变量名称 '{}'", name),
+        ));
+    }
+
+    match (env::var(name), default) {
+        (Ok(value), Some(default)) if value.is_empty() => expand_env(default),
+        (Ok(value), _) => Ok(value),
+        (Err(_), Some(default)) => expand_env(default),
+        (Err(_), None) => Err(env_var_not_found(name)),
+    }
+}
+
+fn get_var(name: &str) -> MyResult<String> {
+    env::var(name).map_err(|_| env_var_not_found(name))
+}
+
+fn env_var_not_found(name: &str) -> MyError {
+    MyError::new(
+        ErrorCode::EnvVarNotFound,
+        format!(
+            "环境变量 {} 不存在，可使用${{{}:-默认值}}指定默认值，或使用%%、$$转义",
+            name, name
+        ),
+    )
+}
+
+/// 变量名由字母、数字与`_`组成，Windows风格还允许`(`、`)`（如`%ProgramFiles(x86)%`）
+fn var_name_len(chars: &[char], windows_style: bool) -> usize {
+    chars
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || **c == '_' || (windows_style && "()".contains(**c)))
+        .count()
+}
+
+/// 从start开始查找与`${`对应的`}`，允许default中嵌套`${...}`
+fn find_closing_brace(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+fn home_dir() -> MyResult<String> {
+    #[cfg(windows)]
+    const HOME_VARS: [&str; 2] = ["USERPROFILE", "HOME"];
+    #[cfg(not(windows))]
+    const HOME_VARS: [&str; 2] = ["HOME", "USERPROFILE"];
+
+    HOME_VARS
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|v| !v.is_empty()))
+        .ok_or_else(|| {
+            MyError::new(
+                ErrorCode::EnvVarNotFound,
+                "无法展开'~'，环境变量 HOME/USERPROFILE 不存在".into(),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_env() {
+        env::set_var("FASTLINK_TEST_VAR", "value");
+        env::set_var("FASTLINK_TEST_EMPTY", "");
+        env::remove_var("FASTLINK_TEST_UNSET");
+
+        assert_eq!(
+            expand_env("C:\\%FASTLINK_TEST_VAR%\\a").unwrap(),
+            "C:\\value\\a"
+        );
+        assert_eq!(expand_env("/$FASTLINK_TEST_VAR/a").unwrap(), "/value/a");
+        assert_eq!(expand_env("/${FASTLINK_TEST_VAR}x").unwrap(), "/valuex");
+        assert_eq!(
+            expand_env("${FASTLINK_TEST_UNSET:-/d/${FASTLINK_TEST_VAR}}").unwrap(),
+            "/d/value"
+        );
+        assert_eq!(expand_env("${FASTLINK_TEST_EMPTY:-d}").unwrap(), "d");
+        assert_eq!(
+            expand_env("100%% $$HOME 50% a$").unwrap(),
+            "100% $HOME 50% a$"
+        );
+
+        let e = expand_env("$FASTLINK_TEST_UNSET/a").unwrap_err();
+        assert_eq!(e.code, ErrorCode::EnvVarNotFound);
+        assert!(e.msg.contains("FASTLINK_TEST_UNSET"));
+        assert_eq!(
+            expand_env("${FASTLINK_TEST_VAR").unwrap_err().code,
+            ErrorCode::InvalidInput
+        );
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = home_dir().unwrap();
+        assert_eq!(expand_env("~").unwrap(), home);
+        assert_eq!(expand_env("~/a").unwrap(), format!("{home}/a"));
+        assert_eq!(expand_env("~a/b").unwrap(), "~a/b");
+        assert_eq!(expand_env("a/~").unwrap(), "a/~");
+    }
+}
@@ -1,5 +1,8 @@
-use crate::types::err::{ErrorCode, MyError};
-use std::path::Path;
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::types::link_task_args::TargetKind;
+use crate::utils::link::create_symlink_as;
+use crate::utils::vfs::{Fs, StdFs};
+use std::path::{Path, PathBuf};
 
 /// 创建目录
 pub fn mkdirs<P: AsRef<Path>>(path: P) -> Result<(), MyError> {
@@ -30,3 +33,213 @@ pub fn mk_parents(path: &Path) -> Result<bool, MyError> {
         Ok(false)
     }
 }
+
+/// 得到与输入路径同目录且尚不存在的临时路径，形如`name_fastlink_{tag}_temp_1`
+pub fn get_temp_path(path: &Path, tag: &str) -> PathBuf {
+    get_temp_path_with(&StdFs, path, tag)
+}
+
+/// 同`get_temp_path`，通过fs访问文件系统
+pub fn get_temp_path_with(fs: &dyn Fs, path: &Path, tag: &str) -> PathBuf {
+    let mut temp_path = path.to_path_buf();
+    let ori_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    let mut cnt: u32 = 0;
+    loop {
+        cnt += 1;
+        temp_path.set_file_name(format!("{ori_name}_fastlink_{tag}_temp_{cnt}"));
+        if fs.symlink_metadata(&temp_path).is_err() {
+            break temp_path;
+        }
+    }
+}
+
+/// 递归复制文件/目录，返回复制的文件总字节数
+///
+/// 符号链接按链接本身复制（不跟随），文件与目录保留权限与修改时间
+pub fn copy_all(src: &Path, dst: &Path) -> MyResult<u64> {
+    copy_all_with(&StdFs, src, dst)
+}
+
+/// 同`copy_all`，通过fs访问文件系统
+pub fn copy_all_with(fs: &dyn Fs, src: &Path, dst: &Path) -> MyResult<u64> {
+    let metadata = fs
+        .symlink_metadata(src)
+        .map_err(|e| io_error("读取元数据", src, e))?;
+
+    if metadata.is_symlink() {
+        let target = fs
+            .read_link(src)
+            .map_err(|e| io_error("读取符号链接", src, e))?;
+        let kind = if fs.metadata(src).is_ok_and(|m| m.is_dir()) {
+            TargetKind::Dir
+        } else {
+            TargetKind::File
+        };
+        create_symlink_as(fs, &target, dst, kind)?;
+        Ok(0)
+    } else if metadata.is_dir() {
+        fs.create_dir(dst)
+            .map_err(|e| io_error("创建目录", dst, e))?;
+        let mut total = 0;
+        for child in fs.read_dir(src).map_err(|e| io_error("读取目录", src, e))? {
+            let Some(name) = child.file_name() else {
+                continue;
+            };
+            total += copy_all_with(fs, &child, &dst.join(name))?;
+        }
+        // 写入子项会更新目录的修改时间，复制完子项后再设置
+        if let Some(modified) = metadata.modified {
+            if let Err(e) = fs.set_modified(dst, modified) {
+                log::warn!("保留修改时间失败 {}: {}", dst.display(), e);
+            }
+        }
+        // 最后设置权限，避免只读目录无法写入子项
+        fs.copy_permissions(src, dst)
+            .map_err(|e| io_error("设置权限", dst, e))?;
+        Ok(total)
+    } else {
+        let len = fs
+            .copy_file(src, dst)
+            .map_err(|e| io_error("复制文件", src, e))?;
+        if let Some(modified) = metadata.modified {
+            if let Err(e) = fs.set_modified(dst, modified) {
+                log::warn!("保留修改时间失败 {}: {}", dst.display(), e);
+            }
+        }
+        Ok(len)
+    }
+}
+
+/// 校验复制结果：目录结构与文件大小一致，`hash`为true时还会比较文件内容的SHA-256
+pub fn verify_copy(src: &Path, dst: &Path, hash: bool) -> MyResult<()> {
+    verify_copy_with(&StdFs, src, dst, hash)
+}
+
+/// 同`verify_copy`，通过fs访问文件系统
+pub fn verify_copy_with(fs: &dyn Fs, src: &Path, dst: &Path, hash: bool) -> MyResult<()> {
+    let src_meta = fs
+        .symlink_metadata(src)
+        .map_err(|e| io_error("读取元数据", src, e))?;
+    let dst_meta = fs.symlink_metadata(dst).map_err(|e| {
+        MyError::new(
+            ErrorCode::VerifyFailed,
+            format!("复制结果中缺少 {}: {}", dst.display(), e),
+        )
+    })?;
+    let mismatch = |reason: &str| {
+        MyError::new(
+            ErrorCode::VerifyFailed,
+            format!("{}与{}{}", src.display(), dst.display(), reason),
+        )
+    };
+
+    if src_meta.is_symlink() {
+        if !dst_meta.is_symlink() || fs.read_link(src).ok() != fs.read_link(dst).ok() {
+            return Err(mismatch("的符号链接不一致"));
+        }
+    } else if src_meta.is_dir() {
+        if !dst_meta.is_dir() {
+            return Err(mismatch("的类型不一致"));
+        }
+        let children = fs.read_dir(src).map_err(|e| io_error("读取目录", src, e))?;
+        for child in children.iter() {
+            let Some(name) = child.file_name() else {
+                continue;
+            };
+            verify_copy_with(fs, child, &dst.join(name), hash)?;
+        }
+        let dst_cnt = fs
+            .read_dir(dst)
+            .map_err(|e| io_error("读取目录", dst, e))?
+            .len();
+        if children.len() != dst_cnt {
+            return Err(mismatch("的目录项数量不一致"));
+        }
+    } else {
+        if !dst_meta.is_file() {
+            return Err(mismatch("的类型不一致"));
+        }
+        if src_meta.len != dst_meta.len {
+            return Err(mismatch("的大小不一致"));
+        }
+        if hash && file_hash_with(fs, src)? != file_hash_with(fs, dst)? {
+            return Err(mismatch("的内容哈希不一致"));
+        }
+    }
+    Ok(())
+}
+
+/// 计算文件内容的SHA-256
+pub fn file_hash(path: &Path) -> MyResult<[u8; 32]> {
+    file_hash_with(&StdFs, path)
+}
+
+/// 同`file_hash`，通过fs访问文件系统
+pub fn file_hash_with(fs: &dyn Fs, path: &Path) -> MyResult<[u8; 32]> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = fs.open(path).map_err(|e| io_error("打开文件", path, e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| io_error("读取文件", path, e))?;
+    Ok(hasher.finalize().into())
+}
+
+/// 删除文件/目录（不跟随符号链接），路径不存在时视为成功
+pub fn remove_all(path: &Path) -> MyResult<()> {
+    remove_all_with(&StdFs, path)
+}
+
+/// 同`remove_all`，通过fs访问文件系统
+pub fn remove_all_with(fs: &dyn Fs, path: &Path) -> MyResult<()> {
+    let metadata = match fs.symlink_metadata(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        res => res.map_err(|e| io_error("删除", path, e))?,
+    };
+    if metadata.is_dir() {
+        for child in fs
+            .read_dir(path)
+            .map_err(|e| io_error("读取目录", path, e))?
+        {
+            remove_all_with(fs, &child)?;
+        }
+        fs.remove_dir(path)
+    } else {
+        // Windows下目录符号链接需要remove_dir删除
+        fs.remove_file(path).or_else(|_| fs.remove_dir(path))
+    }
+    .map_err(|e| io_error("删除", path, e))
+}
+
+pub(crate) fn io_error(action: &str, path: &Path, e: std::io::Error) -> MyError {
+    MyError::new(
+        ErrorCode::IoError,
+        format!("{}失败 {}: {}", action, path.display(), e),
+    )
+}
+
+/// 收集路径下的所有符号链接（不跟随符号链接），路径本身是符号链接时只返回它自己
+pub fn collect_symlinks(root: &Path) -> MyResult<Vec<PathBuf>> {
+    collect_symlinks_with(&StdFs, root)
+}
+
+/// 同`collect_symlinks`，通过fs访问文件系统
+pub fn collect_symlinks_with(fs: &dyn Fs, root: &Path) -> MyResult<Vec<PathBuf>> {
+    let metadata = fs
+        .symlink_metadata(root)
+        .map_err(|e| io_error("读取元数据", root, e))?;
+    if metadata.is_symlink() {
+        return Ok(vec![root.to_path_buf()]);
+    }
+
+    let mut links = Vec::new();
+    if metadata.is_dir() {
+        for child in fs
+            .read_dir(root)
+            .map_err(|e| io_error("读取目录", root, e))?
+        {
+            links.extend(collect_symlinks_with(fs, &child)?);
+        }
+    }
+    Ok(links)
+}
@@ -1,14 +1,10 @@
 use crate::types::err::{ErrorCode, MyError};
-#[cfg(feature = "fastlink-regex")]
 use crate::utils::logs::{FILE_STYLE, PARENT_STYLE};
-use std::path::Path;
-#[cfg(feature = "fastlink-regex")]
-use std::{
-    io::{self, Write},
-    path::PathBuf,
-};
-
-#[cfg(feature = "fastlink-regex")]
+use crate::utils::path::inspect_path_with;
+use crate::utils::vfs::{Fs, StdFs};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
 const MAIN_SEPARATOR: char = std::path::MAIN_SEPARATOR;
 
 /// 创建symlink的前置检查，包含path：
@@ -19,50 +15,18 @@ const MAIN_SEPARATOR: char = std::path::MAIN_SEPARATOR;
 ///
 /// 不满足则会返回对应的ErrorCode
 /// （没有返回Ok的条件，但在后续处理FileNotExists就是Ok）
+///
+/// 新代码请使用[`crate::utils::path::inspect_path`]
 pub fn mklink_pre_check<P: AsRef<Path>>(path: P) -> Result<(), MyError> {
-    let path = path.as_ref();
-    // 读取元数据
-    match std::fs::symlink_metadata(path) {
-        // 路径存在且读取成功
-        Ok(metadata) => {
-            // 是符号链接
-            if metadata.file_type().is_symlink() {
-                // 路径元数据有效但 path.exists() == false，符号链接损坏
-                if !path.exists() {
-                    Err(MyError::new(
-                        ErrorCode::BrokenSymlink,
-                        format!("{}", path.display()),
-                    ))
-                } else {
-                    Err(MyError::new(
-                        ErrorCode::TargetLinkExists,
-                        format!("{}", path.display()),
-                    ))
-                }
-
-            // 路径存在，但不是符号链接
-            } else {
-                Err(MyError::new(
-                    ErrorCode::TargetExistsAndNotLink,
-                    format!("{}", path.display()),
-                ))
-            }
-        }
-        // 文件不存在
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(MyError::new(
-            ErrorCode::FileNotExist,
-            format!("{}", path.display()),
-        )),
-        // 其他错误，如权限问题
-        Err(e) => Err(MyError::new(
-            ErrorCode::FailToGetFileMetadata,
-            format!("无法获取路径元数据({}): {} {}", e.kind(), e, path.display(),),
-        )),
-    }
+    mklink_pre_check_with(&StdFs, path.as_ref())
+}
+
+/// 同`mklink_pre_check`，通过fs访问文件系统
+pub fn mklink_pre_check_with(fs: &dyn Fs, path: &Path) -> Result<(), MyError> {
+    Err(inspect_path_with(fs, path).into_error(path))
 }
 
-#[cfg(feature = "fastlink-regex")]
-/// 用于Re匹配后、创建连接前的检查：按页展示需要建立符号链接的路径对
+/// 用于Re匹配后（或批量创建时）、创建连接前的检查：按页展示需要建立符号链接的路径对
 pub fn display_paginated_paths(
     paths: &[(PathBuf, PathBuf)],
     page_size: usize,
@@ -151,7 +115,6 @@ pub fn display_paginated_paths(
     }
 }
 
-#[cfg(feature = "fastlink-regex")]
 /// 格式化匹配的路径对
 pub fn format_matched_paths(paths: &[(PathBuf, PathBuf)]) -> String {
     paths
@@ -1,77 +1,177 @@
 use crate::{
     types::err::{ErrorCode, MyError, MyResult},
-    utils::func::mklink_pre_check,
-};
-use std::{
-    fs,
-    path::{Path, PathBuf},
+    types::link_task_args::{BrokenSrcPolicy, ConflictPolicy, LinkKind, LinkOptions, TargetKind},
+    utils::fs::verify_copy_with,
+    utils::managed,
+    utils::path::{inspect_path_with, resolve_path_with, PathStatus},
+    utils::shortcut::{
+        is_shortcut_path, read_shortcut_with, shortcut_path, write_shortcut_with, Shortcut,
+    },
+    utils::vfs::{Fs, FsFileType, StdFs},
 };
+use path_clean::PathClean;
+use std::path::Path;
 
 /// 创建符号链接并处理错误
 /// 在dst创建，指向src
 ///
 /// ## Return
-/// - `Ok(true)` 表示成功创建
+/// - `Ok(true)` 表示成功创建，或dst已是指向src的链接
 /// - `Ok(false)` 表示跳过创建
 /// - `Err(e)` 表示出于`e`的错误无法创建
 ///
 /// ## 参数说明
-/// - `overwrite_links` 覆写已存在的link，优先级高于overwrite_broken_links
-/// - `overwrite_broken_links` 仅覆写已存在的损坏的link
-/// - `skip_exist_links` 跳过已存在的符号链接
-/// - `skip_broken_src_links` 若src是损坏的符号链接，则跳过
-/// - `allow_broken_src` （暂不生效）允许损坏的符号链接作为src
-pub fn mklink(
-    src: &PathBuf,
-    dst: &PathBuf,
-    overwrite_links: Option<bool>,
-    overwrite_broken_links: Option<bool>,
-    skip_exist_links: Option<bool>,
-    skip_broken_src_links: Option<bool>,
-    allow_broken_src: Option<bool>,
+/// - `options.conflict` dst已存在符号链接时的处理方式，见[`ConflictPolicy`]
+/// - `options.broken_src` src是损坏的符号链接时的处理方式，见[`BrokenSrcPolicy`]
+/// - `options.kind` 创建的链接类型，创建失败时依次尝试`options.fallback`，见[`LinkKind`]
+pub fn mklink(src: &Path, dst: &Path, options: LinkOptions) -> Result<bool, MyError> {
+    mklink_with(&StdFs, src, dst, options)
+}
+
+/// 同`mklink`，通过fs访问文件系统
+pub fn mklink_with(
+    fs: &dyn Fs,
+    src: &Path,
+    dst: &Path,
+    options: LinkOptions,
 ) -> Result<bool, MyError> {
-    let overwrite_links = overwrite_links.unwrap_or(false);
-    let overwrite_broken_links = overwrite_broken_links.unwrap_or(true);
-    let skip_exist_links = skip_exist_links.unwrap_or(false);
-    let skip_broken_src_links = skip_broken_src_links.unwrap_or(true);
-    let allow_broken_src = allow_broken_src.unwrap_or(false);
+    mklink_as(fs, src, dst, options).map(|outcome| outcome != LinkOutcome::Skipped)
+}
+
+/// 创建链接的结果，见[`mklink_as`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkOutcome {
+    /// 新创建了该类型的链接
+    Created(LinkKind),
+    /// dst已是指向src的该类型链接（如重复执行同一命令），无需创建
+    Exists(LinkKind),
+    /// 按选项跳过创建
+    Skipped,
+}
 
+/// 同`mklink_with`，返回实际使用的链接类型，以及链接是新创建的还是已存在
+///
+/// 依次尝试`options.kind`与`options.fallback`中的类型，只有权限不足或系统/文件系统不支持该链接类型
+/// （如跨卷硬链接、为目录创建硬链接）时尝试下一个，dst已存在等其他错误直接返回
+pub fn mklink_as(
+    fs: &dyn Fs,
+    src: &Path,
+    dst: &Path,
+    options: LinkOptions,
+) -> MyResult<LinkOutcome> {
     // 检查src
-    let res = mklink_pre_check(src);
-    match crate::types::link_task_pre::handle_mklink_pre_check_error_for_src(res) {
-        Ok(_) => Ok(()),
-        Err(e) if e.code == ErrorCode::BrokenSymlink => {
-            if allow_broken_src {
+    match inspect_path_with(fs, src) {
+        PathStatus::BrokenSymlink { .. } => match options.broken_src {
+            BrokenSrcPolicy::Allow => {
                 log::warn!("将使用损坏的符号链接作为src: {}", src.display());
-                Ok(())
-            } else if skip_broken_src_links {
+            }
+            BrokenSrcPolicy::Skip => {
                 log::warn!("src为损坏的符号链接，已跳过: {}", src.display());
-                return Ok(false);
+                return Ok(LinkOutcome::Skipped);
+            }
+            BrokenSrcPolicy::Error => {
+                return Err(MyError::new(
+                    ErrorCode::BrokenSymlink,
+                    format!("{}", src.display()),
+                ));
+            }
+        },
+        PathStatus::Missing => {
+            if options.broken_src == BrokenSrcPolicy::Allow {
+                log::warn!("src不存在，将创建悬空的符号链接: {}", src.display());
             } else {
-                Err(e)
+                return Err(MyError::new(
+                    ErrorCode::FileNotExist,
+                    format!("src不存在 {}", src.display()),
+                ));
             }
         }
-        e => e,
-    }?;
+        PathStatus::Inaccessible(e) => return Err(e),
+        _ => (),
+    };
+    let kinds: Vec<LinkKind> = options.kind_chain().collect();
 
+    // 重复执行同一命令时，之前回退创建的硬链接/副本视为已完成
+    if kinds.contains(&LinkKind::Hardlink) || kinds.contains(&LinkKind::Copy) {
+        if let PathStatus::File | PathStatus::Dir = inspect_path_with(fs, dst) {
+            // 内容一致但没有fastlink标记的（如用户自行复制的备份）不视为已完成
+            let kind = copy_kind_of(fs, src, dst).filter(|kind| kinds.contains(kind));
+            if let Some(kind) = kind.filter(|_| managed::is_managed(fs, dst)) {
+                log::info!(
+                    "{}已存在且与<SRC>一致，无需创建: '{}' -> '{}'",
+                    kind.label(),
+                    dst.display(),
+                    src.display()
+                );
+                return Ok(LinkOutcome::Exists(kind));
+            }
+        }
+    }
+
+    for (i, &kind) in kinds.iter().enumerate() {
+        let res = match kind {
+            LinkKind::Symlink => mksymlink(fs, src, dst, options),
+            LinkKind::Shortcut => mkshortcut(fs, src, dst, options),
+            LinkKind::Hardlink | LinkKind::Copy => mkcopy(fs, src, dst, options, kind),
+        };
+        match res {
+            Err(e)
+                if matches!(e.code, ErrorCode::PermissionDenied | ErrorCode::Unsupported)
+                    && i + 1 < kinds.len() =>
+            {
+                log::warn!(
+                    "无法创建{}，尝试{}: {}",
+                    kind.label(),
+                    kinds[i + 1].label(),
+                    e.msg
+                );
+            }
+            res => {
+                let outcome = res?;
+                if outcome == LinkOutcome::Created(kind) && i > 0 {
+                    log::warn!(
+                        "已回退为{}: '{}' -> '{}'",
+                        kind.label(),
+                        dst.display(),
+                        src.display()
+                    );
+                }
+                return Ok(outcome);
+            }
+        }
+    }
+    unreachable!("kind_chain至少包含options.kind")
+}
+
+/// 在dst创建指向src的符号链接，src已由mklink_as检查
+fn mksymlink(fs: &dyn Fs, src: &Path, dst: &Path, options: LinkOptions) -> MyResult<LinkOutcome> {
     // 检查dst
-    let res = mklink_pre_check(dst);
-    match handle_mklink_pre_check_error_for_dst(
-        res,
-        dst,
-        overwrite_links,
-        overwrite_broken_links,
-        skip_exist_links,
-        allow_broken_src,
-    ) {
+    let status = inspect_path_with(fs, dst);
+    if let PathStatus::Symlink { target, .. } | PathStatus::BrokenSymlink { target } = &status {
+        // 重复执行同一命令时，已指向src的链接视为已完成，并补上fastlink的标记
+        if link_points_to(fs, dst, target, src) {
+            log::info!(
+                "符号链接已存在且指向<SRC>，无需创建: '{}' -> '{}'",
+                dst.display(),
+                src.display()
+            );
+            mark_managed(fs, dst, src);
+            return Ok(LinkOutcome::Exists(LinkKind::Symlink));
+        }
+    }
+    if replace_link_atomic(fs, &status, src, dst, options) {
+        mark_managed(fs, dst, src);
+        return Ok(LinkOutcome::Created(LinkKind::Symlink));
+    }
+    match handle_dst_status(fs, status, src, dst, options.conflict) {
         Ok(_) => Ok(()),
-        Err(e) if e.code == ErrorCode::SkipExistingLink => return Ok(false),
+        Err(e) if e.code == ErrorCode::SkipExistingLink => return Ok(LinkOutcome::Skipped),
         e => e,
     }?;
     // 接下来能够保证dst不存在（且不是已有的其他文件、不是损坏的符号链接），
-    // src是有效路径(且不是损坏符号链接)
+    // src是有效路径(或在BrokenSrcPolicy::Allow下不存在、是损坏的符号链接)
 
-    let mklink_res = create_symlink(src, dst);
+    let mklink_res = create_symlink_as(fs, src, dst, options.target_kind);
     match mklink_res {
         Ok(_) => {
             log::info!(
@@ -79,17 +179,219 @@ pub fn mklink(
                 dst.display(),
                 src.display()
             );
-            Ok(true)
+            mark_managed(fs, dst, src);
+            Ok(LinkOutcome::Created(LinkKind::Symlink))
+        }
+        res => handle_create_symlink_error(res, src, dst)
+            .map(|_| LinkOutcome::Created(LinkKind::Symlink)),
+    }
+}
+
+/// 在dst创建src的硬链接或副本（目录递归复制，其中的符号链接按原样复制），
+/// dst已存在符号链接时按`options.conflict`处理，已有的其他文件/目录不会被覆盖
+fn mkcopy(
+    fs: &dyn Fs,
+    src: &Path,
+    dst: &Path,
+    options: LinkOptions,
+    kind: LinkKind,
+) -> MyResult<LinkOutcome> {
+    match handle_dst_status(fs, inspect_path_with(fs, dst), src, dst, options.conflict) {
+        Err(e) if e.code == ErrorCode::SkipExistingLink => return Ok(LinkOutcome::Skipped),
+        res => res?,
+    }
+    let res = match kind {
+        LinkKind::Hardlink if fs.metadata(src).is_ok_and(|m| m.is_dir()) => Err(
+            std::io::Error::new(std::io::ErrorKind::Unsupported, "不能为目录创建硬链接"),
+        ),
+        LinkKind::Hardlink => fs.hard_link(src, dst),
+        _ => copy_tree_with(fs, src, dst).inspect_err(|_| {
+            // 不留下复制了一半的目录
+            let _ = remove_tree_with(fs, dst);
+        }),
+    };
+    res.map_err(|e| {
+        MyError::new(
+            link_error_code(&e),
+            format!(
+                "无法创建{} '{}' -> '{}': {}",
+                kind.label(),
+                dst.display(),
+                src.display(),
+                e
+            ),
+        )
+    })?;
+    log::info!(
+        "创建{}: 在 '{}', 来自 '{}'",
+        kind.label(),
+        dst.display(),
+        src.display()
+    );
+    mark_managed(fs, dst, src);
+    Ok(LinkOutcome::Created(kind))
+}
+
+/// 将新创建的link标记为fastlink创建，失败时只警告，不影响链接本身
+fn mark_managed(fs: &dyn Fs, link: &Path, src: &Path) {
+    if let Err(e) = managed::mark_managed(fs, link, src) {
+        log::warn!(
+            "无法标记为fastlink创建的链接，--only-managed将跳过它 {}: {}",
+            link.display(),
+            e
+        );
+    }
+}
+
+/// dst（或追加.lnk后的快捷方式）是否由fastlink创建
+pub fn is_managed_with(fs: &dyn Fs, dst: &Path) -> bool {
+    managed::is_managed(fs, dst)
+        || (shortcut_path(dst) != dst && managed::is_managed(fs, &shortcut_path(dst)))
+}
+
+/// 通过fs递归复制src（跟随src自身的符号链接），其中的符号链接按原样复制
+fn copy_tree_with(fs: &dyn Fs, src: &Path, dst: &Path) -> std::io::Result<()> {
+    if !fs.metadata(src)?.is_dir() {
+        return fs.copy_file(src, dst).map(|_| ());
+    }
+    fs.create_dir_all(dst)?;
+    for child in fs.read_dir(src)? {
+        let Some(name) = child.file_name() else {
+            continue;
+        };
+        let to = dst.join(name);
+        if fs.symlink_metadata(&child)?.is_symlink() {
+            let is_dir = fs.metadata(&child).is_ok_and(|m| m.is_dir());
+            fs.symlink(&fs.read_link(&child)?, &to, is_dir)?;
+        } else {
+            copy_tree_with(fs, &child, &to)?;
         }
-        res => handle_create_symlink_error(res, src, dst).map(|_| true),
+    }
+    Ok(())
+}
+
+/// 通过fs递归删除path（不跟随符号链接）
+fn remove_tree_with(fs: &dyn Fs, path: &Path) -> std::io::Result<()> {
+    if fs.symlink_metadata(path)?.is_dir() {
+        for child in fs.read_dir(path)? {
+            remove_tree_with(fs, &child)?;
+        }
+        fs.remove_dir(path)
+    } else {
+        // Windows下目录符号链接需要remove_dir删除
+        fs.remove_file(path).or_else(|_| fs.remove_dir(path))
     }
 }
 
+/// dst是否为src的硬链接或内容一致的副本（目录递归比较，文件比较SHA-256）
+pub fn copy_kind_of(fs: &dyn Fs, src: &Path, dst: &Path) -> Option<LinkKind> {
+    let (Ok(src_meta), Ok(dst_meta)) = (fs.metadata(src), fs.symlink_metadata(dst)) else {
+        return None;
+    };
+    if src_meta.is_file()
+        && dst_meta.is_file()
+        && fs
+            .file_id(src)
+            .ok()
+            .is_some_and(|id| fs.file_id(dst).ok() == Some(id))
+    {
+        return Some(LinkKind::Hardlink);
+    }
+    verify_copy_with(fs, src, dst, true)
+        .ok()
+        .map(|_| LinkKind::Copy)
+}
+
+/// 在dst（没有.lnk扩展名时追加）创建指向src的快捷方式，处理方式同符号链接：
+/// 已指向src时视为已完成，指向其他路径时按`options.conflict`处理（目标不存在视为损坏）
+///
+/// 覆盖时先写入临时文件再替换
+fn mkshortcut(fs: &dyn Fs, src: &Path, dst: &Path, options: LinkOptions) -> MyResult<LinkOutcome> {
+    let dst = shortcut_path(dst);
+    let src = dunce::simplified(src);
+    match inspect_path_with(fs, &dst) {
+        PathStatus::Missing => (),
+        PathStatus::File => {
+            let Ok(old) = read_shortcut_with(fs, &dst) else {
+                return Err(MyError::new(
+                    ErrorCode::TargetExistsAndNotLink,
+                    format!("无法创建快捷方式：dst存在且不是快捷方式 {}", dst.display()),
+                ));
+            };
+            let old_target = Path::new(&old.target);
+            if old_target == src {
+                log::info!(
+                    "快捷方式已存在且指向<SRC>，无需创建: '{}' -> '{}'",
+                    dst.display(),
+                    src.display()
+                );
+                mark_managed(fs, &dst, src);
+                return Ok(LinkOutcome::Exists(LinkKind::Shortcut));
+            }
+            if options.conflict == ConflictPolicy::Skip {
+                log::warn!("已跳过已存在的快捷方式: {}", dst.display());
+                return Ok(LinkOutcome::Skipped);
+            }
+            if !options.conflict.overwrite(!fs.exists(old_target)) {
+                return Err(MyError::new(
+                    ErrorCode::TargetLinkExists,
+                    format!(
+                        "{}: '{}' -> '{}'\n若需覆盖请添加参数--overwrite，需要跳过请添加参数--skip-exist",
+                        dst.display(),
+                        old.target,
+                        src.display()
+                    ),
+                ));
+            }
+        }
+        PathStatus::Inaccessible(e) => return Err(e),
+        _ => {
+            return Err(MyError::new(
+                ErrorCode::TargetExistsAndNotLink,
+                format!("无法创建快捷方式：dst存在且不是快捷方式 {}", dst.display()),
+            ))
+        }
+    }
+
+    let is_dir = match options.target_kind {
+        TargetKind::Dir => true,
+        TargetKind::File => false,
+        TargetKind::Auto => fs.metadata(src).is_ok_and(|m| m.is_dir()),
+    };
+    let shortcut = Shortcut::new(src.to_string_lossy(), is_dir);
+    let mut temp = dst.clone().into_os_string();
+    temp.push(".fastlink-tmp");
+    let temp = std::path::PathBuf::from(temp);
+    write_shortcut_with(fs, &temp, &shortcut)?;
+    // Windows与MemFs下rename不会覆盖已有文件，此时先删除旧的快捷方式
+    let res = fs.rename(&temp, &dst).or_else(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            fs.remove_file(&dst).and_then(|_| fs.rename(&temp, &dst))
+        }
+        _ => Err(e),
+    });
+    res.map_err(|e| {
+        let _ = fs.remove_file(&temp);
+        MyError::new(
+            ErrorCode::FailAtMakeLink,
+            format!("无法写入快捷方式 {}: {}", dst.display(), e),
+        )
+    })?;
+    log::info!(
+        "创建快捷方式: 在 '{}', 指向 '{}'",
+        dst.display(),
+        src.display()
+    );
+    mark_managed(fs, &dst, src);
+    Ok(LinkOutcome::Created(LinkKind::Shortcut))
+}
+
 /// 检查 overwrite 和 skip_exist_links 参数选项
 /// 覆写前删除符号链接也在此处完成
 ///
 /// 调用需要保证dst是一个已存在的符号链接（损坏与否不重要）
 fn handle_exists_link(
+    fs: &dyn Fs,
     dst: &Path,
     overwrite_links: bool,
     skip_exist_links: bool,
@@ -111,7 +413,7 @@ fn handle_exists_link(
         ))
     // 删除已存在链接
     } else if overwrite_links {
-        del_exists_link(dst, overwrite_links, None).map(|b| {
+        del_exists_link_with(fs, dst, overwrite_links, None).map(|b| {
             if log && b {
                 log::info!("删除符号链接成功: {}", dst.display());
             } else if b {
@@ -128,43 +430,190 @@ fn handle_exists_link(
     }
 }
 
-fn handle_mklink_pre_check_error_for_dst(
-    res: Result<(), MyError>,
+/// dst已存在需要覆写的符号链接时，先在同目录下以临时名称创建新链接，再rename覆盖旧链接，
+/// 避免删除与创建之间出现dst不存在的间隙
+///
+/// 返回true表示已完成替换；不需要覆写、无法原子替换（如Windows）或替换失败时返回false，
+/// 由调用者回退到先删除再创建
+fn replace_link_atomic(
+    fs: &dyn Fs,
+    status: &PathStatus,
+    src: &Path,
     dst: &Path,
-    overwrite_links: bool,
-    overwrite_broken_links: bool,
-    skip_exist_links: bool,
-    allow_broken_src: bool,
+    options: LinkOptions,
+) -> bool {
+    let overwrite = match status {
+        PathStatus::Symlink { .. } => options.conflict.overwrite(false),
+        PathStatus::BrokenSymlink { .. } => options.conflict.overwrite(true),
+        _ => false,
+    };
+    let (Some(parent), Some(name)) = (dst.parent(), dst.file_name()) else {
+        return false;
+    };
+    if !overwrite {
+        return false;
+    }
+    let tmp = parent.join(format!(
+        ".{}.fastlink-{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    if let Err(e) = create_symlink_as(fs, src, &tmp, options.target_kind) {
+        log::debug!("无法创建临时符号链接 {}: {}", tmp.display(), e);
+        return false;
+    }
+    match fs.replace_link(&tmp, dst) {
+        Ok(_) => {
+            log::info!(
+                "覆写符号链接: 在 '{}', 指向 '{}'",
+                dst.display(),
+                src.display()
+            );
+            true
+        }
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::Unsupported {
+                log::debug!("原子替换失败 {}: {}，回退为删除后创建", dst.display(), e);
+            }
+            let _ = fs.remove_file(&tmp).or_else(|_| fs.remove_dir(&tmp));
+            false
+        }
+    }
+}
+
+/// dst处已有的符号链接（指向target）是否已指向src，相对的target按dst所在目录解析
+fn link_points_to(fs: &dyn Fs, dst: &Path, target: &Path, src: &Path) -> bool {
+    let target = match dst.parent() {
+        Some(parent) if target.is_relative() => parent.join(target),
+        _ => target.to_path_buf(),
+    };
+    target.clean() == src.clean() || resolve_path_with(fs, &target) == resolve_path_with(fs, src)
+}
+
+/// 根据dst的状态决定能否创建，需要覆写时删除已有的符号链接
+///
+/// 调用前已排除指向src的符号链接，此处的符号链接均指向其他路径，冲突信息中给出`旧指向 -> 新指向`
+fn handle_dst_status(
+    fs: &dyn Fs,
+    status: PathStatus,
+    src: &Path,
+    dst: &Path,
+    conflict: ConflictPolicy,
 ) -> Result<(), MyError> {
-    if let Some(mut e) = res.err() {
-        match e.code {
-            ErrorCode::FileNotExist => Ok(()),
-            ErrorCode::TargetExistsAndNotLink => {
-                // e.log();
-                if allow_broken_src {
-                    Ok(())
-                } else {
-                    e.msg = format!("无法创建链接：dst部分存在且不是符号链接 {}", e.msg);
-                    Err(e)
+    let skip_exist_links = conflict == ConflictPolicy::Skip;
+    let conflict_msg = |old: &Path| {
+        format!(
+            "{}: '{}' -> '{}'",
+            dst.display(),
+            old.display(),
+            src.display()
+        )
+    };
+    let with_hint = |mut e: MyError, msg: String| {
+        if e.code == ErrorCode::TargetLinkExists {
+            e.msg = format!(
+                "{}\n若需覆盖请添加参数--overwrite，需要跳过请添加参数--skip-exist",
+                msg
+            );
+        }
+        e
+    };
+    match status {
+        PathStatus::Missing => Ok(()),
+        PathStatus::File | PathStatus::Dir => Err(MyError::new(
+            ErrorCode::TargetExistsAndNotLink,
+            format!("无法创建链接：dst部分存在且不是符号链接 {}", dst.display()),
+        )),
+        // 确定目标路径已存在指向其他路径的符号链接，需要考虑覆写/跳过
+        PathStatus::Symlink { target, .. } => {
+            let msg = conflict_msg(&target);
+            MyError::new(ErrorCode::TargetLinkExists, msg.clone()).warn();
+            handle_exists_link(fs, dst, conflict.overwrite(false), skip_exist_links, true)
+                .map_err(|e| with_hint(e, msg))
+        }
+        // 确定目标路径已存在且损坏的符号链接，需要考虑覆写/跳过
+        PathStatus::BrokenSymlink { target } => {
+            let msg = conflict_msg(&target);
+            MyError::new(ErrorCode::BrokenSymlink, msg.clone()).warn();
+            handle_exists_link(fs, dst, conflict.overwrite(true), skip_exist_links, true)
+                .map_err(|e| with_hint(e, msg))
+        }
+        PathStatus::Inaccessible(e) => Err(e),
+    }
+}
+
+/// 删除dst处fastlink可能创建的任意类型的链接：符号链接、快捷方式（dst或追加.lnk后的路径），
+/// 给出src时还包括fastlink创建（有链接标记）的src的硬链接与内容一致的副本，并要求快捷方式指向src；
+/// 其他文件/目录（包括用户自行复制、内容恰好与src一致的备份）不会被删除
+///
+/// only_managed为true时只删除由fastlink创建的链接，其他链接跳过并返回`Ok(false)`
+pub fn del_link_of_with(
+    fs: &dyn Fs,
+    src: Option<&Path>,
+    dst: &Path,
+    only_managed: bool,
+) -> MyResult<bool> {
+    let shortcut_of_src = |lnk: &Path| {
+        read_shortcut_with(fs, lnk).is_ok_and(|shortcut| {
+            src.is_none_or(|src| Path::new(&shortcut.target) == dunce::simplified(src))
+        })
+    };
+    let (path, kind) = match inspect_path_with(fs, dst) {
+        PathStatus::File if is_shortcut_path(dst) && shortcut_of_src(dst) => {
+            (dst.to_path_buf(), LinkKind::Shortcut)
+        }
+        PathStatus::File | PathStatus::Dir => {
+            // 硬链接/副本与普通文件无法区分，只删除fastlink标记过的，避免误删用户的备份
+            match src.and_then(|src| copy_kind_of(fs, src, dst)) {
+                Some(kind) if managed::is_managed(fs, dst) => (dst.to_path_buf(), kind),
+                Some(kind) => {
+                    return Err(MyError::new(
+                        ErrorCode::TargetNotALink,
+                        format!(
+                            "{}与<SRC>一致，但不是由fastlink创建的{}，不会删除",
+                            dst.display(),
+                            kind.label()
+                        ),
+                    ))
                 }
+                None => return del_exists_link_with(fs, dst, true, Some(false)),
             }
-            // 确定目标路径已存在符号链接，需要考虑覆写/跳过
-            ErrorCode::TargetLinkExists => {
-                e.warn();
-                handle_exists_link(dst, overwrite_links, skip_exist_links, true)
-            }
-            // 确定目标路径已存在且损坏的符号链接，需要考虑覆写/跳过
-            ErrorCode::BrokenSymlink => {
-                e.warn();
-                // 两overwrite相关参数满足其一即可
-                let cond = overwrite_links || overwrite_broken_links;
-                handle_exists_link(dst, cond, skip_exist_links, true)
-            }
-            _ => Err(e),
         }
+        PathStatus::Symlink { .. } | PathStatus::BrokenSymlink { .. } => {
+            (dst.to_path_buf(), LinkKind::Symlink)
+        }
+        PathStatus::Missing
+            if shortcut_path(dst) != dst
+                && matches!(inspect_path_with(fs, &shortcut_path(dst)), PathStatus::File)
+                && shortcut_of_src(&shortcut_path(dst)) =>
+        {
+            (shortcut_path(dst), LinkKind::Shortcut)
+        }
+        _ => return del_exists_link_with(fs, dst, true, Some(false)),
+    };
+    if only_managed && !managed::is_managed(fs, &path) {
+        log::warn!(
+            "不是由fastlink创建的{}，已跳过: {}",
+            kind.label(),
+            path.display()
+        );
+        return Ok(false);
+    }
+    if kind == LinkKind::Symlink {
+        del_exists_link_with(fs, &path, true, Some(false))?;
     } else {
-        Ok(())
+        remove_tree_with(fs, &path).map_err(|e| {
+            MyError::new(
+                ErrorCode::FailToDelLink,
+                format!("({}){}: {}", kind.label(), path.display(), e),
+            )
+        })?;
+        log::info!("已删除{}: {}", kind.label(), path.display());
     }
+    if let Err(e) = managed::unmark_managed(fs, &path) {
+        log::warn!("清除fastlink链接标记失败 {}: {}", path.display(), e);
+    }
+    Ok(true)
 }
 
 /// 删除符号链接，需要传入overwrite_links参数，避免误用
@@ -172,38 +621,50 @@ pub fn del_exists_link(
     dst: &Path,
     overwrite_links: bool,
     not_exist_ok: Option<bool>,
+) -> Result<bool, MyError> {
+    del_exists_link_with(&StdFs, dst, overwrite_links, not_exist_ok)
+}
+
+/// 同`del_exists_link`，通过fs访问文件系统
+pub fn del_exists_link_with(
+    fs: &dyn Fs,
+    dst: &Path,
+    overwrite_links: bool,
+    not_exist_ok: Option<bool>,
 ) -> Result<bool, MyError> {
     if overwrite_links {
-        // 检查是否是symlink
-        match mklink_pre_check(dst) {
-            Ok(_) => Ok(()),
-            Err(e) if e.code == ErrorCode::TargetLinkExists => Ok(()),
-            Err(e) if e.code == ErrorCode::BrokenSymlink => Ok(()),
-            Err(mut e) if e.code == ErrorCode::FileNotExist => {
+        // 检查是否是symlink，并区分符号链接类型
+        let target_kind = match inspect_path_with(fs, dst) {
+            PathStatus::Symlink { target_kind, .. } => Some(target_kind),
+            PathStatus::BrokenSymlink { .. } => None,
+            PathStatus::Missing => {
                 if not_exist_ok.unwrap_or(true) {
                     return Ok(false);
                 } else {
-                    e.msg = format!("目标不存在 {}", e.msg);
-                    Err(e)
+                    return Err(MyError::new(
+                        ErrorCode::FileNotExist,
+                        format!("目标不存在 {}", dst.display()),
+                    ));
                 }
             }
-            Err(mut e) if e.code == ErrorCode::TargetNotALink => {
-                e.msg = format!("尝试删除非符号链接路径 {}", e.msg);
-                Err(e)
+            PathStatus::File | PathStatus::Dir => {
+                return Err(MyError::new(
+                    ErrorCode::TargetNotALink,
+                    format!("尝试删除非符号链接路径 {}", dst.display()),
+                ));
             }
-            e => e,
-        }?;
-        // 区分符号链接类型
-        if dst.is_dir() {
-            fs::remove_dir(dst).map_err(|e| {
+            PathStatus::Inaccessible(e) => return Err(e),
+        };
+        if target_kind == Some(FsFileType::Dir) {
+            fs.remove_dir(dst).map_err(|e| {
                 MyError::new(
                     ErrorCode::FailToDelLink,
                     format!("(DIR) {}: {}", dst.display(), e),
                 )
             })?;
             Ok(true)
-        } else if dst.is_file() {
-            fs::remove_file(dst).map_err(|e| {
+        } else if target_kind == Some(FsFileType::File) {
+            fs.remove_file(dst).map_err(|e| {
                 MyError::new(
                     ErrorCode::FailToDelLink,
                     format!("(FILE){}: {}", dst.display(), e),
@@ -212,10 +673,10 @@ pub fn del_exists_link(
             Ok(true)
         } else {
             log::debug!("损坏的符号链接 {}, 尝试作为文件删除", dst.display());
-            let res_file = fs::remove_file(dst);
+            let res_file = fs.remove_file(dst);
             if res_file.is_err() {
                 log::debug!("删除失败: {}，尝试作为目录删除", dst.display());
-                let res_dir = fs::remove_dir(dst).map_err(|_| {
+                let res_dir = fs.remove_dir(dst).map_err(|_| {
                     MyError::new(
                         ErrorCode::FailToDelLink,
                         format!(
@@ -279,31 +740,71 @@ fn handle_create_symlink_error(
 /// 安全、智能创建符号链接（自动判断文件/目录）, 不允许src为损坏的符号链接或不存在的目录
 ///
 /// 需要简单地创建使用 `mklink_when_src_dir_not_exists` 或 `mklink_when_src_file_not_exists`
-#[cfg(windows)]
 pub fn create_symlink<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dst: Q) -> Result<(), MyError> {
-    let src = src.as_ref();
-    let dst = dst.as_ref();
+    create_symlink_with(&StdFs, src.as_ref(), dst.as_ref())
+}
 
-    // 获取源文件元数据
-    let metadata = fs::metadata(src).map_err(|e| {
-        MyError::new(
-            ErrorCode::FailToGetFileMetadata,
-            format!("无法获取源文件元数据 {}: {}", src.display(), e),
-        )
-    })?;
+/// 同`create_symlink`，通过fs访问文件系统
+pub fn create_symlink_with(fs: &dyn Fs, src: &Path, dst: &Path) -> MyResult<()> {
+    create_symlink_as(fs, src, dst, TargetKind::Auto)
+}
+
+/// 按指定的目标类型创建符号链接，src可以不存在
+///
+/// kind为`TargetKind::Auto`时由src的类型决定，Windows下src不存在（或为损坏的符号链接）则报错
+pub fn create_symlink_as(fs: &dyn Fs, src: &Path, dst: &Path, kind: TargetKind) -> MyResult<()> {
+    let is_dir = match kind {
+        TargetKind::File => false,
+        TargetKind::Dir => true,
+        // 获取源文件元数据，根据类型选择创建方式
+        TargetKind::Auto => match fs.metadata(src) {
+            Ok(metadata) => metadata.is_dir(),
+            // 非Windows下符号链接不区分文件/目录
+            Err(_) if !cfg!(windows) => false,
+            Err(e) => {
+                return Err(MyError::new(
+                    ErrorCode::FailToGetFileMetadata,
+                    format!(
+                        "无法获取源文件元数据 {}: {}，可使用--target-kind指定目标类型",
+                        src.display(),
+                        e
+                    ),
+                ))
+            }
+        },
+    };
+    let res = fs.symlink(src, dst, is_dir);
+    convert_create_symlink_res(res, src, dst)
+}
+
+/// 创建符号链接权限不足时的提示
+#[cfg(windows)]
+const PERMISSION_HINT: &str = "权限不足，请尝试使用管理员权限，或开启开发者模式";
+#[cfg(not(windows))]
+const PERMISSION_HINT: &str = "权限不足，请尝试sudo";
+
+/// 探测能否在dir中创建符号链接，见`Fs::probe_symlink`
+pub fn probe_symlink_in(fs: &dyn Fs, dir: &Path) -> MyResult<()> {
+    fs.probe_symlink(dir).map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => MyError::new(
+            ErrorCode::PermissionDenied,
+            format!("无法在{}中创建符号链接，{}", dir.display(), PERMISSION_HINT),
+        ),
+        _ => MyError::new(
+            ErrorCode::FailAtMakeLink,
+            format!("无法在{}中创建符号链接: {}", dir.display(), e),
+        ),
+    })
+}
 
-    // 根据类型选择创建方式
-    if metadata.is_file() {
-        let res = std::os::windows::fs::symlink_file(src, dst);
-        convert_create_symlink_res(res, src, dst)
-    } else if metadata.is_dir() {
-        let res = std::os::windows::fs::symlink_dir(src, dst);
-        convert_create_symlink_res(res, src, dst)
+/// 在系统临时目录中探测当前进程能否创建符号链接（如Windows下需要管理员权限或开发者模式），
+/// fs中不存在临时目录（如MemFs）时跳过
+pub fn probe_symlink_capability(fs: &dyn Fs) -> MyResult<()> {
+    let temp = std::env::temp_dir();
+    if fs.metadata(&temp).is_ok_and(|m| m.is_dir()) {
+        probe_symlink_in(fs, &temp)
     } else {
-        Err(MyError::new(
-            ErrorCode::Unknown,
-            "奇怪的错误: <SRC>既不是文件也不是目录，可能是损坏的符号链接或别的什么".into(),
-        ))
+        Ok(())
     }
 }
 
@@ -317,19 +818,13 @@ fn convert_create_symlink_res<P: AsRef<Path>, Q: AsRef<Path>>(
     let dst = dst.as_ref();
     // fn convert_create_symlink_res(res: std::io::Result<()>, src: &Path, dst: &Path) -> MyResult<()> {
     if let Err(e) = res {
-        match e.kind() {
-            #[cfg(windows)]
-            std::io::ErrorKind::PermissionDenied => Err(MyError::new(
+        match link_error_code(&e) {
+            ErrorCode::PermissionDenied => Err(MyError::new(
                 ErrorCode::PermissionDenied,
-                "权限不足，请尝试使用管理员权限，或开启开发者模式".into(),
+                PERMISSION_HINT.into(),
             )),
-            #[cfg(not(windows))]
-            std::io::ErrorKind::PermissionDenied => Err(MyError::new(
-                ErrorCode::PermissionDenied,
-                "权限不足，请尝试sudo".into(),
-            )),
-            _ => Err(MyError::new(
-                ErrorCode::FailAtMakeLink,
+            code => Err(MyError::new(
+                code,
                 format!(
                     "无法创建目录符号链接 '{}' -> '{}': {}",
                     dst.display(),
@@ -343,46 +838,349 @@ fn convert_create_symlink_res<P: AsRef<Path>, Q: AsRef<Path>>(
     }
 }
 
-#[cfg(unix)]
-pub fn create_symlink<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dst: Q) -> MyResult<()> {
-    let src = src.as_ref();
-    let dst = dst.as_ref();
-    let res = std::os::unix::fs::symlink(src, dst);
-    convert_create_symlink_res(res, src, dst)
+/// 创建链接失败时的错误码：权限不足与不支持的操作可以回退为其他链接类型，见`mklink_as`
+fn link_error_code(e: &std::io::Error) -> ErrorCode {
+    /// Windows下没有创建符号链接的权限（非管理员且未开启开发者模式）
+    #[cfg(windows)]
+    const ERROR_PRIVILEGE_NOT_HELD: i32 = 1314;
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+        std::io::ErrorKind::Unsupported | std::io::ErrorKind::CrossesDevices => {
+            ErrorCode::Unsupported
+        }
+        #[cfg(windows)]
+        _ if e.raw_os_error() == Some(ERROR_PRIVILEGE_NOT_HELD) => ErrorCode::PermissionDenied,
+        _ => ErrorCode::FailAtMakeLink,
+    }
 }
 
+/// 创建指向目录的符号链接，src可以不存在，LinkTask中见`TargetKind::Dir`
 #[inline]
-#[cfg(unix)]
 pub fn mklink_when_src_dir_not_exists<P: AsRef<Path>, Q: AsRef<Path>>(
     src: P,
     dst: Q,
 ) -> MyResult<()> {
-    create_symlink(src, dst)
+    create_symlink_as(&StdFs, src.as_ref(), dst.as_ref(), TargetKind::Dir)
 }
 
+/// 创建指向文件的符号链接，src可以不存在，LinkTask中见`TargetKind::File`
 #[inline]
-#[cfg(unix)]
 pub fn mklink_when_src_file_not_exists<P: AsRef<Path>, Q: AsRef<Path>>(
     src: P,
     dst: Q,
 ) -> MyResult<()> {
-    create_symlink(src, dst)
+    create_symlink_as(&StdFs, src.as_ref(), dst.as_ref(), TargetKind::File)
 }
 
-#[cfg(windows)]
-pub fn mklink_when_src_dir_not_exists<P: AsRef<Path>, Q: AsRef<Path>>(
-    src: P,
-    dst: Q,
-) -> MyResult<()> {
-    let res = std::os::windows::fs::symlink_dir(&src, &dst);
-    convert_create_symlink_res(res, src, dst)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::managed::tests::TestRegistry;
+    use crate::utils::shortcut::read_shortcut;
+    use crate::utils::vfs::{FsOp, MemFs};
 
-#[cfg(windows)]
-pub fn mklink_when_src_file_not_exists<P: AsRef<Path>, Q: AsRef<Path>>(
-    src: P,
-    dst: Q,
-) -> MyResult<()> {
-    let res = std::os::windows::fs::symlink_file(&src, &dst);
-    convert_create_symlink_res(res, src, dst)
+    #[test]
+    /// src不存在：默认报错，BrokenSrcPolicy::Allow时按指定的类型创建悬空链接
+    fn test_mklink_dangling_on_mem_fs() {
+        let fs = MemFs::new();
+        fs.add_dir("/links");
+        let src = Path::new("/mnt/d/games");
+        let dst = Path::new("/links/games");
+
+        let e = mklink_with(&fs, src, dst, LinkOptions::default()).unwrap_err();
+        assert_eq!(e.code, ErrorCode::FileNotExist);
+        assert!(fs.journal().is_empty());
+
+        let options = LinkOptions::new(ConflictPolicy::default(), BrokenSrcPolicy::Allow)
+            .with_target_kind(TargetKind::Dir);
+        assert!(mklink_with(&fs, src, dst, options).unwrap());
+        assert_eq!(
+            fs.journal(),
+            vec!["symlink_dir /links/games -> /mnt/d/games"]
+        );
+        assert!(matches!(
+            inspect_path_with(&fs, dst),
+            PathStatus::BrokenSymlink { .. }
+        ));
+    }
+
+    #[test]
+    /// src为损坏的符号链接：默认跳过，Error时报错
+    fn test_mklink_broken_src_policy() {
+        let fs = MemFs::new();
+        fs.add_dir("/links").add_symlink("/gone", "/broken");
+        let src = Path::new("/broken");
+        let dst = Path::new("/links/broken");
+
+        assert!(!mklink_with(&fs, src, dst, LinkOptions::default()).unwrap());
+        let options = LinkOptions::new(ConflictPolicy::default(), BrokenSrcPolicy::Error);
+        let e = mklink_with(&fs, src, dst, options).unwrap_err();
+        assert_eq!(e.code, ErrorCode::BrokenSymlink);
+
+        let options = LinkOptions::new(ConflictPolicy::default(), BrokenSrcPolicy::Allow)
+            .with_target_kind(TargetKind::File);
+        assert!(mklink_with(&fs, src, dst, options).unwrap());
+        assert_eq!(fs.journal(), vec!["symlink_file /links/broken -> /broken"]);
+    }
+
+    #[test]
+    /// 覆写已有的符号链接：临时链接rename覆盖旧链接，失败时回退为删除后创建
+    fn test_mklink_overwrite_atomic() {
+        let fs = MemFs::new();
+        fs.add_file("/data/new", 1)
+            .add_symlink("/data/old", "/links/a")
+            .add_symlink("/data/old", "/links/b");
+        let options = LinkOptions::new(ConflictPolicy::Overwrite, BrokenSrcPolicy::default());
+
+        assert!(mklink_with(&fs, Path::new("/data/new"), Path::new("/links/a"), options).unwrap());
+        let tmp = format!("/links/.a.fastlink-{}.tmp", std::process::id());
+        assert_eq!(
+            fs.journal(),
+            vec![
+                format!("symlink_file {} -> /data/new", tmp),
+                format!("replace {} -> /links/a", tmp),
+            ]
+        );
+        assert_eq!(
+            fs.read_link(Path::new("/links/a")).unwrap(),
+            Path::new("/data/new")
+        );
+
+        fs.fail_nth(FsOp::Rename, 2);
+        assert!(mklink_with(&fs, Path::new("/data/new"), Path::new("/links/b"), options).unwrap());
+        assert_eq!(
+            fs.journal()[2..],
+            [
+                format!(
+                    "symlink_file /links/.b.fastlink-{}.tmp -> /data/new",
+                    std::process::id()
+                ),
+                format!("remove /links/.b.fastlink-{}.tmp", std::process::id()),
+                "remove /links/b".to_string(),
+                "symlink_file /links/b -> /data/new".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    /// 重复执行：已指向src的链接（含相对指向）视为成功，指向其他路径才是冲突
+    fn test_mklink_idempotent() {
+        let fs = MemFs::new();
+        fs.add_file("/data/a", 1)
+            .add_file("/data/b", 1)
+            .add_symlink("/data/a", "/links/abs")
+            .add_symlink("../data/a", "/links/rel");
+        let src = Path::new("/data/a");
+
+        for dst in ["/links/abs", "/links/rel"] {
+            assert!(mklink_with(&fs, src, Path::new(dst), LinkOptions::default()).unwrap());
+        }
+        assert!(fs.journal().is_empty());
+
+        let e = mklink_with(
+            &fs,
+            Path::new("/data/b"),
+            Path::new("/links/abs"),
+            LinkOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(e.code, ErrorCode::TargetLinkExists);
+        assert!(e.msg.contains("'/data/a' -> '/data/b'"));
+        assert!(fs.journal().is_empty());
+    }
+
+    #[test]
+    /// 快捷方式：追加.lnk扩展名，重复创建视为成功，指向其他路径时按冲突处理
+    fn test_mkshortcut() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+        std::fs::create_dir(&a).unwrap();
+        std::fs::write(&b, "b").unwrap();
+        let dst = tmp.path().join("link");
+        let options = LinkOptions::default().with_kind(LinkKind::Shortcut);
+
+        assert!(mklink(&a, &dst, options).unwrap());
+        let lnk = tmp.path().join("link.lnk");
+        let shortcut = read_shortcut(&lnk).unwrap();
+        assert_eq!(Path::new(&shortcut.target), a);
+        assert!(shortcut.is_dir);
+        assert!(mklink(&a, &lnk, options).unwrap());
+
+        let e = mklink(&b, &dst, options).unwrap_err();
+        assert_eq!(e.code, ErrorCode::TargetLinkExists);
+        let skip = LinkOptions {
+            conflict: ConflictPolicy::Skip,
+            ..options
+        };
+        assert!(!mklink(&b, &dst, skip).unwrap());
+        let overwrite = LinkOptions {
+            conflict: ConflictPolicy::Overwrite,
+            ..options
+        };
+        assert!(mklink(&b, &dst, overwrite).unwrap());
+        let shortcut = read_shortcut(&lnk).unwrap();
+        assert_eq!(Path::new(&shortcut.target), b);
+        assert!(!shortcut.is_dir);
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 3);
+
+        // rm：给出[DST]（不含.lnk）时删除指向<SRC>的快捷方式
+        assert!(del_link_of_with(&StdFs, Some(&a), &dst, false).is_err());
+        assert!(del_link_of_with(&StdFs, Some(&b), &dst, false).unwrap());
+        assert!(!lnk.exists());
+
+        std::fs::write(tmp.path().join("plain.lnk"), "not a shortcut").unwrap();
+        let e = mklink(&a, &tmp.path().join("plain"), options).unwrap_err();
+        assert_eq!(e.code, ErrorCode::TargetExistsAndNotLink);
+    }
+
+    #[test]
+    /// 创建符号链接失败时按回退链逐个尝试：文件回退为硬链接，目录无法硬链接时回退为复制
+    fn test_mklink_fallback_on_mem_fs() {
+        let fs = MemFs::new();
+        fs.add_file("/data/a", 3)
+            .add_file("/data/dir/x", 1)
+            .add_symlink("x", "/data/dir/y")
+            .add_dir("/links")
+            .fail_nth_with(FsOp::Symlink, 1, std::io::ErrorKind::PermissionDenied)
+            .fail_nth_with(FsOp::Symlink, 2, std::io::ErrorKind::PermissionDenied);
+        let options = LinkOptions::default().with_fallback("hardlink,copy".parse().unwrap());
+
+        let kind = mklink_as(&fs, Path::new("/data/a"), Path::new("/links/a"), options);
+        assert_eq!(kind.unwrap(), LinkOutcome::Created(LinkKind::Hardlink));
+        let kind = mklink_as(
+            &fs,
+            Path::new("/data/dir"),
+            Path::new("/links/dir"),
+            options,
+        );
+        assert_eq!(kind.unwrap(), LinkOutcome::Created(LinkKind::Copy));
+        assert_eq!(
+            fs.journal(),
+            [
+                "hard_link /links/a -> /data/a",
+                "mkdir /links/dir",
+                "copy /links/dir/x -> /data/dir/x",
+                "symlink_file /links/dir/y -> x",
+            ]
+        );
+
+        // 权限不足、不支持以外的错误不回退（复制目录时已创建了第3个符号链接）
+        fs.fail_nth(FsOp::Symlink, 4);
+        let e = mklink_with(&fs, Path::new("/data/a"), Path::new("/links/c"), options).unwrap_err();
+        assert_eq!(e.code, ErrorCode::FailAtMakeLink);
+        assert!(!fs.exists(Path::new("/links/c")));
+
+        // 没有回退类型时直接报错
+        fs.fail_nth_with(FsOp::Symlink, 5, std::io::ErrorKind::PermissionDenied);
+        let e = mklink_with(
+            &fs,
+            Path::new("/data/a"),
+            Path::new("/links/b"),
+            LinkOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(e.code, ErrorCode::PermissionDenied);
+    }
+
+    #[test]
+    /// 重复执行时已指向src的符号链接不计为新创建，并补上fastlink的标记
+    fn test_existing_symlink_adopted() {
+        let fs = MemFs::new();
+        fs.add_file("/data/a", 1).add_symlink("/data/a", "/links/a");
+        let (src, dst) = (Path::new("/data/a"), Path::new("/links/a"));
+        assert!(!managed::is_managed(&fs, dst));
+        let outcome = mklink_as(&fs, src, dst, LinkOptions::default()).unwrap();
+        assert_eq!(outcome, LinkOutcome::Exists(LinkKind::Symlink));
+        assert!(managed::is_managed(&fs, dst));
+        assert!(fs.journal().is_empty());
+    }
+
+    #[test]
+    /// 回退创建的硬链接/副本：重复执行视为已完成，check/rm能识别，无关的文件与用户的备份不会被删除
+    fn test_copy_kind_recognized() {
+        let _registry = TestRegistry::new();
+        let tmp = tempfile::tempdir().unwrap();
+        let (src, dir) = (tmp.path().join("a"), tmp.path().join("dir"));
+        std::fs::write(&src, "data").unwrap();
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("x"), "x").unwrap();
+        let options = LinkOptions::default()
+            .with_kind(LinkKind::Hardlink)
+            .with_fallback("copy".parse().unwrap());
+
+        let (hard, copy) = (tmp.path().join("hard"), tmp.path().join("copy"));
+        assert_eq!(
+            mklink_as(&StdFs, &src, &hard, options).unwrap(),
+            LinkOutcome::Created(LinkKind::Hardlink)
+        );
+        assert_eq!(
+            mklink_as(&StdFs, &dir, &copy, options).unwrap(),
+            LinkOutcome::Created(LinkKind::Copy)
+        );
+        assert_eq!(copy_kind_of(&StdFs, &src, &hard), Some(LinkKind::Hardlink));
+        assert_eq!(copy_kind_of(&StdFs, &dir, &copy), Some(LinkKind::Copy));
+        assert_eq!(
+            mklink_as(&StdFs, &dir, &copy, options).unwrap(),
+            LinkOutcome::Exists(LinkKind::Copy)
+        );
+
+        let other = tmp.path().join("other");
+        std::fs::write(&other, "other").unwrap();
+        let e = del_link_of_with(&StdFs, Some(&src), &other, false).unwrap_err();
+        assert_eq!(e.code, ErrorCode::TargetNotALink);
+        assert!(del_link_of_with(&StdFs, None, &hard, false).is_err());
+
+        // 用户自行复制的备份（cp -r dir backup）内容一致，但没有fastlink的标记，不会被删除
+        let backup = tmp.path().join("backup");
+        std::fs::create_dir(&backup).unwrap();
+        std::fs::write(backup.join("x"), "x").unwrap();
+        assert_eq!(copy_kind_of(&StdFs, &dir, &backup), Some(LinkKind::Copy));
+        let e = del_link_of_with(&StdFs, Some(&dir), &backup, false).unwrap_err();
+        assert_eq!(e.code, ErrorCode::TargetNotALink);
+        assert!(backup.join("x").exists());
+        // 重复执行时也不会把它当作已创建的副本
+        let e = mklink_as(&StdFs, &dir, &backup, options).unwrap_err();
+        assert_eq!(e.code, ErrorCode::TargetExistsAndNotLink);
+
+        assert!(del_link_of_with(&StdFs, Some(&src), &hard, false).unwrap());
+        assert!(del_link_of_with(&StdFs, Some(&dir), &copy, false).unwrap());
+        assert!(!hard.exists() && !copy.exists());
+        assert!(src.exists() && dir.join("x").exists());
+    }
+
+    #[test]
+    /// 硬链接/副本的识别与快捷方式的读写同样通过fs完成
+    fn test_copy_kind_and_shortcut_on_mem_fs() {
+        let fs = MemFs::new();
+        fs.add_file_with("/data/a", "data")
+            .add_file_with("/data/b", "data")
+            .add_file_with("/data/c", "diff")
+            .add_dir("/links");
+        fs.hard_link(Path::new("/data/a"), Path::new("/links/a"))
+            .unwrap();
+        let (a, b, c) = (
+            Path::new("/data/a"),
+            Path::new("/data/b"),
+            Path::new("/data/c"),
+        );
+        assert_eq!(
+            copy_kind_of(&fs, a, Path::new("/links/a")),
+            Some(LinkKind::Hardlink)
+        );
+        assert_eq!(copy_kind_of(&fs, a, b), Some(LinkKind::Copy));
+        assert_eq!(copy_kind_of(&fs, a, c), None);
+
+        let options = LinkOptions::new(ConflictPolicy::Overwrite, BrokenSrcPolicy::Skip)
+            .with_kind(LinkKind::Shortcut);
+        let lnk = Path::new("/links/s.lnk");
+        assert_eq!(
+            mklink_as(&fs, a, Path::new("/links/s"), options).unwrap(),
+            LinkOutcome::Created(LinkKind::Shortcut)
+        );
+        assert_eq!(read_shortcut_with(&fs, lnk).unwrap().target, "/data/a");
+        // 覆盖已有的快捷方式
+        mklink_as(&fs, b, lnk, options).unwrap();
+        assert_eq!(read_shortcut_with(&fs, lnk).unwrap().target, "/data/b");
+        assert_eq!(fs.read_dir(Path::new("/links")).unwrap().len(), 2);
+    }
 }
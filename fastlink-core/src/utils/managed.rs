@@ -0,0 +1,296 @@
+//! fastlink创建的链接的标记，用于`--only-managed`与`rm`：check/rm只处理自己创建的链接
+//!
+//! 优先在链接自身写入扩展属性[`MANAGED_XATTR`]（Linux与macOS），标记随链接移动、随链接删除；
+//! 无法写入时（Linux不允许为符号链接设置user命名空间的扩展属性、Windows、不支持扩展属性的文件系统）
+//! 以及硬链接（扩展属性与来源共用）改为登记到用户数据目录的登记目录[`registry_dir`]中，
+//! 不在链接所在目录写入任何文件。每个链接一个登记文件，文件名为链接绝对路径的SHA-256，
+//! 重复创建时覆盖原记录；记录链接路径、目标与创建时的文件标识。
+//!
+//! 读写扩展属性与登记文件、核对链接当前的状态均通过[`Fs`]完成，判断时发现记录已失效即删除
+
+use crate::utils::vfs::{FileId, Fs};
+use sha2::{Digest, Sha256};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// 链接自身的扩展属性名，值为链接指向（或副本来自）的路径
+pub const MANAGED_XATTR: &str = "user.fastlink.managed";
+
+/// 登记目录：`<data_dir>/fastlink/managed`
+///
+/// Windows下为`%APPDATA%\fastlink\managed`，Linux下为`$XDG_DATA_HOME/fastlink/managed`
+#[cfg(not(test))]
+pub fn registry_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("fastlink").join("managed"))
+}
+
+/// 测试时不写入用户的数据目录，使用当前测试的[`tests::TestRegistry`]，没有时不登记
+#[cfg(test)]
+pub fn registry_dir() -> Option<PathBuf> {
+    tests::TEST_REGISTRY.with(|dir| dir.borrow().clone())
+}
+
+/// 一条登记记录
+#[derive(Debug, PartialEq, Eq)]
+struct Record {
+    /// 链接的绝对路径
+    link: String,
+    /// 链接指向（或硬链接/副本来自）的路径
+    target: String,
+    /// 符号链接为None，其他链接（硬链接、副本、快捷方式）为创建时的文件标识
+    id: Option<FileId>,
+}
+
+impl Record {
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let (link, target) = (lines.next()?, lines.next()?);
+        let id = match lines.next().unwrap_or("") {
+            "" => None,
+            id => {
+                let (dev, ino) = id.split_once(' ')?;
+                Some(FileId {
+                    dev: dev.parse().ok()?,
+                    ino: ino.parse().ok()?,
+                })
+            }
+        };
+        Some(Record {
+            link: link.to_string(),
+            target: target.to_string(),
+            id,
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let id = self
+            .id
+            .map(|id| format!("{} {}", id.dev, id.ino))
+            .unwrap_or_default();
+        format!("{}\n{}\n{}\n", self.link, self.target, id)
+    }
+}
+
+/// 标记link为fastlink创建，target为链接指向（或硬链接/副本来自）的路径
+pub fn mark_managed(fs: &dyn Fs, link: &Path, target: &Path) -> io::Result<()> {
+    let metadata = fs.symlink_metadata(link)?;
+    let target = target.to_string_lossy();
+    // 硬链接与来源是同一文件，写入扩展属性会把来源也标记为fastlink创建
+    let is_hardlink = metadata.is_file() && metadata.nlink.is_some_and(|n| n > 1);
+    if !is_hardlink && fs.set_xattr(link, MANAGED_XATTR, target.as_bytes()).is_ok() {
+        return Ok(());
+    }
+    let (registry, key) = registry_of(link)?;
+    let record = Record {
+        link: key,
+        target: target.into_owned(),
+        id: match metadata.is_symlink() {
+            true => None,
+            false => Some(fs.file_id(link)?),
+        },
+    };
+    if let Some(parent) = registry.parent() {
+        fs.create_dir_all(parent)?;
+    }
+    fs.write(&registry, record.to_text().as_bytes())
+}
+
+/// link是否由fastlink创建：自身带有扩展属性，或登记目录中有其记录，且符号链接仍指向记录的目标、
+/// 其他链接仍是记录的文件（避免同名的链接/文件被替换后仍被视为fastlink创建）；
+/// 链接已不存在或已被替换时删除失效的记录
+pub fn is_managed(fs: &dyn Fs, link: &Path) -> bool {
+    let metadata = fs.symlink_metadata(link);
+    if metadata.is_ok() && fs.get_xattr(link, MANAGED_XATTR).is_ok() {
+        return true;
+    }
+    let Ok((registry, key)) = registry_of(link) else {
+        return false;
+    };
+    let Some(record) = read_record(fs, &registry) else {
+        return false;
+    };
+    // 文件名相同但路径不同（哈希碰撞）的记录属于其他链接
+    if record.link != key {
+        return false;
+    }
+    let valid = metadata.is_ok_and(|metadata| match record.id {
+        None => {
+            metadata.is_symlink()
+                && fs
+                    .read_link(link)
+                    .is_ok_and(|t| t.to_string_lossy() == record.target)
+        }
+        Some(id) => !metadata.is_symlink() && fs.file_id(link).is_ok_and(|i| i == id),
+    });
+    if !valid {
+        if let Err(e) = remove_record(fs, &registry) {
+            log::debug!("删除失效的登记记录失败 {}: {}", registry.display(), e);
+        }
+    }
+    valid
+}
+
+/// 删除link后清除其登记记录（扩展属性随链接一起删除），没有记录时什么也不做
+pub fn unmark_managed(fs: &dyn Fs, link: &Path) -> io::Result<()> {
+    let (registry, _) = registry_of(link)?;
+    remove_record(fs, &registry)
+}
+
+fn read_record(fs: &dyn Fs, registry: &Path) -> Option<Record> {
+    let mut text = String::new();
+    fs.open(registry).ok()?.read_to_string(&mut text).ok()?;
+    Record::parse(&text)
+}
+
+fn remove_record(fs: &dyn Fs, registry: &Path) -> io::Result<()> {
+    match fs.remove_file(registry) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
+
+/// link的登记文件与作为键的绝对路径（不跟随符号链接，只去除`.`与`..`）
+fn registry_of(link: &Path) -> io::Result<(PathBuf, String)> {
+    let key = path_clean::clean(std::path::absolute(link)?)
+        .to_string_lossy()
+        .into_owned();
+    if key.contains(['\n', '\r']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("无法登记的路径 {}", link.display()),
+        ));
+    }
+    let dir = registry_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "无法确定用户数据目录"))?;
+    let name: String = Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok((dir.join(name), key))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::utils::vfs::{FsOp, MemFs, StdFs};
+    use std::cell::RefCell;
+
+    thread_local! {
+        /// 当前测试的登记目录，见[`TestRegistry`]
+        pub(super) static TEST_REGISTRY: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+    }
+
+    /// 当前测试（线程）使用的临时登记目录，离开作用域时删除
+    pub(crate) struct TestRegistry {
+        dir: tempfile::TempDir,
+    }
+
+    impl TestRegistry {
+        pub(crate) fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            TEST_REGISTRY.with(|registry| *registry.borrow_mut() = Some(dir.path().to_path_buf()));
+            TestRegistry { dir }
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            self.dir.path()
+        }
+    }
+
+    impl Drop for TestRegistry {
+        fn drop(&mut self) {
+            TEST_REGISTRY.with(|registry| registry.borrow_mut().take());
+        }
+    }
+
+    #[test]
+    /// 副本的标记写在自身的扩展属性中；硬链接只登记，来源不会被视为fastlink创建；
+    /// 被替换的文件不再视为fastlink创建，失效的记录随之删除
+    fn test_registry_marks() {
+        let registry = TestRegistry::new();
+        let tmp = tempfile::tempdir().unwrap();
+        let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+        std::fs::write(&a, "a").unwrap();
+        std::fs::write(&b, "b").unwrap();
+        let (copy, hard) = (tmp.path().join("copy"), tmp.path().join("hard"));
+        std::fs::write(&copy, "a").unwrap();
+        std::fs::hard_link(&a, &hard).unwrap();
+
+        assert!(!is_managed(&StdFs, &copy));
+        mark_managed(&StdFs, &copy, &a).unwrap();
+        mark_managed(&StdFs, &hard, &a).unwrap();
+        mark_managed(&StdFs, &b, &a).unwrap();
+        mark_managed(&StdFs, &b, &a).unwrap();
+        assert!(is_managed(&StdFs, &copy) && is_managed(&StdFs, &hard) && is_managed(&StdFs, &b));
+        assert!(!is_managed(&StdFs, &a));
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 4);
+
+        std::fs::remove_file(&hard).unwrap();
+        unmark_managed(&StdFs, &hard).unwrap();
+        assert!(!is_managed(&StdFs, &hard) && is_managed(&StdFs, &b));
+        unmark_managed(&StdFs, &hard).unwrap();
+
+        // 同名的文件被替换后不再视为fastlink创建
+        let other = tmp.path().join("other");
+        std::fs::write(&other, "a").unwrap();
+        std::fs::rename(&other, &b).unwrap();
+        assert!(!is_managed(&StdFs, &b));
+        std::fs::remove_file(&copy).unwrap();
+        assert!(!is_managed(&StdFs, &copy));
+        // 登记的记录均已删除或失效后被删除
+        assert_eq!(std::fs::read_dir(registry.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    /// 同名符号链接被替换为指向其他路径后不再视为fastlink创建，失效的记录被删除
+    fn test_replaced_symlink_not_managed() {
+        let registry = TestRegistry::new();
+        let tmp = tempfile::tempdir().unwrap();
+        let link = tmp.path().join("link");
+        std::os::unix::fs::symlink("a", &link).unwrap();
+        mark_managed(&StdFs, &link, Path::new("a")).unwrap();
+        assert!(is_managed(&StdFs, &link));
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 1);
+
+        std::fs::remove_file(&link).unwrap();
+        std::os::unix::fs::symlink("b", &link).unwrap();
+        assert!(!is_managed(&StdFs, &link));
+        assert_eq!(std::fs::read_dir(registry.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    /// 扩展属性与登记均通过fs读写，不写入真实文件系统；无法写入扩展属性时改为登记
+    fn test_marks_through_fs() {
+        let registry = TestRegistry::new();
+        let fs = MemFs::new();
+        fs.add_file("/m/a", 1)
+            .add_file("/m/copy", 1)
+            .add_symlink("/m/a", "/m/link")
+            .fail_nth_with(FsOp::SetXattr, 2, io::ErrorKind::Unsupported);
+        let (a, copy, link) = (
+            Path::new("/m/a"),
+            Path::new("/m/copy"),
+            Path::new("/m/link"),
+        );
+        let hard = Path::new("/m/hard");
+        fs.hard_link(a, hard).unwrap();
+        mark_managed(&fs, copy, a).unwrap();
+        mark_managed(&fs, link, a).unwrap();
+        mark_managed(&fs, hard, a).unwrap();
+        assert!(is_managed(&fs, copy) && is_managed(&fs, link) && is_managed(&fs, hard));
+        assert!(!is_managed(&fs, a));
+        // 真实文件系统上没有这些路径，也没有登记
+        assert!(!is_managed(&StdFs, link) && !is_managed(&StdFs, hard));
+        assert_eq!(std::fs::read_dir(registry.path()).unwrap().count(), 0);
+        let records = fs.read_dir(registry.path()).unwrap();
+        assert_eq!(records.len(), 2);
+
+        // 链接被删除后的记录在判断时删除
+        fs.remove_file(hard).unwrap();
+        fs.add_file("/m/hard", 1);
+        fs.remove_file(link).unwrap();
+        assert!(!is_managed(&fs, hard) && !is_managed(&fs, link));
+        assert!(fs.read_dir(registry.path()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::utils::expand::expand_path;
+use crate::utils::fs::{
+    collect_symlinks_with, copy_all_with, get_temp_path_with, remove_all_with, verify_copy_with,
+};
+use crate::utils::path::canonicalize_path;
+use crate::utils::rollback::Transaction;
+use crate::utils::vfs::{std_fs, Fs, SharedFs};
+use std::path::{Path, PathBuf};
+
+/// 将`path`（或`path`目录下）的符号链接替换为其目标的真实副本，返回被替换（dry_run时为将被替换）的符号链接
+///
+/// `filter`接收相对`path`的路径，返回false的符号链接会被跳过；损坏的符号链接会被跳过，
+/// 指向自身所在目录（或其上级目录）的符号链接无法复制，计为失败。
+///
+/// 副本先写入同目录下的临时路径并校验，再通过重命名替换符号链接：
+/// 文件直接覆盖重命名，是原子的；目录不能覆盖重命名，需要先将符号链接重命名为临时备份，
+/// 再将副本重命名为原路径，两次重命名之间原路径短暂不存在，此时访问它的程序会失败
+/// （原子替换需要交换重命名，如Linux的`renameat2(RENAME_EXCHANGE)`，不可移植）。
+/// 期间中断时原符号链接保留在`*_fastlink_materialize_temp_*`中，不会丢失
+pub fn materialize(
+    path: &Path,
+    dry_run: bool,
+    filter: &dyn Fn(&Path) -> bool,
+) -> MyResult<Vec<PathBuf>> {
+    materialize_with(std_fs(), path, dry_run, filter)
+}
+
+/// 同`materialize`，通过fs访问文件系统
+pub fn materialize_with(
+    fs: SharedFs,
+    path: &Path,
+    dry_run: bool,
+    filter: &dyn Fn(&Path) -> bool,
+) -> MyResult<Vec<PathBuf>> {
+    let root = canonicalize_path(expand_path(path)?);
+    let mut links = collect_symlinks_with(fs.as_ref(), &root)?;
+    links.sort();
+
+    let mut done = Vec::new();
+    let mut failed = 0;
+    for link in links {
+        let relative = link.strip_prefix(&root).unwrap_or(&link);
+        if !filter(relative) {
+            log::debug!("未匹配，已跳过: {}", link.display());
+            continue;
+        }
+        let target = match fs.canonicalize(&link) {
+            Ok(target) => target,
+            Err(e) => {
+                log::warn!("跳过损坏的符号链接 {}: {}", link.display(), e);
+                continue;
+            }
+        };
+
+        if let Err(e) = check_target(fs.as_ref(), &link, &target) {
+            e.log();
+            failed += 1;
+            continue;
+        }
+
+        if dry_run {
+            log::info!("将替换: {} -> {}", link.display(), target.display());
+            done.push(link);
+            continue;
+        }
+        match materialize_link(&fs, &link, &target) {
+            Ok(()) => {
+                log::info!("已替换: {} <- {}", link.display(), target.display());
+                done.push(link);
+            }
+            Err(e) => {
+                e.log();
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(MyError::new(
+            ErrorCode::IoError,
+            format!("{}个符号链接替换失败，已替换{}个", failed, done.len()),
+        ));
+    }
+    Ok(done)
+}
+
+/// target为link自身或其上级目录时，复制会进入复制结果本身，无法完成
+fn check_target(fs: &dyn Fs, link: &Path, target: &Path) -> MyResult<()> {
+    let real_link = match (link.parent(), link.file_name()) {
+        (Some(parent), Some(name)) => fs
+            .canonicalize(parent)
+            .map_or_else(|_| link.to_path_buf(), |parent| parent.join(name)),
+        _ => link.to_path_buf(),
+    };
+    if real_link.starts_with(target) {
+        return Err(MyError::new(
+            ErrorCode::InvalidInput,
+            format!(
+                "符号链接{}指向其所在的目录{}，无法复制",
+                link.display(),
+                target.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// 用`target`的副本替换符号链接`link`
+fn materialize_link(fs: &SharedFs, link: &Path, target: &Path) -> MyResult<()> {
+    let temp = get_temp_path_with(fs.as_ref(), link, "materialize");
+    let mut tx = Transaction::with_fs(fs.clone());
+
+    let (copy_dst, undo_dst) = (temp.clone(), temp.clone());
+    let copy_src = target.to_path_buf();
+    let undo_fs = fs.clone();
+    tx.add_op(
+        move || copy_all_with(fs.as_ref(), &copy_src, &copy_dst).map(|_| ()),
+        move || remove_all_with(undo_fs.as_ref(), &undo_dst),
+        Some(format!("复制{}到临时路径", target.display())),
+    )?;
+    verify_copy_with(fs.as_ref(), target, &temp, false)?;
+
+    if fs.metadata(target).is_ok_and(|m| m.is_dir()) {
+        let backup = get_temp_path_with(fs.as_ref(), link, "materialize");
+        tx.add_op_rename_dir(
+            link.to_path_buf(),
+            backup.clone(),
+            Some("将符号链接重命名以backup".into()),
+        )?;
+        rename(fs, &mut tx, &temp, link)?;
+        tx.commit()?;
+        if let Err(e) = remove_all_with(fs.as_ref(), &backup) {
+            log::warn!("删除备份{}失败，请手动删除: {}", backup.display(), e);
+        }
+        Ok(())
+    } else {
+        // 覆盖重命名会直接替换符号链接本身，不存在路径缺失的时刻
+        fs.rename(&temp, link).map_err(|e| {
+            MyError::new(
+                ErrorCode::IoError,
+                format!("替换符号链接{}失败: {}", link.display(), e),
+            )
+        })?;
+        tx.commit()
+    }
+}
+
+fn rename(fs: &SharedFs, tx: &mut Transaction, from: &Path, to: &Path) -> MyResult<()> {
+    let (op_from, op_to) = (from.to_path_buf(), to.to_path_buf());
+    let (undo_from, undo_to) = (from.to_path_buf(), to.to_path_buf());
+    let undo_fs = fs.clone();
+    tx.add_op(
+        move || {
+            fs.rename(&op_from, &op_to).map_err(|e| {
+                MyError::new(
+                    ErrorCode::IoError,
+                    format!(
+                        "将{}重命名为{}失败: {}",
+                        op_from.display(),
+                        op_to.display(),
+                        e
+                    ),
+                )
+            })
+        },
+        move || {
+            undo_fs.rename(&undo_to, &undo_from).map_err(|e| {
+                MyError::new(
+                    ErrorCode::IoError,
+                    format!(
+                        "将{}重命名回{}失败: {}",
+                        undo_to.display(),
+                        undo_from.display(),
+                        e
+                    ),
+                )
+            })
+        },
+        Some(format!("将副本重命名为{}", to.display())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::link::create_symlink;
+    use crate::utils::vfs::{MemFs, StdFs};
+    use std::fs;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    fn make_links(root: &Path) -> (PathBuf, PathBuf) {
+        let real = root.join("real");
+        fs::create_dir_all(real.join("dir")).unwrap();
+        fs::write(real.join("a.txt"), "a").unwrap();
+        fs::write(real.join("dir/b.txt"), "b").unwrap();
+
+        let tree = root.join("tree");
+        fs::create_dir_all(&tree).unwrap();
+        create_symlink(real.join("a.txt"), tree.join("a.txt")).unwrap();
+        create_symlink(real.join("dir"), tree.join("dir")).unwrap();
+        create_symlink(real.join("missing"), tree.join("broken")).unwrap();
+        (real, tree)
+    }
+
+    fn is_symlink(path: &Path) -> bool {
+        fs::symlink_metadata(path).unwrap().file_type().is_symlink()
+    }
+
+    #[test]
+    fn test_materialize_tree() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (real, tree) = make_links(tmp.path());
+
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        StdFs.set_modified(&real.join("dir"), old).unwrap();
+
+        let done = materialize(&tree, false, &|_| true).unwrap();
+
+        assert_eq!(done, vec![tree.join("a.txt"), tree.join("dir")]);
+        assert_eq!(
+            fs::metadata(tree.join("dir")).unwrap().modified().unwrap(),
+            old
+        );
+        assert!(!is_symlink(&tree.join("a.txt")));
+        assert!(!is_symlink(&tree.join("dir")));
+        assert_eq!(fs::read_to_string(tree.join("dir/b.txt")).unwrap(), "b");
+        // 损坏的符号链接保持不变，原目标不受影响，没有残留的临时路径
+        assert!(is_symlink(&tree.join("broken")));
+        assert!(real.join("dir/b.txt").exists());
+        assert_eq!(fs::read_dir(&tree).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_materialize_dry_run_and_filter() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, tree) = make_links(tmp.path());
+
+        let done = materialize(&tree, true, &|_| true).unwrap();
+        assert_eq!(done.len(), 2);
+        assert!(is_symlink(&tree.join("a.txt")));
+        assert!(is_symlink(&tree.join("dir")));
+
+        let done = materialize(&tree, false, &|p| p.ends_with("a.txt")).unwrap();
+        assert_eq!(done, vec![tree.join("a.txt")]);
+        assert!(!is_symlink(&tree.join("a.txt")));
+        assert!(is_symlink(&tree.join("dir")));
+    }
+
+    #[test]
+    /// 指向上级目录的符号链接计为失败，其他符号链接照常替换，目录保留修改时间
+    fn test_materialize_ancestor_target_on_mem_fs() {
+        let mem = Arc::new(MemFs::new());
+        mem.add_file_with("/real/dir/b.txt", "b")
+            .add_symlink("/real/dir", "/tree/dir")
+            .add_symlink("/tree", "/tree/loop")
+            .add_symlink("..", "/tree/up");
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        mem.set_modified(Path::new("/real/dir"), old).unwrap();
+
+        let e = materialize_with(mem.clone(), Path::new("/tree"), true, &|_| true).unwrap_err();
+        assert_eq!(e.code, ErrorCode::IoError);
+        assert!(mem.journal().is_empty());
+
+        let e = materialize_with(mem.clone(), Path::new("/tree"), false, &|_| true).unwrap_err();
+        assert!(e.msg.starts_with("2个"), "{}", e.msg);
+        let dir = mem.symlink_metadata(Path::new("/tree/dir")).unwrap();
+        assert!(dir.is_dir());
+        assert_eq!(dir.modified, Some(old));
+        assert!(mem
+            .symlink_metadata(Path::new("/tree/loop"))
+            .unwrap()
+            .is_symlink());
+        assert!(mem
+            .symlink_metadata(Path::new("/tree/up"))
+            .unwrap()
+            .is_symlink());
+        assert_eq!(mem.read_dir(Path::new("/tree")).unwrap().len(), 3);
+    }
+}
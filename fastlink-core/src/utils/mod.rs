@@ -1,5 +1,14 @@
+pub mod dedupe;
+pub mod expand;
 pub mod fs;
 pub mod func;
 pub mod link;
 pub mod logs;
+pub mod managed;
+pub mod materialize;
 pub mod path;
+pub mod relocate;
+pub mod rollback;
+pub mod script;
+pub mod shortcut;
+pub mod vfs;
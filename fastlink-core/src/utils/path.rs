@@ -1,14 +1,137 @@
-use crate::types::err::MyError;
+use crate::types::err::{ErrorCode, MyError};
+use crate::utils::vfs::{Fs, FsFileType, StdFs};
 use path_clean::PathClean;
 use std::path::{Path, PathBuf};
 
-/// 路径规范化
+/// 路径规范化：相对路径先拼接到工作目录再去除`.`与`..`，得到不含`.`与`..`的绝对路径，不访问文件系统
 pub fn canonicalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
     let path = path.as_ref();
     if path.is_absolute() {
         path.to_path_buf().clean()
     } else {
-        crate::WORK_DIR.join(path.clean())
+        crate::WORK_DIR.join(path).clean()
+    }
+}
+
+/// 路径在文件系统中的状态，由[`inspect_path`]得到
+#[derive(Debug)]
+pub enum PathStatus {
+    /// 不存在
+    Missing,
+    /// 存在且不是符号链接的文件
+    File,
+    /// 存在且不是符号链接的目录
+    Dir,
+    /// 有效的符号链接，target_kind为跟随后的类型（File或Dir）
+    Symlink {
+        target: PathBuf,
+        target_kind: FsFileType,
+    },
+    /// 损坏的符号链接，target为其记录的指向
+    BrokenSymlink { target: PathBuf },
+    /// 无法获取元数据，如权限问题
+    Inaccessible(MyError),
+}
+
+impl PathStatus {
+    /// 路径本身不存在（损坏的符号链接不算不存在）
+    pub fn is_missing(&self) -> bool {
+        matches!(self, PathStatus::Missing)
+    }
+
+    /// 是否是符号链接，损坏与否均可
+    pub fn is_symlink(&self) -> bool {
+        matches!(
+            self,
+            PathStatus::Symlink { .. } | PathStatus::BrokenSymlink { .. }
+        )
+    }
+
+    /// 跟随符号链接后是否为文件
+    pub fn is_file(&self) -> bool {
+        matches!(
+            self,
+            PathStatus::File
+                | PathStatus::Symlink {
+                    target_kind: FsFileType::File,
+                    ..
+                }
+        )
+    }
+
+    /// 跟随符号链接后是否为目录
+    pub fn is_dir(&self) -> bool {
+        matches!(
+            self,
+            PathStatus::Dir
+                | PathStatus::Symlink {
+                    target_kind: FsFileType::Dir,
+                    ..
+                }
+        )
+    }
+
+    /// 转为旧的以错误码表示的状态，见[`crate::utils::func::mklink_pre_check`]
+    pub fn into_error(self, path: &Path) -> MyError {
+        let code = match self {
+            PathStatus::Missing => ErrorCode::FileNotExist,
+            PathStatus::File | PathStatus::Dir => ErrorCode::TargetExistsAndNotLink,
+            PathStatus::Symlink { .. } => ErrorCode::TargetLinkExists,
+            PathStatus::BrokenSymlink { .. } => ErrorCode::BrokenSymlink,
+            PathStatus::Inaccessible(e) => return e,
+        };
+        MyError::new(code, format!("{}", path.display()))
+    }
+}
+
+/// 获取路径的状态，不跟随路径本身的符号链接
+pub fn inspect_path<P: AsRef<Path>>(path: P) -> PathStatus {
+    inspect_path_with(&StdFs, path.as_ref())
+}
+
+/// 同`inspect_path`，通过fs访问文件系统
+pub fn inspect_path_with(fs: &dyn Fs, path: &Path) -> PathStatus {
+    let metadata = match fs.symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return PathStatus::Missing,
+        Err(e) => {
+            return PathStatus::Inaccessible(MyError::new(
+                ErrorCode::FailToGetFileMetadata,
+                format!("无法获取路径元数据({}): {} {}", e.kind(), e, path.display()),
+            ));
+        }
+    };
+    match metadata.file_type {
+        FsFileType::File => PathStatus::File,
+        FsFileType::Dir => PathStatus::Dir,
+        FsFileType::Symlink => {
+            let target = match fs.read_link(path) {
+                Ok(target) => target,
+                Err(e) => {
+                    return PathStatus::Inaccessible(MyError::new(
+                        ErrorCode::FailToGetFileMetadata,
+                        format!(
+                            "无法读取符号链接指向({}): {} {}",
+                            e.kind(),
+                            e,
+                            path.display()
+                        ),
+                    ));
+                }
+            };
+            // 跟随后无法获取元数据，符号链接损坏
+            match fs.metadata(path) {
+                Ok(m) if m.is_dir() => PathStatus::Symlink {
+                    target,
+                    target_kind: FsFileType::Dir,
+                },
+                Ok(_) => PathStatus::Symlink {
+                    target,
+                    target_kind: FsFileType::File,
+                },
+                Err(_) => PathStatus::BrokenSymlink { target },
+            }
+        }
     }
 }
 
@@ -19,6 +142,156 @@ pub fn canonicalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
 /// 2. 是否是损坏的符号链接(BrokenSymlink)
 /// 3. 是否是存在但不是符号链接(TargetExistsAndNotLink)
 /// 4. 是否已存在的符号链接(TargetLinkExists)
+///
+/// 新代码请使用[`inspect_path`]
 pub fn get_path_type<P: AsRef<Path>>(path: P) -> Result<(), MyError> {
     crate::utils::func::mklink_pre_check(path)
 }
+
+/// 解析符号链接时允许的最大跳数，超过后不再跟随
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// 通过fs逐段解析路径中的符号链接与`..`，得到真实的绝对路径，不存在的部分按字面拼接
+///
+/// 与canonicalize不同，路径不需要存在；`..`在其前面的符号链接解析后才生效，与系统行为一致
+pub fn resolve_path_with(fs: &dyn Fs, path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        crate::WORK_DIR.join(path)
+    };
+    // 待处理的路径分段，逆序存放
+    let mut pending: Vec<PathBuf> = path
+        .components()
+        .rev()
+        .map(|c| PathBuf::from(c.as_os_str()))
+        .collect();
+    let mut resolved = PathBuf::new();
+    let mut hops = 0;
+    while let Some(part) = pending.pop() {
+        if part == Path::new(".") {
+            continue;
+        }
+        if part == Path::new("..") {
+            resolved.pop();
+            continue;
+        }
+        // 根目录、盘符等分段会替换resolved
+        resolved.push(&part);
+        if hops >= MAX_SYMLINK_HOPS || !fs.symlink_metadata(&resolved).is_ok_and(|m| m.is_symlink())
+        {
+            continue;
+        }
+        if let Ok(target) = fs.read_link(&resolved) {
+            hops += 1;
+            resolved.pop();
+            pending.extend(
+                target
+                    .components()
+                    .rev()
+                    .map(|c| PathBuf::from(c.as_os_str())),
+            );
+        }
+    }
+    resolved
+}
+
+/// 同`resolve_path_with`，但不跟随路径最后一段本身的符号链接，用于解析将要创建（或覆盖）的链接路径
+pub fn resolve_link_path_with(fs: &dyn Fs, path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => resolve_path_with(fs, parent).join(name),
+        _ => resolve_path_with(fs, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::vfs::MemFs;
+
+    #[test]
+    fn test_inspect_path_with_mem_fs() {
+        let fs = MemFs::new();
+        fs.add_dir("/a").add_file("/a/f", 1);
+        fs.add_symlink("/a", "/ld")
+            .add_symlink("/a/f", "/lf")
+            .add_symlink("/gone", "/lb");
+
+        assert!(inspect_path_with(&fs, Path::new("/none")).is_missing());
+        assert!(matches!(
+            inspect_path_with(&fs, Path::new("/a")),
+            PathStatus::Dir
+        ));
+        assert!(matches!(
+            inspect_path_with(&fs, Path::new("/a/f")),
+            PathStatus::File
+        ));
+        match inspect_path_with(&fs, Path::new("/ld")) {
+            PathStatus::Symlink {
+                target,
+                target_kind,
+            } => {
+                assert_eq!(target, PathBuf::from("/a"));
+                assert_eq!(target_kind, FsFileType::Dir);
+            }
+            s => panic!("{:?}", s),
+        }
+        assert!(inspect_path_with(&fs, Path::new("/lf")).is_file());
+        match inspect_path_with(&fs, Path::new("/lb")) {
+            PathStatus::BrokenSymlink { target } => assert_eq!(target, PathBuf::from("/gone")),
+            s => panic!("{:?}", s),
+        }
+    }
+
+    #[test]
+    fn test_inspect_path_into_error() {
+        let fs = MemFs::new();
+        fs.add_symlink("/gone", "/lb");
+        let e = inspect_path_with(&fs, Path::new("/lb")).into_error(Path::new("/lb"));
+        assert_eq!(e.code, ErrorCode::BrokenSymlink);
+        let e = inspect_path_with(&fs, Path::new("/x")).into_error(Path::new("/x"));
+        assert_eq!(e.code, ErrorCode::FileNotExist);
+    }
+
+    #[test]
+    /// 相对路径开头的`..`相对工作目录解析，不会残留在结果中
+    fn test_canonicalize_relative_path() {
+        let work_dir = crate::WORK_DIR.as_path();
+        assert_eq!(canonicalize_path("."), work_dir);
+        assert_eq!(canonicalize_path("a/./b/../c"), work_dir.join("a/c"));
+        let parent = work_dir.parent().unwrap_or(work_dir);
+        assert_eq!(canonicalize_path("../x"), parent.join("x"));
+        assert_eq!(canonicalize_path(".."), parent);
+        assert!(canonicalize_path("../../a")
+            .components()
+            .all(|c| !matches!(c, std::path::Component::ParentDir)));
+    }
+
+    #[test]
+    fn test_resolve_path_with_mem_fs() {
+        let fs = MemFs::new();
+        fs.add_dir("/root/proj").add_dir("/outside/dir");
+        fs.add_symlink("/outside", "/root/proj/out")
+            .add_symlink("../proj", "/root/proj/self");
+
+        let resolve = |p: &str| resolve_path_with(&fs, Path::new(p));
+        assert_eq!(resolve("/root/proj/a/../b"), PathBuf::from("/root/proj/b"));
+        assert_eq!(
+            resolve("/root/proj/out/dir/x"),
+            PathBuf::from("/outside/dir/x")
+        );
+        // `..`在符号链接解析后生效
+        assert_eq!(
+            resolve("/root/proj/out/dir/../y"),
+            PathBuf::from("/outside/y")
+        );
+        assert_eq!(
+            resolve("/root/proj/self/new"),
+            PathBuf::from("/root/proj/new")
+        );
+        assert_eq!(
+            resolve_link_path_with(&fs, Path::new("/root/proj/out")),
+            PathBuf::from("/root/proj/out")
+        );
+    }
+}
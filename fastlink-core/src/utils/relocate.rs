@@ -0,0 +1,333 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::utils::expand::expand_path;
+use crate::utils::fs::{copy_all_with, get_temp_path_with, remove_all_with, verify_copy_with};
+use crate::utils::path::canonicalize_path;
+use crate::utils::rollback::Transaction;
+use crate::utils::vfs::{std_fs, Fs, SharedFs};
+use std::path::{Path, PathBuf};
+
+/// 将`path`移动到`new_location`，并在`path`处创建指向新位置的符号链接，返回新位置
+///
+/// 流程：创建缺少的父目录 -> 复制并校验 -> 将原路径重命名为临时备份 -> 在原路径创建符号链接 -> 删除备份。
+/// 创建符号链接之前（包括创建时）的任一步骤失败，都会按相反顺序回滚，原路径保持不变，
+/// 也不会留下新建的父目录
+pub fn relocate(path: &Path, new_location: &Path, make_dir: bool, hash: bool) -> MyResult<PathBuf> {
+    relocate_with(std_fs(), path, new_location, make_dir, hash)
+}
+
+/// 同`relocate`，通过fs访问文件系统
+pub fn relocate_with(
+    fs: SharedFs,
+    path: &Path,
+    new_location: &Path,
+    make_dir: bool,
+    hash: bool,
+) -> MyResult<PathBuf> {
+    let path = canonicalize_path(expand_path(path)?);
+    let target = resolve_target(fs.as_ref(), &path, &expand_path(new_location)?)?;
+    check_relocate(fs.as_ref(), &path, &target)?;
+
+    // 需要创建的父目录，由近及远
+    let mut missing_dirs = Vec::new();
+    if let Some(parent) = target.parent() {
+        if !fs.exists(parent) && !make_dir {
+            return Err(MyError::new(
+                ErrorCode::ParentNotExist,
+                format!("{}，可使用--make-dir自动创建", parent.display()),
+            ));
+        }
+        missing_dirs = parent
+            .ancestors()
+            .take_while(|dir| fs.symlink_metadata(dir).is_err())
+            .map(Path::to_path_buf)
+            .collect();
+    }
+
+    let mut tx = Transaction::with_fs(fs.clone());
+
+    // 逐级创建父目录，回滚时由近及远删除
+    for dir in missing_dirs.into_iter().rev() {
+        let name = format!("创建目录{}", dir.display());
+        tx.add_op_mkdir(dir, Some(name))?;
+    }
+
+    // 复制到新位置，undo删除复制结果；复制中途失败时undo不会被记录，由操作自身删除复制了一半的结果
+    let (copy_src, copy_dst, undo_dst) = (path.clone(), target.clone(), target.clone());
+    let (copy_fs, undo_fs) = (fs.clone(), fs.clone());
+    tx.add_op(
+        move || {
+            let copy_fs = copy_fs.as_ref();
+            let size = copy_all_with(copy_fs, &copy_src, &copy_dst).inspect_err(|_| {
+                if let Err(e) = remove_all_with(copy_fs, &copy_dst) {
+                    log::warn!(
+                        "删除复制了一半的{}失败，请手动删除: {}",
+                        copy_dst.display(),
+                        e
+                    );
+                }
+            })?;
+            log::info!("已复制 {} 字节到 {}", size, copy_dst.display());
+            Ok(())
+        },
+        move || remove_all_with(undo_fs.as_ref(), &undo_dst),
+        Some(format!("复制到{}", target.display())),
+    )?;
+
+    verify_copy_with(fs.as_ref(), &path, &target, hash)?;
+    log::info!(
+        "校验通过{}",
+        if hash {
+            "（大小与SHA-256）"
+        } else {
+            "（大小）"
+        }
+    );
+
+    // 原路径先重命名为备份，在链接创建成功前不删除
+    let backup = get_temp_path_with(fs.as_ref(), &path, "relocate");
+    tx.add_op_rename_dir(
+        path.clone(),
+        backup.clone(),
+        Some("将原路径重命名以backup".into()),
+    )?;
+    tx.add_op_mklink(
+        target.clone(),
+        path.clone(),
+        Some("在原路径创建指向新位置的符号链接".into()),
+    )?;
+    tx.commit()?;
+
+    if let Err(e) = remove_all_with(fs.as_ref(), &backup) {
+        log::warn!("删除备份{}失败，请手动删除: {}", backup.display(), e);
+    }
+    log::info!("{} -> {}", path.display(), target.display());
+    Ok(target)
+}
+
+/// 新位置以路径分隔符结尾或为已存在的目录时，移动到该目录下并保持原名称
+fn resolve_target(fs: &dyn Fs, path: &Path, new_location: &Path) -> MyResult<PathBuf> {
+    let raw = new_location.to_string_lossy();
+    let dir_tendency = raw.ends_with('/') || raw.ends_with('\\');
+    let new_location = canonicalize_path(new_location);
+
+    if dir_tendency || fs.metadata(&new_location).is_ok_and(|m| m.is_dir()) {
+        let name = path.file_name().ok_or_else(|| {
+            MyError::new(
+                ErrorCode::InvalidInput,
+                format!("无法获取{}的名称", path.display()),
+            )
+        })?;
+        Ok(new_location.join(name))
+    } else {
+        Ok(new_location)
+    }
+}
+
+fn check_relocate(fs: &dyn Fs, path: &Path, target: &Path) -> MyResult<()> {
+    let metadata = fs.symlink_metadata(path).map_err(|e| {
+        MyError::new(
+            ErrorCode::FileNotExist,
+            format!("{}: {}", path.display(), e),
+        )
+    })?;
+    if metadata.is_symlink() {
+        return Err(MyError::new(
+            ErrorCode::InvalidInput,
+            format!("{}已经是符号链接", path.display()),
+        ));
+    }
+    // 路径中可能含有符号链接，按真实路径比较
+    let real_path = fs.canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let real_target = canonicalize_existing(fs, target);
+    if path == target || real_path == real_target {
+        return Err(MyError::new(
+            ErrorCode::SrcEqDst,
+            format!("{}", path.display()),
+        ));
+    }
+    if real_target.starts_with(&real_path) {
+        return Err(MyError::new(
+            ErrorCode::InvalidInput,
+            format!("不能移动到自身内部: {}", target.display()),
+        ));
+    }
+    if fs.symlink_metadata(target).is_ok() {
+        return Err(MyError::new(
+            ErrorCode::InvalidInput,
+            format!("新位置已存在: {}", target.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// 跟随符号链接解析path中已存在的最长前缀，再拼接其余部分
+fn canonicalize_existing(fs: &dyn Fs, path: &Path) -> PathBuf {
+    for existing in path.ancestors() {
+        if let Ok(real) = fs.canonicalize(existing) {
+            return real.join(path.strip_prefix(existing).unwrap_or(Path::new("")));
+        }
+    }
+    path.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn make_data(root: &Path) -> PathBuf {
+        let data = root.join("data");
+        fs::create_dir_all(data.join("sub")).unwrap();
+        fs::write(data.join("a.txt"), "a").unwrap();
+        fs::write(data.join("sub/b.txt"), "bb").unwrap();
+        data
+    }
+
+    #[test]
+    fn test_relocate_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data = make_data(tmp.path());
+        let new_dir = tmp.path().join("disk2");
+
+        let target = relocate(
+            &data,
+            Path::new(&format!("{}/", new_dir.display())),
+            true,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(target, new_dir.join("data"));
+        assert!(fs::symlink_metadata(&data)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_link(&data).unwrap(), target);
+        assert_eq!(fs::read_to_string(data.join("sub/b.txt")).unwrap(), "bb");
+        // 备份已删除，只剩下链接与新位置
+        assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_relocate_file_rename() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("save.dat");
+        fs::write(&file, "save").unwrap();
+        let new_file = tmp.path().join("save-moved.dat");
+
+        relocate(&file, &new_file, false, false).unwrap();
+
+        assert_eq!(fs::read_link(&file).unwrap(), new_file);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "save");
+    }
+
+    #[test]
+    fn test_relocate_refused() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data = make_data(tmp.path());
+        let exists = tmp.path().join("exists");
+        fs::write(&exists, "").unwrap();
+
+        // 移动到自身内部
+        assert!(relocate(&data, &data.join("sub/inner"), true, false).is_err());
+        // 新位置已存在
+        assert!(relocate(&data, &exists, false, false).is_err());
+        // 父目录不存在且未启用make_dir
+        assert_eq!(
+            relocate(&data, &tmp.path().join("no/such"), false, false)
+                .unwrap_err()
+                .code,
+            ErrorCode::ParentNotExist
+        );
+
+        assert!(fs::symlink_metadata(&data).unwrap().is_dir());
+        assert!(!tmp.path().join("no/such").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    /// 复制中途失败（套接字文件无法打开）：原路径保持不变，不留下复制了一半的新位置与备份
+    fn test_relocate_rollback_on_copy_failure() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data = make_data(tmp.path());
+        let _socket = std::os::unix::net::UnixListener::bind(data.join("sub/sock")).unwrap();
+        let new_dir = tmp.path().join("disk2");
+
+        let e = relocate(&data, &new_dir.join("data"), true, true).unwrap_err();
+        assert_eq!(e.code, ErrorCode::IoError);
+
+        assert!(fs::symlink_metadata(&data).unwrap().is_dir());
+        assert_eq!(fs::read_to_string(data.join("a.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(data.join("sub/b.txt")).unwrap(), "bb");
+        assert!(fs::symlink_metadata(new_dir.join("data")).is_err());
+        let leftovers: Vec<_> = fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name.to_string_lossy().contains("_fastlink_relocate_temp_"))
+            .collect();
+        assert!(leftovers.is_empty(), "{:?}", leftovers);
+    }
+
+    #[test]
+    /// 新位置经由符号链接位于原路径内部时同样拒绝
+    fn test_relocate_refused_through_symlink() {
+        use crate::utils::vfs::MemFs;
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        mem.add_file("/data/a.txt", 1)
+            .add_symlink("/data", "/alias");
+
+        let e = relocate_with(
+            mem.clone(),
+            Path::new("/data"),
+            Path::new("/alias/sub/inner"),
+            true,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(e.code, ErrorCode::InvalidInput);
+        let e = relocate_with(
+            mem.clone(),
+            Path::new("/data"),
+            Path::new("/alias"),
+            true,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(e.code, ErrorCode::InvalidInput);
+        assert!(mem.journal().is_empty());
+    }
+
+    #[test]
+    /// 创建符号链接失败：备份重命名回原路径，删除复制结果与新建的父目录
+    fn test_relocate_rollback_on_mklink_failure() {
+        use crate::utils::vfs::{FsOp, MemFs};
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        mem.add_file_with("/data/a.txt", "a")
+            .add_file_with("/data/sub/b.txt", "bb")
+            .add_dir("/disk2");
+        mem.fail_nth(FsOp::Symlink, 1);
+
+        let e = relocate_with(
+            mem.clone(),
+            Path::new("/data"),
+            Path::new("/disk2/new/data"),
+            true,
+            true,
+        )
+        .unwrap_err();
+        assert_eq!(e.code, ErrorCode::FailAtMakeLink);
+
+        assert!(mem.symlink_metadata(Path::new("/data")).unwrap().is_dir());
+        assert_eq!(
+            mem.read_dir(Path::new("/data/sub")).unwrap(),
+            vec![PathBuf::from("/data/sub/b.txt")]
+        );
+        assert!(mem.read_dir(Path::new("/disk2")).unwrap().is_empty());
+        assert_eq!(mem.read_dir(Path::new("/")).unwrap().len(), 2);
+        assert_eq!(mem.journal().last().unwrap(), "remove /disk2/new");
+    }
+}
@@ -0,0 +1,454 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::types::link_task_args::{
+    BrokenSrcPolicy, ConflictPolicy, LinkFallback, LinkKind, LinkOptions, TargetKind,
+};
+use crate::utils::link::{
+    create_symlink_as,
+    del_exists_link_with,
+    mklink_with,
+    //  mklink_when_src_file_not_exists,
+};
+use crate::utils::vfs::{std_fs, SharedFs};
+use std::iter::zip;
+use std::path::PathBuf;
+
+pub struct Transaction {
+    undo_ops_name: Vec<String>,
+    // 存储已执行的操作及其撤销函数
+    undo_ops: Vec<Box<dyn FnOnce() -> MyResult<()>>>,
+    // add_op_*系列操作使用的文件系统
+    fs: SharedFs,
+    // add_op_mklink/add_op_del_link创建链接时使用的选项
+    link_options: LinkOptions,
+}
+
+/// 事务中创建链接的默认选项：只覆盖损坏的符号链接，src不存在或为损坏的符号链接时报错，
+/// 不会创建悬空链接；只有撤销删除链接时允许（原目标可能已不存在），见`op_del_link`
+pub const TX_LINK_OPTIONS: LinkOptions = LinkOptions {
+    conflict: ConflictPolicy::OverwriteBroken,
+    broken_src: BrokenSrcPolicy::Error,
+    target_kind: TargetKind::Auto,
+    kind: LinkKind::Symlink,
+    fallback: LinkFallback::NONE,
+};
+
+impl Default for Transaction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::with_fs(std_fs())
+    }
+
+    /// 使用指定文件系统的事务，只影响add_op_*系列操作，add_op的闭包自行决定访问方式
+    pub fn with_fs(fs: SharedFs) -> Self {
+        Transaction {
+            undo_ops_name: Vec::new(),
+            undo_ops: Vec::new(),
+            fs,
+            link_options: TX_LINK_OPTIONS,
+        }
+    }
+
+    /// 替换add_op_mklink/add_op_del_link创建链接时使用的选项，默认为[`TX_LINK_OPTIONS`]
+    pub fn with_link_options(mut self, options: LinkOptions) -> Self {
+        self.link_options = options;
+        self
+    }
+
+    /// 添加一个操作及其撤销函数
+    pub fn add_op<F, U>(&mut self, op: F, undo: U, name: Option<String>) -> MyResult<()>
+    where
+        F: FnOnce() -> MyResult<()>,
+        U: FnOnce() -> MyResult<()> + 'static,
+    {
+        let name = name.unwrap_or_default();
+        // 执行操作
+        log::debug!("执行操作 {} 中", name);
+        op().inspect_err(|e| {
+            log::warn!("执行操作 {} 失败: {}", name, e);
+        })?;
+        log::debug!("执行操作 {} 成功", name);
+        // 如果成功，记录撤销函数
+        self.undo_ops_name.push(name);
+        self.undo_ops.push(Box::new(undo));
+        Ok(())
+    }
+
+    /// 提交事务（清空撤销操作）
+    pub fn commit(mut self) -> MyResult<()> {
+        self.undo_ops.clear();
+        self.undo_ops_name.clear();
+        Ok(())
+    }
+
+    /// 回滚所有操作
+    pub fn rollback(&mut self) -> MyResult<()> {
+        // 从后向前执行撤销操作
+        for (name, undo) in zip(
+            self.undo_ops_name.drain(..).rev(),
+            self.undo_ops.drain(..).rev(),
+        ) {
+            log::debug!("回滚操作 {} 中", name);
+            undo().inspect_err(|e| {
+                log::warn!("回滚操作 {} 失败: {}", name, e);
+            })?;
+            log::debug!("回滚操作 {} 成功", name);
+        }
+        Ok(())
+    }
+
+    pub fn add_op_rename_dir(
+        &mut self,
+        from: PathBuf,
+        to: PathBuf,
+        name: Option<String>,
+    ) -> MyResult<()> {
+        let (op, undo) = op_rename_dir(self.fs.clone(), from, to);
+        self.add_op(op, undo, name)
+    }
+
+    /// 创建单个目录（父目录需已存在），undo删除该目录，目录非空时undo失败
+    pub fn add_op_mkdir(&mut self, dir: PathBuf, name: Option<String>) -> MyResult<()> {
+        let (op, undo) = op_mkdir(self.fs.clone(), dir);
+        self.add_op(op, undo, name)
+    }
+
+    pub fn add_op_mklink(
+        &mut self,
+        original: PathBuf,
+        link: PathBuf,
+        name: Option<String>,
+    ) -> MyResult<()> {
+        let (op, undo) = op_mklink(self.fs.clone(), self.link_options, original, link);
+        self.add_op(op, undo, name)
+    }
+
+    pub fn add_op_del_link(
+        &mut self,
+        original: PathBuf,
+        link: PathBuf,
+        name: Option<String>,
+    ) -> MyResult<()> {
+        let (op, undo) = op_del_link(self.fs.clone(), self.link_options, original, link);
+        self.add_op(op, undo, name)
+    }
+
+    /// unsafe表示它undo操作将创建一个指向不存在的路径的符号链接
+    pub fn add_op_del_link_unsafe_dir(
+        &mut self,
+        original: PathBuf,
+        link: PathBuf,
+        name: Option<String>,
+    ) -> MyResult<()> {
+        let (op, undo) = op_del_link_unsafe_dir(self.fs.clone(), original, link);
+        self.add_op(op, undo, name)
+    }
+
+    // /// unsafe表示它undo操作将创建一个指向不存在的路径的符号链接
+    // pub fn add_op_del_link_unsafe_file(
+    //     &mut self,
+    //     original: PathBuf,
+    //     link: PathBuf,
+    //     name: Option<String>,
+    // ) -> MyResult<()> {
+    //     let (op, undo) = op_del_link_unsafe_file(original, link);
+    //     self.add_op(op, undo, name)
+    // }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        // 如果未显式提交或回滚，自动回滚
+        if !self.undo_ops.is_empty() {
+            log::warn!("正在回滚操作");
+            if let Err(e) = self.rollback() {
+                eprintln!("回滚失败: {}", e);
+            }
+        }
+    }
+}
+
+fn op_del_link(
+    fs: SharedFs,
+    options: LinkOptions,
+    original: PathBuf,
+    link: PathBuf,
+) -> (impl FnOnce() -> MyResult<()>, impl FnOnce() -> MyResult<()>) {
+    let src = original;
+    let dst = link;
+    // let src_c = src.clone();
+    let dst_c = dst.clone();
+
+    let fs_c = fs.clone();
+
+    let op = move || del_exists_link_with(fs_c.as_ref(), &dst_c, true, Some(false)).map(|_| ());
+    // 恢复被删除的链接，原目标可能已不存在
+    let options = LinkOptions {
+        broken_src: BrokenSrcPolicy::Allow,
+        ..options
+    };
+    let undo = move || mklink_with(fs.as_ref(), &src, &dst, options).map(|_| ());
+    (op, undo)
+}
+
+fn op_del_link_unsafe_dir(
+    fs: SharedFs,
+    original: PathBuf,
+    link: PathBuf,
+) -> (impl FnOnce() -> MyResult<()>, impl FnOnce() -> MyResult<()>) {
+    let src = original;
+    let dst = link;
+    // let src_c = src.clone();
+    let dst_c = dst.clone();
+
+    let fs_c = fs.clone();
+
+    let op = move || del_exists_link_with(fs_c.as_ref(), &dst_c, true, Some(false)).map(|_| ());
+    let undo = move || create_symlink_as(fs.as_ref(), &src, &dst, TargetKind::Dir);
+
+    (op, undo)
+}
+
+// fn op_del_link_unsafe_file(
+//     original: PathBuf,
+//     link: PathBuf,
+// ) -> (impl FnOnce() -> MyResult<()>, impl FnOnce() -> MyResult<()>) {
+//     let src = original;
+//     let dst = link;
+//     // let src_c = src.clone();
+//     let dst_c = dst.clone();
+
+//     let op = move || del_exists_link(&dst_c, true, Some(false)).map(|_| ());
+//     let undo = || mklink_when_src_file_not_exists(src, dst);
+
+//     (op, undo)
+// }
+
+// fn op_null() -> (impl FnOnce() -> MyResult<()>, impl FnOnce() -> MyResult<()>) {
+//     let op = move || Ok(());
+//     let undo = move || Ok(());
+
+//     (op, undo)
+// }
+
+fn op_rename_dir(
+    fs: SharedFs,
+    from: PathBuf,
+    to: PathBuf,
+) -> (impl FnOnce() -> MyResult<()>, impl FnOnce() -> MyResult<()>) {
+    let from_clone = from.clone();
+    let to_clone = to.clone();
+    let fs_c = fs.clone();
+
+    let op = move || {
+        fs_c.rename(&from_clone, &to_clone).map_err(move |e| {
+            MyError::new(
+                ErrorCode::IoError,
+                format!(
+                    "尝试将{}重命名为临时名称{}失败：{e}",
+                    from_clone.display(),
+                    to_clone.display()
+                ),
+            )
+        })
+    };
+    let undo = move || {
+        // 预期内：源路径不存在，目标路径存在
+        if !fs.exists(&from) && fs.exists(&to) {
+            fs.rename(&to, &from).map_err(move |e| {
+                MyError::new(
+                    ErrorCode::IoError,
+                    format!(
+                        "尝试将{}重命名为临时名称{}失败：{e}",
+                        to.display(),
+                        from.display()
+                    ),
+                )
+            })
+        // 问题：两路径都不存在
+        } else if !fs.exists(&from) && !fs.exists(&to) {
+            Err(MyError {
+                code: ErrorCode::Unknown,
+                msg: format!("重大问题：{}与{}不见了！", from.display(), to.display()),
+            })
+        // 安全：源路径存在，目标路径不存在
+        } else if fs.exists(&from) && !fs.exists(&to) {
+            Ok(())
+        // 问题：两路径都存在
+        } else {
+            log::warn!("{}与{}都已存在", &from.display(), &to.display());
+            let empty = !fs
+                .read_dir(&to)
+                .map_err(|e| {
+                    MyError::new(
+                        ErrorCode::IoError,
+                        format!(
+                            "{}与{}都已存在，且检查前者是否为空目录失败: {e}",
+                            &from.display(),
+                            &to.display()
+                        ),
+                    )
+                })?
+                .is_empty();
+            // to为空
+            if empty {
+                fs.rename(&to, &from).map_err(move |e| {
+                    MyError::new(
+                        ErrorCode::IoError,
+                        format!(
+                            "尝试将{}重命名为临时名称{}失败：{e}",
+                            &to.display(),
+                            &from.display(),
+                        ),
+                    )
+                })?;
+                Ok(())
+            // to非空
+            } else {
+                Err(MyError::new(
+                    ErrorCode::DirectoryNotEmpty,
+                    format!(
+                        "{}与{}都已存在，且前者非空目录",
+                        &from.display(),
+                        &to.display()
+                    ),
+                ))
+            }
+        }
+    };
+
+    (op, undo)
+}
+
+fn op_mkdir(
+    fs: SharedFs,
+    dir: PathBuf,
+) -> (impl FnOnce() -> MyResult<()>, impl FnOnce() -> MyResult<()>) {
+    let dir_c = dir.clone();
+    let fs_c = fs.clone();
+
+    let op = move || {
+        fs_c.create_dir(&dir_c).map_err(|e| {
+            MyError::new(
+                ErrorCode::FailToMakeDir,
+                format!("{}: {}", dir_c.display(), e),
+            )
+        })
+    };
+    let undo = move || {
+        fs.remove_dir(&dir).map_err(|e| {
+            MyError::new(
+                ErrorCode::IoError,
+                format!("删除创建的目录{}失败: {}", dir.display(), e),
+            )
+        })
+    };
+
+    (op, undo)
+}
+
+fn op_mklink(
+    fs: SharedFs,
+    options: LinkOptions,
+    original: PathBuf,
+    link: PathBuf,
+) -> (impl FnOnce() -> MyResult<()>, impl FnOnce() -> MyResult<()>) {
+    let src = original;
+    let dst = link;
+    // let src_c = src.clone();
+    let dst_c = dst.clone();
+
+    let fs_c = fs.clone();
+
+    let op = move || mklink_with(fs_c.as_ref(), &src, &dst, options).map(|_| ());
+
+    let undo = move || del_exists_link_with(fs.as_ref(), &dst_c, true, Some(true)).map(|_| ());
+
+    (op, undo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn counting_undo(counter: &Rc<Cell<u32>>) -> impl FnOnce() -> MyResult<()> {
+        let counter = counter.clone();
+        move || {
+            counter.set(counter.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_rollback_on_drop() {
+        let undone = Rc::new(Cell::new(0));
+        {
+            let mut tx = Transaction::new();
+            tx.add_op(|| Ok(()), counting_undo(&undone), None).unwrap();
+            tx.add_op(|| Ok(()), counting_undo(&undone), None).unwrap();
+            let failed = tx.add_op(
+                || Err(MyError::new(ErrorCode::Unknown, "失败".into())),
+                counting_undo(&undone),
+                None,
+            );
+            assert!(failed.is_err());
+        }
+        // 失败的操作不会记录undo，已成功的两个操作被回滚
+        assert_eq!(undone.get(), 2);
+
+        let mut tx = Transaction::new();
+        tx.add_op(|| Ok(()), counting_undo(&undone), None).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(undone.get(), 2);
+    }
+
+    #[test]
+    fn test_rollback_on_mem_fs() {
+        use crate::utils::vfs::{Fs, FsOp, MemFs};
+        use std::path::Path;
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        mem.add_dir("/data/sub").fail_nth(FsOp::Symlink, 1);
+        {
+            let mut tx = Transaction::with_fs(mem.clone());
+            tx.add_op_rename_dir("/data".into(), "/backup".into(), None)
+                .unwrap();
+            assert!(tx
+                .add_op_mklink("/backup".into(), "/data".into(), None)
+                .is_err());
+        }
+        // 创建符号链接失败，重命名被回滚
+        assert!(mem.metadata(Path::new("/data/sub")).unwrap().is_dir());
+        assert!(!mem.exists(Path::new("/backup")));
+    }
+
+    #[test]
+    /// src不存在时mklink报错，不会创建悬空链接，之前的操作被回滚
+    fn test_mklink_missing_src_rolls_back() {
+        use crate::utils::vfs::{Fs, MemFs};
+        use std::path::Path;
+        use std::sync::Arc;
+
+        let mem = Arc::new(MemFs::new());
+        mem.add_dir("/data/sub");
+        {
+            let mut tx = Transaction::with_fs(mem.clone());
+            tx.add_op_rename_dir("/data".into(), "/backup".into(), None)
+                .unwrap();
+            let err = tx
+                .add_op_mklink("/missing".into(), "/data".into(), None)
+                .unwrap_err();
+            assert_eq!(err.code, ErrorCode::FileNotExist);
+        }
+        assert!(mem.symlink_metadata(Path::new("/data")).unwrap().is_dir());
+        assert!(!mem.exists(Path::new("/backup")));
+        assert!(!mem.journal().iter().any(|op| op.starts_with("symlink")));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::utils::expand::expand_path;
+use crate::utils::fs::collect_symlinks;
+use crate::utils::path::canonicalize_path;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// 导出脚本的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    /// POSIX shell，使用`ln -s`
+    Sh,
+    /// PowerShell，使用`New-Item -ItemType SymbolicLink`
+    Ps1,
+    /// Windows批处理，使用`mklink`
+    Cmd,
+}
+
+impl std::str::FromStr for ScriptKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sh" => Ok(Self::Sh),
+            "ps1" | "powershell" => Ok(Self::Ps1),
+            "cmd" | "bat" => Ok(Self::Cmd),
+            _ => Err(format!("无效的脚本类型 '{}'，可选: sh, ps1, cmd", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for ScriptKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Sh => write!(f, "sh"),
+            Self::Ps1 => write!(f, "ps1"),
+            Self::Cmd => write!(f, "cmd"),
+        }
+    }
+}
+
+/// 脚本中要创建的一个符号链接
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptLink {
+    /// 链接目标，原样写入脚本（可以是相对链接所在目录的路径）
+    pub target: PathBuf,
+    /// 链接路径
+    pub link: PathBuf,
+    /// 目标是否为目录，决定`mklink /D`
+    pub is_dir: bool,
+}
+
+impl ScriptLink {
+    /// 由(<SRC>, [DST])路径对构建，目标类型由<SRC>决定
+    pub fn from_pair(src: PathBuf, dst: PathBuf) -> Self {
+        let is_dir = src.is_dir();
+        ScriptLink {
+            target: src,
+            link: dst,
+            is_dir,
+        }
+    }
+}
+
+/// 递归扫描path下已有的符号链接（不跟随符号链接深入），损坏的符号链接同样导出
+pub fn scan_links(path: &Path) -> MyResult<Vec<ScriptLink>> {
+    let root = canonicalize_path(expand_path(path)?);
+    let mut links = collect_symlinks(&root)?;
+    links.sort();
+    links
+        .into_iter()
+        .map(|link| {
+            let target = std::fs::read_link(&link).map_err(|e| {
+                MyError::new(
+                    ErrorCode::IoError,
+                    format!("读取符号链接{}失败: {}", link.display(), e),
+                )
+            })?;
+            let is_dir = link.is_dir();
+            Ok(ScriptLink {
+                target,
+                link,
+                is_dir,
+            })
+        })
+        .collect()
+}
+
+/// 生成可独立运行的脚本：先创建所需的父目录，再逐个创建符号链接，已存在的链接路径会被跳过
+pub fn render_script(kind: ScriptKind, links: &[ScriptLink]) -> MyResult<String> {
+    let dirs: BTreeSet<&Path> = links.iter().filter_map(|l| l.link.parent()).collect();
+    let mut out = String::new();
+    match kind {
+        ScriptKind::Sh => {
+            out.push_str("#!/bin/sh\n# 由fastlink export生成\nset -e\n\n");
+            out.push_str(
+                "link() {\n    if [ -e \"$2\" ] || [ -L \"$2\" ]; then\n        echo \"已存在，跳过: $2\" >&2\n    else\n        ln -s -- \"$1\" \"$2\"\n    fi\n}\n\n",
+            );
+            for dir in dirs {
+                let _ = writeln!(out, "mkdir -p -- {}", quote_sh(dir));
+            }
+            for l in links {
+                let _ = writeln!(out, "link {} {}", quote_sh(&l.target), quote_sh(&l.link));
+            }
+        }
+        ScriptKind::Ps1 => {
+            out.push_str("# 由fastlink export生成\n$ErrorActionPreference = 'Stop'\n\n");
+            for dir in dirs {
+                let _ = writeln!(
+                    out,
+                    "New-Item -ItemType Directory -Force -Path {} | Out-Null",
+                    quote_ps1(dir)
+                );
+            }
+            for l in links {
+                let link = quote_ps1(&l.link);
+                let _ = writeln!(
+                    out,
+                    "if (-not (Test-Path -LiteralPath {link})) {{ New-Item -ItemType SymbolicLink -Path {link} -Target {} | Out-Null }}",
+                    quote_ps1(&l.target)
+                );
+            }
+        }
+        ScriptKind::Cmd => {
+            out.push_str("@echo off\r\nrem 由fastlink export生成\r\nchcp 65001 >nul\r\n\r\n");
+            for dir in dirs {
+                let dir = quote_cmd(dir)?;
+                let _ = write!(out, "if not exist {dir} mkdir {dir}\r\n");
+            }
+            for l in links {
+                let link = quote_cmd(&l.link)?;
+                let flag = if l.is_dir { "/D " } else { "" };
+                let _ = write!(
+                    out,
+                    "if not exist {link} mklink {flag}{link} {} >nul\r\n",
+                    quote_cmd(&l.target)?
+                );
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// sh单引号转义：`'`写作`'\''`
+fn quote_sh(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"))
+}
+
+/// PowerShell单引号转义：`'`写作`''`
+fn quote_ps1(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "''"))
+}
+
+/// cmd双引号：`%`写作`%%`，路径中不能包含`"`
+fn quote_cmd(path: &Path) -> MyResult<String> {
+    let s = path.to_string_lossy();
+    if s.contains('"') || s.contains('\n') || s.contains('\r') {
+        return Err(MyError::new(
+            ErrorCode::InvalidInput,
+            format!("路径无法写入cmd脚本: {:?}", s),
+        ));
+    }
+    Ok(format!("\"{}\"", s.replace('%', "%%")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links() -> Vec<ScriptLink> {
+        vec![
+            ScriptLink {
+                target: PathBuf::from("/data/it's here"),
+                link: PathBuf::from("/home/u/links/a"),
+                is_dir: true,
+            },
+            ScriptLink {
+                target: PathBuf::from("../100%.txt"),
+                link: PathBuf::from("/home/u/links/b"),
+                is_dir: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_script_quoting() {
+        let sh = render_script(ScriptKind::Sh, &links()).unwrap();
+        assert!(sh.contains("mkdir -p -- '/home/u/links'\n"));
+        assert!(sh.contains(r"link '/data/it'\''s here' '/home/u/links/a'"));
+
+        let ps1 = render_script(ScriptKind::Ps1, &links()).unwrap();
+        assert!(ps1.contains("-Target '/data/it''s here'"));
+
+        let cmd = render_script(ScriptKind::Cmd, &links()).unwrap();
+        assert!(cmd.contains("mklink /D \"/home/u/links/a\" \"/data/it's here\""));
+        assert!(cmd.contains("mklink \"/home/u/links/b\" \"../100%%.txt\""));
+
+        let bad = vec![ScriptLink {
+            target: PathBuf::from("a\"b"),
+            link: PathBuf::from("c"),
+            is_dir: false,
+        }];
+        assert!(render_script(ScriptKind::Cmd, &bad).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_exported_sh_recreates_links() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tree = tmp.path().join("tree");
+        std::fs::create_dir_all(tree.join("sub")).unwrap();
+        std::os::unix::fs::symlink("/data/it's here", tree.join("sub/a")).unwrap();
+        std::os::unix::fs::symlink("../x y", tree.join("b")).unwrap();
+
+        let links = scan_links(&tree).unwrap();
+        assert_eq!(links.len(), 2);
+        let script = tmp.path().join("links.sh");
+        std::fs::write(&script, render_script(ScriptKind::Sh, &links).unwrap()).unwrap();
+
+        std::fs::remove_dir_all(&tree).unwrap();
+        let status = std::process::Command::new("sh")
+            .arg(&script)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(
+            std::fs::read_link(tree.join("sub/a")).unwrap(),
+            Path::new("/data/it's here")
+        );
+        assert_eq!(
+            std::fs::read_link(tree.join("b")).unwrap(),
+            Path::new("../x y")
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! Windows快捷方式（.lnk，MS-SHLLINK）的读写，纯文件编码，不依赖Windows API
+//!
+//! 写入的快捷方式只包含ShellLinkHeader、LinkInfo（本地路径，含Unicode路径）、StringData与空的ExtraData；
+//! 读取时跳过LinkTargetIDList，依次从LinkInfo、EnvironmentVariableDataBlock、RELATIVE_PATH中解析目标
+
+use crate::types::err::{ErrorCode, MyError, MyResult};
+use crate::utils::vfs::{Fs, StdFs};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// ShellLinkHeader的固定大小
+const HEADER_SIZE: u32 = 0x4C;
+/// LinkCLSID: 00021401-0000-0000-C000-000000000046
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+// LinkFlags
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x1;
+const HAS_LINK_INFO: u32 = 0x2;
+const HAS_NAME: u32 = 0x4;
+const HAS_RELATIVE_PATH: u32 = 0x8;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const HAS_ICON_LOCATION: u32 = 0x40;
+const IS_UNICODE: u32 = 0x80;
+
+// FileAttributes
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+const FILE_ATTRIBUTE_ARCHIVE: u32 = 0x20;
+
+// LinkInfoFlags
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+const COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX: u32 = 0x2;
+
+/// 包含Unicode路径偏移的LinkInfo头大小
+const LINK_INFO_HEADER_UNICODE: u32 = 0x24;
+/// DRIVE_FIXED
+const DRIVE_FIXED: u32 = 3;
+/// SW_SHOWNORMAL
+const SHOW_NORMAL: u32 = 1;
+const ENVIRONMENT_VARIABLE_DATA_BLOCK: u32 = 0xA000_0001;
+
+/// 快捷方式的扩展名
+pub const SHORTCUT_EXTENSION: &str = "lnk";
+
+/// 一个Windows快捷方式，路径均为Windows形式的字符串
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Shortcut {
+    /// 目标路径，通常为绝对路径
+    pub target: String,
+    /// 目标是否为目录
+    pub is_dir: bool,
+    /// 描述（NAME_STRING）
+    pub description: Option<String>,
+    /// 相对快捷方式所在目录的目标路径
+    pub relative_path: Option<String>,
+    /// 起始位置
+    pub working_dir: Option<String>,
+    /// 命令行参数
+    pub arguments: Option<String>,
+    /// 图标位置
+    pub icon_location: Option<String>,
+}
+
+impl Shortcut {
+    pub fn new(target: impl Into<String>, is_dir: bool) -> Self {
+        Shortcut {
+            target: target.into(),
+            is_dir,
+            ..Default::default()
+        }
+    }
+
+    /// 编码为.lnk文件内容，时间戳与文件大小均为0，相同输入得到相同的字节
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let strings = [
+            (HAS_NAME, &self.description),
+            (HAS_RELATIVE_PATH, &self.relative_path),
+            (HAS_WORKING_DIR, &self.working_dir),
+            (HAS_ARGUMENTS, &self.arguments),
+            (HAS_ICON_LOCATION, &self.icon_location),
+        ];
+        let flags = strings
+            .iter()
+            .filter(|(_, value)| value.is_some())
+            .fold(HAS_LINK_INFO | IS_UNICODE, |flags, (flag, _)| flags | flag);
+        let attributes = if self.is_dir {
+            FILE_ATTRIBUTE_DIRECTORY
+        } else {
+            FILE_ATTRIBUTE_ARCHIVE
+        };
+
+        let mut out = Vec::new();
+        // ShellLinkHeader
+        put_u32(&mut out, HEADER_SIZE);
+        out.extend_from_slice(&LINK_CLSID);
+        put_u32(&mut out, flags);
+        put_u32(&mut out, attributes);
+        out.extend_from_slice(&[0; 24]); // CreationTime, AccessTime, WriteTime
+        put_u32(&mut out, 0); // FileSize
+        put_u32(&mut out, 0); // IconIndex
+        put_u32(&mut out, SHOW_NORMAL);
+        out.extend_from_slice(&[0; 2 + 2 + 4 + 4]); // HotKey, Reserved1-3
+
+        out.extend_from_slice(&link_info(&self.target));
+
+        for value in strings.iter().filter_map(|(_, value)| value.as_ref()) {
+            let units: Vec<u16> = value.encode_utf16().collect();
+            out.extend_from_slice(&(units.len() as u16).to_le_bytes());
+            units
+                .iter()
+                .for_each(|u| out.extend_from_slice(&u.to_le_bytes()));
+        }
+        // TerminalBlock
+        put_u32(&mut out, 0);
+        out
+    }
+
+    /// 解析.lnk文件内容
+    pub fn from_bytes(data: &[u8]) -> MyResult<Self> {
+        let mut r = Reader { data, pos: 0 };
+        if r.u32()? != HEADER_SIZE || r.bytes(16)? != LINK_CLSID {
+            return Err(MyError::new(
+                ErrorCode::InvalidInput,
+                "不是有效的快捷方式(.lnk)文件".into(),
+            ));
+        }
+        let flags = r.u32()?;
+        let attributes = r.u32()?;
+        r.bytes(HEADER_SIZE as usize - r.pos)?;
+
+        if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+            let size = r.u16()? as usize;
+            r.bytes(size)?;
+        }
+        let mut target = None;
+        if flags & HAS_LINK_INFO != 0 {
+            let start = r.pos;
+            let size = r.u32()? as usize;
+            r.pos = start;
+            target = read_link_info(r.bytes(size)?)?;
+        }
+
+        let mut shortcut = Shortcut {
+            is_dir: attributes & FILE_ATTRIBUTE_DIRECTORY != 0,
+            ..Default::default()
+        };
+        for (flag, field) in [
+            (HAS_NAME, &mut shortcut.description),
+            (HAS_RELATIVE_PATH, &mut shortcut.relative_path),
+            (HAS_WORKING_DIR, &mut shortcut.working_dir),
+            (HAS_ARGUMENTS, &mut shortcut.arguments),
+            (HAS_ICON_LOCATION, &mut shortcut.icon_location),
+        ] {
+            if flags & flag != 0 {
+                let count = r.u16()? as usize;
+                *field = Some(if flags & IS_UNICODE != 0 {
+                    utf16(r.bytes(count * 2)?)
+                } else {
+                    ansi(r.bytes(count)?)
+                });
+            }
+        }
+
+        // ExtraData，以小于4的块大小（TerminalBlock）结束
+        let mut env_target = None;
+        while r.pos + 4 <= data.len() {
+            let start = r.pos;
+            let size = r.u32()? as usize;
+            if size < 4 {
+                break;
+            }
+            if size >= 8 + 260 + 520 && r.u32()? == ENVIRONMENT_VARIABLE_DATA_BLOCK {
+                let target_ansi = ansi_z(r.bytes(260)?);
+                let target_unicode = utf16_z(r.bytes(520)?);
+                env_target = Some(if target_unicode.is_empty() {
+                    target_ansi
+                } else {
+                    target_unicode
+                });
+            }
+            r.pos = start;
+            r.bytes(size)?;
+        }
+
+        shortcut.target = target
+            .or(env_target)
+            .or_else(|| shortcut.relative_path.clone())
+            .ok_or_else(|| {
+                MyError::new(
+                    ErrorCode::InvalidInput,
+                    "无法解析快捷方式的目标：不包含LinkInfo、环境变量路径或相对路径".into(),
+                )
+            })?;
+        Ok(shortcut)
+    }
+}
+
+/// 读取快捷方式文件
+pub fn read_shortcut(path: &Path) -> MyResult<Shortcut> {
+    read_shortcut_with(&StdFs, path)
+}
+
+/// 同`read_shortcut`，通过fs访问文件系统
+pub fn read_shortcut_with(fs: &dyn Fs, path: &Path) -> MyResult<Shortcut> {
+    let mut data = Vec::new();
+    let res = fs
+        .open(path)
+        .and_then(|mut file| file.read_to_end(&mut data));
+    res.map_err(|e| {
+        MyError::new(
+            ErrorCode::IoError,
+            format!("读取快捷方式{}失败: {}", path.display(), e),
+        )
+    })?;
+    Shortcut::from_bytes(&data).map_err(|mut e| {
+        e.msg = format!("{}: {}", path.display(), e.msg);
+        e
+    })
+}
+
+/// 写入快捷方式文件，已存在时覆盖
+pub fn write_shortcut(path: &Path, shortcut: &Shortcut) -> MyResult<()> {
+    write_shortcut_with(&StdFs, path, shortcut)
+}
+
+/// 同`write_shortcut`，通过fs访问文件系统
+pub fn write_shortcut_with(fs: &dyn Fs, path: &Path, shortcut: &Shortcut) -> MyResult<()> {
+    fs.write(path, &shortcut.to_bytes()).map_err(|e| {
+        MyError::new(
+            ErrorCode::FailAtMakeLink,
+            format!("写入快捷方式{}失败: {}", path.display(), e),
+        )
+    })
+}
+
+/// 是否为.lnk扩展名（不区分大小写）
+pub fn is_shortcut_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(SHORTCUT_EXTENSION))
+}
+
+/// 快捷方式文件的路径：没有.lnk扩展名时追加
+pub fn shortcut_path(path: &Path) -> PathBuf {
+    if is_shortcut_path(path) {
+        path.to_path_buf()
+    } else {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(".");
+        name.push(SHORTCUT_EXTENSION);
+        PathBuf::from(name)
+    }
+}
+
+/// 只包含本地路径的LinkInfo，同时写入ANSI（非ASCII字符写为`?`）与Unicode路径
+fn link_info(target: &str) -> Vec<u8> {
+    let mut volume_id = Vec::new();
+    put_u32(&mut volume_id, 0x11); // VolumeIDSize
+    put_u32(&mut volume_id, DRIVE_FIXED);
+    put_u32(&mut volume_id, 0); // DriveSerialNumber
+    put_u32(&mut volume_id, 0x10); // VolumeLabelOffset
+    volume_id.push(0); // 空卷标
+
+    let mut base: Vec<u8> = target
+        .chars()
+        .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+        .collect();
+    base.push(0);
+    let suffix = [0u8];
+    let mut base_unicode: Vec<u8> = target.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    base_unicode.extend_from_slice(&[0, 0]);
+    let suffix_unicode = [0u8; 2];
+
+    let volume_offset = LINK_INFO_HEADER_UNICODE;
+    let base_offset = volume_offset + volume_id.len() as u32;
+    let suffix_offset = base_offset + base.len() as u32;
+    let base_unicode_offset = suffix_offset + suffix.len() as u32;
+    let suffix_unicode_offset = base_unicode_offset + base_unicode.len() as u32;
+    let size = suffix_unicode_offset + suffix_unicode.len() as u32;
+
+    let mut out = Vec::with_capacity(size as usize);
+    for value in [
+        size,
+        LINK_INFO_HEADER_UNICODE,
+        VOLUME_ID_AND_LOCAL_BASE_PATH,
+        volume_offset,
+        base_offset,
+        0, // CommonNetworkRelativeLinkOffset
+        suffix_offset,
+        base_unicode_offset,
+        suffix_unicode_offset,
+    ] {
+        put_u32(&mut out, value);
+    }
+    out.extend_from_slice(&volume_id);
+    out.extend_from_slice(&base);
+    out.extend_from_slice(&suffix);
+    out.extend_from_slice(&base_unicode);
+    out.extend_from_slice(&suffix_unicode);
+    out
+}
+
+/// 从LinkInfo解析目标：本地路径或网络路径，再拼接CommonPathSuffix
+fn read_link_info(info: &[u8]) -> MyResult<Option<String>> {
+    let field = |index: usize| {
+        Reader {
+            data: info,
+            pos: index * 4,
+        }
+        .u32()
+        .map(|v| v as usize)
+    };
+    let header_size = field(1)?;
+    let flags = field(2)? as u32;
+    let unicode = header_size >= LINK_INFO_HEADER_UNICODE as usize;
+    let string_at = |offset: usize, unicode_offset: Option<usize>| -> MyResult<String> {
+        match unicode_offset.filter(|offset| *offset != 0) {
+            Some(offset) => Ok(utf16_z(slice_from(info, offset)?)),
+            None => Ok(ansi_z(slice_from(info, offset)?)),
+        }
+    };
+
+    let base = if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+        string_at(field(4)?, if unicode { Some(field(7)?) } else { None })?
+    } else if flags & COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX != 0 {
+        // CommonNetworkRelativeLink中的NetName
+        let link = slice_from(info, field(5)?)?;
+        let net_name_offset = Reader { data: link, pos: 8 }.u32()? as usize;
+        ansi_z(slice_from(link, net_name_offset)?)
+    } else {
+        return Ok(None);
+    };
+    let suffix = string_at(field(6)?, if unicode { Some(field(8)?) } else { None })?;
+
+    Ok(Some(if suffix.is_empty() {
+        base
+    } else if base.ends_with('\\') {
+        base + &suffix
+    } else {
+        format!("{}\\{}", base, suffix)
+    }))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> MyResult<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len());
+        let Some(end) = end else {
+            return Err(truncated());
+        };
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> MyResult<u16> {
+        self.bytes(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> MyResult<u32> {
+        self.bytes(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+fn truncated() -> MyError {
+    MyError::new(ErrorCode::InvalidInput, "快捷方式文件已截断或已损坏".into())
+}
+
+fn slice_from(data: &[u8], offset: usize) -> MyResult<&[u8]> {
+    data.get(offset..).ok_or_else(truncated)
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// 以NUL结尾的UTF-16字符串
+fn utf16_z(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|u| *u != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// ANSI字符串，代码页未知，按UTF-8宽松解码
+fn ansi(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// 以NUL结尾的ANSI字符串
+fn ansi_z(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    ansi(&bytes[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE_LNK: &[u8] = include_bytes!("../../tests/fixtures/file.lnk");
+    const DIR_LNK: &[u8] = include_bytes!("../../tests/fixtures/dir.lnk");
+    const EXPLORER_LNK: &[u8] = include_bytes!("../../tests/fixtures/explorer.lnk");
+    const NETWORK_LNK: &[u8] = include_bytes!("../../tests/fixtures/network.lnk");
+
+    fn file_shortcut() -> Shortcut {
+        Shortcut {
+            description: Some("fastlink".into()),
+            relative_path: Some(r"..\Documents\报告 1.txt".into()),
+            working_dir: Some(r"C:\Users\Public\Documents".into()),
+            ..Shortcut::new(r"C:\Users\Public\Documents\报告 1.txt", false)
+        }
+    }
+
+    #[test]
+    fn test_write_shortcut_matches_fixtures() {
+        assert_eq!(file_shortcut().to_bytes(), FILE_LNK);
+        assert_eq!(Shortcut::new(r"D:\Games\Save", true).to_bytes(), DIR_LNK);
+    }
+
+    #[test]
+    fn test_read_shortcut_fixtures() {
+        assert_eq!(Shortcut::from_bytes(FILE_LNK).unwrap(), file_shortcut());
+        assert_eq!(
+            Shortcut::from_bytes(DIR_LNK).unwrap(),
+            Shortcut::new(r"D:\Games\Save", true)
+        );
+
+        // 含IDList、仅ANSI的LinkInfo与多个ExtraData块
+        let explorer = Shortcut::from_bytes(EXPLORER_LNK).unwrap();
+        assert_eq!(explorer.target, r"C:\Program Files\App\app.exe");
+        assert_eq!(explorer.arguments.as_deref(), Some(r#"--safe "x y""#));
+        assert_eq!(
+            explorer.working_dir.as_deref(),
+            Some(r"C:\Program Files\App")
+        );
+        assert!(!explorer.is_dir);
+
+        let network = Shortcut::from_bytes(NETWORK_LNK).unwrap();
+        assert_eq!(network.target, r"\\server\share\docs\a.txt");
+    }
+
+    #[test]
+    fn test_read_shortcut_rejects_invalid() {
+        assert!(Shortcut::from_bytes(b"not a shortcut").is_err());
+        assert!(Shortcut::from_bytes(&FILE_LNK[..FILE_LNK.len() - 20]).is_err());
+        assert_eq!(shortcut_path(Path::new("a/game")), Path::new("a/game.lnk"));
+        assert_eq!(shortcut_path(Path::new("a/b.LNK")), Path::new("a/b.LNK"));
+    }
+}
@@ -0,0 +1,1411 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// 文件系统抽象，创建/删除/检查符号链接与事务操作均通过它访问文件系统
+///
+/// 默认使用[`StdFs`]，测试与预览时可替换为[`MemFs`]
+pub trait Fs: fmt::Debug + Send + Sync {
+    /// 读取路径本身的元数据，不跟随符号链接
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    /// 读取元数据，跟随符号链接
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// 读取文件标识，跟随符号链接，用于识别硬链接与判断是否跨越文件系统
+    fn file_id(&self, path: &Path) -> io::Result<FileId>;
+
+    /// 打开文件读取内容，跟随符号链接
+    fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read>>;
+
+    /// 写入文件，已存在时覆盖其内容
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+
+    /// 在link创建指向original的符号链接，is_dir只在Windows下用于区分目录/文件符号链接
+    fn symlink(&self, original: &Path, link: &Path, is_dir: bool) -> io::Result<()>;
+
+    /// 在link创建指向original的硬链接，只支持文件
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()>;
+
+    /// 复制文件内容与权限，返回复制的字节数
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<u64>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// 删除空目录，或Windows下的目录符号链接
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// 以from原子地替换已存在的符号链接to，不支持原子替换时返回`ErrorKind::Unsupported`
+    fn replace_link(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let _ = (from, to);
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// 创建单个目录，父目录需已存在，path已存在时返回`ErrorKind::AlreadyExists`
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// 跟随所有符号链接得到绝对路径，路径不存在（包括损坏的符号链接）时报错
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// 将from的权限（不跟随符号链接）设置到to上，用于复制文件/目录
+    fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// 设置文件/目录（跟随符号链接）的修改时间
+    fn set_modified(&self, path: &Path, time: SystemTime) -> io::Result<()>;
+
+    /// path（需已存在）所在文件系统中当前用户可用的剩余空间（字节）
+    fn available_space(&self, path: &Path) -> io::Result<u64>;
+
+    /// 在dir中以临时名称创建并删除一个符号链接，探测能否在该目录创建符号链接（写入权限与系统/文件系统支持）
+    fn probe_symlink(&self, dir: &Path) -> io::Result<()> {
+        let probe = dir.join(format!(".fastlink-probe-{}", std::process::id()));
+        self.symlink(Path::new("."), &probe, false)?;
+        self.remove_file(&probe)
+    }
+
+    /// 列出目录下的直接子路径
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// 跟随符号链接后路径是否存在，损坏的符号链接返回false
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    /// 读取路径本身（不跟随符号链接）的扩展属性，不支持扩展属性时返回`ErrorKind::Unsupported`
+    fn get_xattr(&self, path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        let _ = (path, name);
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// 设置路径本身（不跟随符号链接）的扩展属性，用于标记fastlink创建的链接，见[`crate::utils::managed`]
+    fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let _ = (path, name, value);
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
+/// 共享的文件系统实例
+pub type SharedFs = Arc<dyn Fs>;
+
+/// 默认的文件系统实例
+pub fn std_fs() -> SharedFs {
+    Arc::new(StdFs)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsFileType {
+    File,
+    Dir,
+    Symlink,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FsMetadata {
+    pub file_type: FsFileType,
+    pub len: u64,
+    /// 修改时间，无法读取时为None
+    pub modified: Option<SystemTime>,
+    /// Unix下的权限位，其他系统为0
+    pub mode: u32,
+    /// Windows下的隐藏属性，其他系统始终为false
+    pub hidden: bool,
+    /// 硬链接数，无法读取（如Windows）时为None
+    pub nlink: Option<u64>,
+}
+
+/// 文件标识：所在设备（卷）与设备内的编号，两个路径的标识相同即为同一文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId {
+    pub dev: u64,
+    pub ino: u64,
+}
+
+impl FsMetadata {
+    pub fn is_file(&self) -> bool {
+        self.file_type == FsFileType::File
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file_type == FsFileType::Dir
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.file_type == FsFileType::Symlink
+    }
+}
+
+impl From<std::fs::Metadata> for FsMetadata {
+    fn from(metadata: std::fs::Metadata) -> Self {
+        let file_type = if metadata.file_type().is_symlink() {
+            FsFileType::Symlink
+        } else if metadata.is_dir() {
+            FsFileType::Dir
+        } else {
+            FsFileType::File
+        };
+        #[cfg(unix)]
+        let (mode, hidden, nlink) = {
+            use std::os::unix::fs::MetadataExt;
+            (metadata.mode(), false, Some(metadata.nlink()))
+        };
+        #[cfg(windows)]
+        let (mode, hidden, nlink) = {
+            use std::os::windows::fs::MetadataExt;
+            const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+            (
+                0,
+                metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0,
+                None,
+            )
+        };
+        #[cfg(not(any(unix, windows)))]
+        let (mode, hidden, nlink) = (0, false, None);
+        FsMetadata {
+            file_type,
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            mode,
+            hidden,
+            nlink,
+        }
+    }
+}
+
+/// 真实文件系统，直接调用std::fs
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdFs;
+
+impl Fs for StdFs {
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        std::fs::symlink_metadata(path).map(FsMetadata::from)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        std::fs::metadata(path).map(FsMetadata::from)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::read_link(path)
+    }
+
+    #[cfg(unix)]
+    fn file_id(&self, path: &Path) -> io::Result<FileId> {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::metadata(path)?;
+        Ok(FileId {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        })
+    }
+
+    #[cfg(windows)]
+    fn file_id(&self, path: &Path) -> io::Result<FileId> {
+        let handle = winapi_util::Handle::from_path_any(path)?;
+        let info = winapi_util::file::information(&handle)?;
+        Ok(FileId {
+            dev: info.volume_serial_number(),
+            ino: info.file_index(),
+        })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn file_id(&self, _path: &Path) -> io::Result<FileId> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        Ok(Box::new(std::fs::File::open(path)?))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        std::fs::write(path, data)
+    }
+
+    #[cfg(unix)]
+    fn symlink(&self, original: &Path, link: &Path, _is_dir: bool) -> io::Result<()> {
+        std::os::unix::fs::symlink(original, link)
+    }
+
+    #[cfg(windows)]
+    fn symlink(&self, original: &Path, link: &Path, is_dir: bool) -> io::Result<()> {
+        if is_dir {
+            std::os::windows::fs::symlink_dir(original, link)
+        } else {
+            std::os::windows::fs::symlink_file(original, link)
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn symlink(&self, _original: &Path, _link: &Path, _is_dir: bool) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+        std::fs::hard_link(original, link)
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        std::fs::copy(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_dir(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    /// Unix下rename会原子地替换目标，且不跟随作为目标的符号链接
+    #[cfg(unix)]
+    fn replace_link(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::canonicalize(path)
+    }
+
+    fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::set_permissions(to, std::fs::symlink_metadata(from)?.permissions())
+    }
+
+    /// 只读文件无法以写入方式打开时改为只读打开，Unix下目录也是如此
+    fn set_modified(&self, path: &Path, time: SystemTime) -> io::Result<()> {
+        let mut options = std::fs::File::options();
+        options.write(true);
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+            // FILE_FLAG_BACKUP_SEMANTICS，打开目录需要
+            options.custom_flags(0x0200_0000);
+        }
+        options
+            .open(path)
+            .or_else(|_| std::fs::File::open(path))?
+            .set_modified(time)
+    }
+
+    #[cfg(unix)]
+    #[allow(clippy::unnecessary_cast)]
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        use std::os::unix::ffi::OsStrExt;
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+        // SAFETY: statvfs是只含整数字段的C结构体，全零是有效值
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: path为以NUL结尾的字符串，stat在调用期间有效
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // 各平台字段类型不同
+        Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+
+    #[cfg(windows)]
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        use std::os::windows::ffi::OsStrExt;
+        use windows::core::PCWSTR;
+        use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+        let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut available = 0u64;
+        // SAFETY: path为以NUL结尾的宽字符串，available在调用期间有效
+        unsafe {
+            GetDiskFreeSpaceExW(
+                PCWSTR(path.as_ptr()),
+                Some(&mut available as *mut u64),
+                None,
+                None,
+            )
+        }
+        .map_err(io::Error::other)?;
+        Ok(available)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn available_space(&self, _path: &Path) -> io::Result<u64> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+    fn get_xattr(&self, path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        xattr::get(path, name)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+    fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        xattr::set(path, name, value)
+    }
+}
+
+/// 读写路径本身（不跟随符号链接）的扩展属性
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+mod xattr {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    fn c_str(path: &Path, name: &str) -> io::Result<(CString, CString)> {
+        Ok((
+            CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?,
+            CString::new(name).map_err(io::Error::other)?,
+        ))
+    }
+
+    pub fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let (path, name) = c_str(path, name)?;
+        // SAFETY: path与name为以NUL结尾的字符串，value的指针与长度来自同一切片
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let res = unsafe {
+            libc::lsetxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+            )
+        };
+        // SAFETY: 同上
+        #[cfg(target_os = "macos")]
+        let res = unsafe {
+            libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+                libc::XATTR_NOFOLLOW,
+            )
+        };
+        if res == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        let (path, name) = c_str(path, name)?;
+        let mut value: Vec<u8> = Vec::new();
+        // 先查询长度再读取，两次调用之间属性被修改时重试
+        loop {
+            // SAFETY: path与name为以NUL结尾的字符串，value的指针与容量来自同一缓冲区
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            let res = unsafe {
+                libc::lgetxattr(
+                    path.as_ptr(),
+                    name.as_ptr(),
+                    value.as_mut_ptr().cast(),
+                    value.capacity(),
+                )
+            };
+            // SAFETY: 同上
+            #[cfg(target_os = "macos")]
+            let res = unsafe {
+                libc::getxattr(
+                    path.as_ptr(),
+                    name.as_ptr(),
+                    value.as_mut_ptr().cast(),
+                    value.capacity(),
+                    0,
+                    libc::XATTR_NOFOLLOW,
+                )
+            };
+            let Ok(len) = usize::try_from(res) else {
+                let e = io::Error::last_os_error();
+                if e.raw_os_error() == Some(libc::ERANGE) {
+                    value = Vec::new();
+                    continue;
+                }
+                return Err(e);
+            };
+            if value.capacity() == 0 && len > 0 {
+                value.reserve_exact(len);
+                continue;
+            }
+            // SAFETY: 系统调用已写入前len个字节，len不超过容量
+            unsafe { value.set_len(len) };
+            return Ok(value);
+        }
+    }
+}
+
+/// 遍历产出的一项，见[`walk`]
+#[derive(Debug, Clone)]
+pub struct WalkEntry {
+    pub path: PathBuf,
+    /// 相对遍历起点的深度，起点为0
+    pub depth: usize,
+    /// 元数据，跟随符号链接时为链接目标的元数据
+    pub metadata: FsMetadata,
+    /// 路径本身是否为符号链接
+    pub path_is_symlink: bool,
+}
+
+impl WalkEntry {
+    pub fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+}
+
+/// 遍历中的一层目录
+struct WalkFrame {
+    children: std::vec::IntoIter<PathBuf>,
+    /// 跟随符号链接时用于检测循环
+    id: Option<FileId>,
+}
+
+/// 通过Fs深度优先遍历目录树，先产出目录再产出其中的内容，同一目录下按名称排序
+///
+/// 起点是符号链接时总是跟随；跟随符号链接时损坏的符号链接按链接本身产出，
+/// 指向祖先目录的符号链接产出后不再深入，并在下一项返回错误
+pub struct Walk<'a> {
+    fs: &'a dyn Fs,
+    root: Option<PathBuf>,
+    max_depth: usize,
+    follow_links: bool,
+    same_file_system: bool,
+    root_dev: Option<u64>,
+    stack: Vec<WalkFrame>,
+    pending: Option<io::Error>,
+    /// 上一个产出的项是否为目录（已为其入栈一层）
+    last_is_dir: bool,
+}
+
+/// 从root开始遍历，默认不限深度、不跟随符号链接
+pub fn walk(fs: &dyn Fs, root: impl Into<PathBuf>) -> Walk<'_> {
+    Walk {
+        fs,
+        root: Some(root.into()),
+        max_depth: usize::MAX,
+        follow_links: false,
+        same_file_system: false,
+        root_dev: None,
+        stack: Vec::new(),
+        pending: None,
+        last_is_dir: false,
+    }
+}
+
+impl Walk<'_> {
+    /// 最大深度（含），为0时只产出起点
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// 跟随符号链接深入遍历
+    pub fn follow_links(mut self, yes: bool) -> Self {
+        self.follow_links = yes;
+        self
+    }
+
+    /// 不进入与起点不在同一文件系统上的目录（目录本身仍会产出）
+    pub fn same_file_system(mut self, yes: bool) -> Self {
+        self.same_file_system = yes;
+        self
+    }
+
+    /// 不再遍历上一个产出的目录的子树；上一项不是目录时什么也不做
+    pub fn skip_current_dir(&mut self) {
+        if std::mem::take(&mut self.last_is_dir) {
+            self.stack.pop();
+        }
+    }
+
+    fn visit(&mut self, path: PathBuf, depth: usize) -> io::Result<WalkEntry> {
+        let link_metadata = self.fs.symlink_metadata(&path)?;
+        let path_is_symlink = link_metadata.is_symlink();
+        let metadata = if path_is_symlink && (self.follow_links || depth == 0) {
+            self.fs.metadata(&path).unwrap_or(link_metadata)
+        } else {
+            link_metadata
+        };
+        if metadata.is_dir() {
+            // 不深入的目录也入栈一个空层，使skip_current_dir总是作用于该目录
+            let children = self.descend(&path, depth).unwrap_or_else(|e| {
+                self.pending = Some(e);
+                Vec::new()
+            });
+            let id = match self.follow_links {
+                true => self.fs.file_id(&path).ok(),
+                false => None,
+            };
+            self.stack.push(WalkFrame {
+                children: children.into_iter(),
+                id,
+            });
+            self.last_is_dir = true;
+        }
+        Ok(WalkEntry {
+            path,
+            depth,
+            metadata,
+            path_is_symlink,
+        })
+    }
+
+    /// 目录下需要继续遍历的子路径
+    fn descend(&mut self, dir: &Path, depth: usize) -> io::Result<Vec<PathBuf>> {
+        if depth >= self.max_depth {
+            return Ok(Vec::new());
+        }
+        if self.same_file_system {
+            let dev = self.fs.file_id(dir)?.dev;
+            match self.root_dev {
+                None => self.root_dev = Some(dev),
+                Some(root_dev) if root_dev != dev => return Ok(Vec::new()),
+                _ => (),
+            }
+        }
+        if self.follow_links {
+            let id = self.fs.file_id(dir)?;
+            if self.stack.iter().any(|frame| frame.id == Some(id)) {
+                return Err(io::Error::other(format!(
+                    "符号链接循环，已跳过 {}",
+                    dir.display()
+                )));
+            }
+        }
+        let mut children = self.fs.read_dir(dir)?;
+        children.sort();
+        Ok(children)
+    }
+}
+
+impl Iterator for Walk<'_> {
+    type Item = io::Result<WalkEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.last_is_dir = false;
+        if let Some(e) = self.pending.take() {
+            return Some(Err(e));
+        }
+        let (path, depth) = match self.root.take() {
+            Some(root) => (root, 0),
+            None => loop {
+                let frame = self.stack.last_mut()?;
+                match frame.children.next() {
+                    Some(path) => break (path, self.stack.len()),
+                    None => {
+                        self.stack.pop();
+                    }
+                }
+            },
+        };
+        Some(self.visit(path, depth))
+    }
+}
+
+/// MemFs中可注入失败的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FsOp {
+    Symlink,
+    HardLink,
+    Copy,
+    Remove,
+    Rename,
+    CreateDir,
+    Write,
+    SetXattr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MemNode {
+    /// 文件内容的编号，硬链接共用同一编号
+    File(u64),
+    Dir,
+    Symlink(PathBuf),
+}
+
+#[derive(Debug, Default)]
+struct MemState {
+    nodes: BTreeMap<PathBuf, MemNode>,
+    /// 每种操作已执行（包括失败）的次数
+    op_counts: HashMap<FsOp, usize>,
+    /// 第n次（从1开始）执行该操作时以该类型的错误失败
+    faults: Vec<(FsOp, usize, io::ErrorKind)>,
+    /// 成功执行的修改操作记录
+    journal: Vec<String>,
+    /// 各路径（不跟随符号链接）的扩展属性，随路径删除或移动
+    xattrs: HashMap<PathBuf, BTreeMap<String, Vec<u8>>>,
+    /// 通过`set_modified`设置过的修改时间，按解析后的路径保存，随路径删除或移动
+    mtimes: HashMap<PathBuf, SystemTime>,
+    /// 各编号的文件内容
+    contents: HashMap<u64, Vec<u8>>,
+    /// 挂载点及其设备号，不在任何挂载点下的路径设备号为0
+    devices: BTreeMap<PathBuf, u64>,
+    /// 各设备的剩余空间，未设置时不限
+    spaces: HashMap<u64, u64>,
+}
+
+/// 内存文件系统，用于测试与预览，路径需为绝对路径
+///
+/// 符号链接的相对目标相对于链接所在目录解析；
+/// 可通过[`MemFs::fail_nth`]注入失败，如第3次创建符号链接时失败
+#[derive(Debug, Default)]
+pub struct MemFs {
+    state: Mutex<MemState>,
+}
+
+impl MemFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加内容为len个0字节的文件，自动创建父目录
+    pub fn add_file(&self, path: impl AsRef<Path>, len: u64) -> &Self {
+        self.add_file_with(path, vec![0; len as usize])
+    }
+
+    /// 添加内容为data的文件，自动创建父目录
+    pub fn add_file_with(&self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) -> &Self {
+        let node = Self::new_file(&mut self.lock(), data.into());
+        self.insert(path.as_ref(), node);
+        self
+    }
+
+    /// 添加目录，自动创建父目录
+    pub fn add_dir(&self, path: impl AsRef<Path>) -> &Self {
+        self.insert(path.as_ref(), MemNode::Dir);
+        self
+    }
+
+    /// 添加符号链接，自动创建父目录，目标可以不存在
+    pub fn add_symlink(&self, original: impl AsRef<Path>, link: impl AsRef<Path>) -> &Self {
+        self.insert(
+            link.as_ref(),
+            MemNode::Symlink(original.as_ref().to_path_buf()),
+        );
+        self
+    }
+
+    /// 将path及其下的路径视为位于设备dev上，模拟挂载点
+    pub fn set_device(&self, path: impl AsRef<Path>, dev: u64) -> &Self {
+        self.lock().devices.insert(normalize(path.as_ref()), dev);
+        self
+    }
+
+    /// 设置设备dev（见`set_device`）的剩余空间，不会随写入减少
+    pub fn set_available_space(&self, dev: u64, bytes: u64) -> &Self {
+        self.lock().spaces.insert(dev, bytes);
+        self
+    }
+
+    /// 第n次（从1开始计数）执行op时返回错误
+    pub fn fail_nth(&self, op: FsOp, n: usize) -> &Self {
+        self.fail_nth_with(op, n, io::ErrorKind::Other)
+    }
+
+    /// 同`fail_nth`，返回kind类型的错误，如模拟权限不足
+    pub fn fail_nth_with(&self, op: FsOp, n: usize, kind: io::ErrorKind) -> &Self {
+        self.lock().faults.push((op, n, kind));
+        self
+    }
+
+    /// 成功执行的修改操作，按执行顺序
+    pub fn journal(&self) -> Vec<String> {
+        self.lock().journal.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn new_file(state: &mut MemState, data: Vec<u8>) -> MemNode {
+        let id = state.contents.len() as u64 + 1;
+        state.contents.insert(id, data);
+        MemNode::File(id)
+    }
+
+    fn node_metadata(state: &MemState, path: &Path, node: &MemNode) -> FsMetadata {
+        let (file_type, len, mode, nlink) = match node {
+            MemNode::File(id) => (
+                FsFileType::File,
+                state.contents.get(id).map_or(0, |data| data.len() as u64),
+                0o644,
+                state
+                    .nodes
+                    .values()
+                    .filter(|node| *node == &MemNode::File(*id))
+                    .count() as u64,
+            ),
+            MemNode::Dir => (FsFileType::Dir, 0, 0o755, 1),
+            MemNode::Symlink(_) => (FsFileType::Symlink, 0, 0o777, 1),
+        };
+        FsMetadata {
+            file_type,
+            len,
+            modified: state.mtimes.get(path).copied(),
+            mode,
+            hidden: false,
+            nlink: Some(nlink),
+        }
+    }
+
+    fn insert(&self, path: &Path, node: MemNode) {
+        let path = normalize(path);
+        let mut state = self.lock();
+        for parent in path.ancestors().skip(1) {
+            state
+                .nodes
+                .entry(parent.to_path_buf())
+                .or_insert(MemNode::Dir);
+        }
+        state.nodes.insert(path, node);
+    }
+
+    /// 记录一次操作，命中注入的失败时返回错误
+    fn begin(state: &mut MemState, op: FsOp, path: &Path) -> io::Result<()> {
+        let count = state.op_counts.entry(op).or_insert(0);
+        *count += 1;
+        let count = *count;
+        let fault = state
+            .faults
+            .iter()
+            .find(|(fault_op, n, _)| (*fault_op, *n) == (op, count));
+        if let Some((_, _, kind)) = fault {
+            return Err(io::Error::new(
+                *kind,
+                format!("MemFs: 注入的失败 {:?}#{} {}", op, count, path.display()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 已解析的路径所在的设备号：最长的挂载点前缀，见`set_device`
+    fn device_of(state: &MemState, resolved: &Path) -> u64 {
+        state
+            .devices
+            .iter()
+            .filter(|(mount, _)| resolved.starts_with(mount))
+            .max_by_key(|(mount, _)| mount.components().count())
+            .map_or(0, |(_, dev)| *dev)
+    }
+
+    /// 跟随符号链接解析路径，包括路径中间的符号链接
+    fn resolve(state: &MemState, path: &Path) -> io::Result<PathBuf> {
+        let mut resolved = PathBuf::new();
+        let mut hops = 0;
+        for comp in normalize(path).components() {
+            resolved.push(comp);
+            while let Some(MemNode::Symlink(target)) = state.nodes.get(&resolved) {
+                hops += 1;
+                if hops > 40 {
+                    return Err(io::Error::other("MemFs: 符号链接层数过多"));
+                }
+                let parent = resolved.parent().unwrap_or(Path::new("/")).to_path_buf();
+                resolved = normalize(&parent.join(target));
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn check_parent(state: &MemState, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if parent.as_os_str().is_empty() => Ok(()),
+            Some(parent) => match state.nodes.get(&Self::resolve(state, parent)?) {
+                Some(MemNode::Dir) => Ok(()),
+                Some(_) => Err(io::Error::other(format!(
+                    "MemFs: 父路径不是目录 {}",
+                    parent.display()
+                ))),
+                None => Err(not_found(parent)),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// 硬链接与复制：在to添加与from（跟随符号链接）共用内容或内容相同的文件，返回其大小
+    fn add_file_node(&self, op: FsOp, from: &Path, to: &Path) -> io::Result<u64> {
+        let mut state = self.lock();
+        let to = normalize(to);
+        Self::begin(&mut state, op, &to)?;
+        Self::check_parent(&state, &to)?;
+        let id = match state.nodes.get(&Self::resolve(&state, from)?) {
+            Some(MemNode::File(id)) => *id,
+            Some(_) => {
+                return Err(io::Error::other(format!(
+                    "MemFs: 不是文件 {}",
+                    from.display()
+                )))
+            }
+            None => return Err(not_found(from)),
+        };
+        if state.nodes.contains_key(&to) {
+            return Err(already_exists(&to));
+        }
+        let data = state.contents.get(&id).cloned().unwrap_or_default();
+        let len = data.len() as u64;
+        let node = match op {
+            FsOp::HardLink => MemNode::File(id),
+            _ => Self::new_file(&mut state, data),
+        };
+        state.nodes.insert(to.clone(), node);
+        let name = match op {
+            FsOp::HardLink => "hard_link",
+            _ => "copy",
+        };
+        state
+            .journal
+            .push(format!("{} {} -> {}", name, to.display(), from.display()));
+        Ok(len)
+    }
+
+    fn has_children(state: &MemState, path: &Path) -> bool {
+        state
+            .nodes
+            .range(path.to_path_buf()..)
+            .nth(1)
+            .is_some_and(|(p, _)| p.starts_with(path))
+    }
+}
+
+impl Fs for MemFs {
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let state = self.lock();
+        let path = normalize(path);
+        let parent = path
+            .parent()
+            .map(|p| Self::resolve(&state, p))
+            .transpose()?;
+        let key = match (parent, path.file_name()) {
+            (Some(parent), Some(name)) => parent.join(name),
+            _ => path.clone(),
+        };
+        state
+            .nodes
+            .get(&key)
+            .map(|node| Self::node_metadata(&state, &key, node))
+            .ok_or_else(|| not_found(&path))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let state = self.lock();
+        let resolved = Self::resolve(&state, path)?;
+        state
+            .nodes
+            .get(&resolved)
+            .map(|node| Self::node_metadata(&state, &resolved, node))
+            .ok_or_else(|| not_found(path))
+    }
+
+    /// 文件的编号为内容编号，其他路径的编号由解析后的路径得出；设备号见[`MemFs::set_device`]
+    fn file_id(&self, path: &Path) -> io::Result<FileId> {
+        let state = self.lock();
+        let resolved = Self::resolve(&state, path)?;
+        let ino = match state.nodes.get(&resolved) {
+            Some(MemNode::File(id)) => *id,
+            Some(_) => {
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                resolved.hash(&mut hasher);
+                hasher.finish() | 1 << 63
+            }
+            None => return Err(not_found(path)),
+        };
+        let dev = Self::device_of(&state, &resolved);
+        Ok(FileId { dev, ino })
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read>> {
+        let state = self.lock();
+        match state.nodes.get(&Self::resolve(&state, path)?) {
+            Some(MemNode::File(id)) => Ok(Box::new(io::Cursor::new(
+                state.contents.get(id).cloned().unwrap_or_default(),
+            ))),
+            Some(_) => Err(io::Error::other(format!(
+                "MemFs: 不是文件 {}",
+                path.display()
+            ))),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let mut state = self.lock();
+        let path = normalize(path);
+        Self::begin(&mut state, FsOp::Write, &path)?;
+        Self::check_parent(&state, &path)?;
+        let resolved = Self::resolve(&state, &path)?;
+        match state.nodes.get(&resolved) {
+            Some(MemNode::File(id)) => {
+                let id = *id;
+                state.contents.insert(id, data.to_vec());
+            }
+            Some(_) => {
+                return Err(io::Error::other(format!(
+                    "MemFs: 不是文件 {}",
+                    path.display()
+                )))
+            }
+            None => {
+                let node = Self::new_file(&mut state, data.to_vec());
+                state.nodes.insert(resolved, node);
+            }
+        }
+        state.journal.push(format!("write {}", path.display()));
+        Ok(())
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.lock().nodes.get(&normalize(path)) {
+            Some(MemNode::Symlink(target)) => Ok(target.clone()),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("MemFs: 不是符号链接 {}", path.display()),
+            )),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn symlink(&self, original: &Path, link: &Path, is_dir: bool) -> io::Result<()> {
+        let mut state = self.lock();
+        let link = normalize(link);
+        Self::begin(&mut state, FsOp::Symlink, &link)?;
+        Self::check_parent(&state, &link)?;
+        if state.nodes.contains_key(&link) {
+            return Err(already_exists(&link));
+        }
+        state
+            .nodes
+            .insert(link.clone(), MemNode::Symlink(original.to_path_buf()));
+        state.journal.push(format!(
+            "{} {} -> {}",
+            if is_dir {
+                "symlink_dir"
+            } else {
+                "symlink_file"
+            },
+            link.display(),
+            original.display()
+        ));
+        Ok(())
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+        self.add_file_node(FsOp::HardLink, original, link)
+            .map(|_| ())
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        self.add_file_node(FsOp::Copy, from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        let path = normalize(path);
+        Self::begin(&mut state, FsOp::Remove, &path)?;
+        match state.nodes.get(&path) {
+            Some(MemNode::Dir) => Err(io::Error::other(format!(
+                "MemFs: 是目录 {}",
+                path.display()
+            ))),
+            Some(_) => {
+                state.nodes.remove(&path);
+                state.xattrs.remove(&path);
+                state.mtimes.remove(&path);
+                state.journal.push(format!("remove {}", path.display()));
+                Ok(())
+            }
+            None => Err(not_found(&path)),
+        }
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        let path = normalize(path);
+        Self::begin(&mut state, FsOp::Remove, &path)?;
+        match state.nodes.get(&path) {
+            Some(MemNode::Dir) if Self::has_children(&state, &path) => Err(io::Error::other(
+                format!("MemFs: 目录非空 {}", path.display()),
+            )),
+            Some(MemNode::File(_)) => Err(io::Error::other(format!(
+                "MemFs: 不是目录 {}",
+                path.display()
+            ))),
+            Some(_) => {
+                state.nodes.remove(&path);
+                state.xattrs.remove(&path);
+                state.mtimes.remove(&path);
+                state.journal.push(format!("remove {}", path.display()));
+                Ok(())
+            }
+            None => Err(not_found(&path)),
+        }
+    }
+
+    /// 与std::fs::rename一致，from不是目录时覆盖已存在的文件或符号链接to
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        let (from, to) = (normalize(from), normalize(to));
+        Self::begin(&mut state, FsOp::Rename, &from)?;
+        let from_is_dir = match state.nodes.get(&from) {
+            Some(node) => node == &MemNode::Dir,
+            None => return Err(not_found(&from)),
+        };
+        if from == to {
+            return Ok(());
+        }
+        Self::check_parent(&state, &to)?;
+        match state.nodes.get(&to) {
+            None => (),
+            Some(MemNode::Dir) => return Err(already_exists(&to)),
+            Some(_) if from_is_dir => return Err(already_exists(&to)),
+            Some(_) => {
+                state.nodes.remove(&to);
+                state.xattrs.remove(&to);
+                state.mtimes.remove(&to);
+            }
+        }
+        let moved: Vec<_> = state
+            .nodes
+            .range(from.clone()..)
+            .take_while(|(p, _)| p.starts_with(&from))
+            .map(|(p, _)| p.clone())
+            .collect();
+        for path in moved {
+            let node = state.nodes.remove(&path).unwrap();
+            let relative = path.strip_prefix(&from).unwrap();
+            if let Some(xattrs) = state.xattrs.remove(&path) {
+                state.xattrs.insert(to.join(relative), xattrs);
+            }
+            if let Some(mtime) = state.mtimes.remove(&path) {
+                state.mtimes.insert(to.join(relative), mtime);
+            }
+            state.nodes.insert(to.join(relative), node);
+        }
+        state
+            .journal
+            .push(format!("rename {} -> {}", from.display(), to.display()));
+        Ok(())
+    }
+
+    /// 只检查dir是否为目录，探测不留下记录，也不计入注入失败的次数
+    fn probe_symlink(&self, dir: &Path) -> io::Result<()> {
+        let state = self.lock();
+        let dir = Self::resolve(&state, dir)?;
+        match state.nodes.get(&dir) {
+            Some(MemNode::Dir) => Ok(()),
+            Some(_) => Err(io::Error::other(format!(
+                "MemFs: 不是目录 {}",
+                dir.display()
+            ))),
+            None => Err(not_found(&dir)),
+        }
+    }
+
+    fn replace_link(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        let (from, to) = (normalize(from), normalize(to));
+        Self::begin(&mut state, FsOp::Rename, &from)?;
+        if !matches!(state.nodes.get(&to), Some(MemNode::Symlink(_))) {
+            return Err(io::Error::other(format!(
+                "MemFs: 不是符号链接 {}",
+                to.display()
+            )));
+        }
+        let node = state.nodes.remove(&from).ok_or_else(|| not_found(&from))?;
+        state.nodes.insert(to.clone(), node);
+        state.xattrs.remove(&to);
+        if let Some(xattrs) = state.xattrs.remove(&from) {
+            state.xattrs.insert(to.clone(), xattrs);
+        }
+        state
+            .journal
+            .push(format!("replace {} -> {}", from.display(), to.display()));
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        let path = normalize(path);
+        Self::begin(&mut state, FsOp::CreateDir, &path)?;
+        Self::check_parent(&state, &path)?;
+        if state.nodes.contains_key(&path) {
+            return Err(already_exists(&path));
+        }
+        state.nodes.insert(path.clone(), MemNode::Dir);
+        state.journal.push(format!("mkdir {}", path.display()));
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.lock();
+        let path = normalize(path);
+        Self::begin(&mut state, FsOp::CreateDir, &path)?;
+        let resolved = Self::resolve(&state, &path)?;
+        match state.nodes.get(&resolved) {
+            Some(MemNode::Dir) => return Ok(()),
+            Some(_) => return Err(already_exists(&path)),
+            None => (),
+        }
+        for dir in resolved.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            match state.nodes.get(dir) {
+                Some(MemNode::Dir) => (),
+                Some(_) => return Err(already_exists(dir)),
+                None => {
+                    state.nodes.insert(dir.to_path_buf(), MemNode::Dir);
+                }
+            }
+        }
+        state.journal.push(format!("mkdir {}", path.display()));
+        Ok(())
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let state = self.lock();
+        let resolved = Self::resolve(&state, path)?;
+        if !state.nodes.contains_key(&resolved) {
+            return Err(not_found(path));
+        }
+        Ok(resolved)
+    }
+
+    /// 不记录权限，只检查两个路径存在
+    fn copy_permissions(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.symlink_metadata(from)?;
+        self.symlink_metadata(to).map(|_| ())
+    }
+
+    /// 不计入journal
+    fn set_modified(&self, path: &Path, time: SystemTime) -> io::Result<()> {
+        let mut state = self.lock();
+        let resolved = Self::resolve(&state, path)?;
+        if !state.nodes.contains_key(&resolved) {
+            return Err(not_found(path));
+        }
+        state.mtimes.insert(resolved, time);
+        Ok(())
+    }
+
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        let state = self.lock();
+        let resolved = Self::resolve(&state, path)?;
+        if !state.nodes.contains_key(&resolved) {
+            return Err(not_found(path));
+        }
+        let dev = Self::device_of(&state, &resolved);
+        Ok(state.spaces.get(&dev).copied().unwrap_or(u64::MAX))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let state = self.lock();
+        let resolved = Self::resolve(&state, path)?;
+        match state.nodes.get(&resolved) {
+            Some(MemNode::Dir) => Ok(state
+                .nodes
+                .keys()
+                .filter(|p| p.parent() == Some(resolved.as_path()))
+                .map(|p| normalize(path).join(p.file_name().unwrap()))
+                .collect()),
+            Some(_) => Err(io::Error::other(format!(
+                "MemFs: 不是目录 {}",
+                path.display()
+            ))),
+            None => Err(not_found(path)),
+        }
+    }
+
+    /// 扩展属性按路径保存，硬链接之间不共用
+    fn get_xattr(&self, path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        let state = self.lock();
+        let path = normalize(path);
+        if !state.nodes.contains_key(&path) {
+            return Err(not_found(&path));
+        }
+        state
+            .xattrs
+            .get(&path)
+            .and_then(|xattrs| xattrs.get(name))
+            .cloned()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("MemFs: 没有扩展属性 {} {}", name, path.display()),
+                )
+            })
+    }
+
+    /// 不计入journal
+    fn set_xattr(&self, path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let mut state = self.lock();
+        let path = normalize(path);
+        Self::begin(&mut state, FsOp::SetXattr, &path)?;
+        if !state.nodes.contains_key(&path) {
+            return Err(not_found(&path));
+        }
+        state
+            .xattrs
+            .entry(path)
+            .or_default()
+            .insert(name.to_string(), value.to_vec());
+        Ok(())
+    }
+}
+
+/// 去除`.`与`..`，不访问文件系统
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                out.pop();
+            }
+            comp => out.push(comp),
+        }
+    }
+    out
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("MemFs: 不存在 {}", path.display()),
+    )
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("MemFs: 已存在 {}", path.display()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mem_fs_links() {
+        let fs = MemFs::new();
+        fs.add_file("/src/a.txt", 3).add_dir("/dst");
+        fs.symlink(Path::new("../src"), Path::new("/dst/src"), true)
+            .unwrap();
+        fs.add_symlink("/missing", "/dst/broken");
+
+        assert!(fs
+            .symlink_metadata(Path::new("/dst/src"))
+            .unwrap()
+            .is_symlink());
+        assert!(fs.metadata(Path::new("/dst/src")).unwrap().is_dir());
+        assert_eq!(fs.metadata(Path::new("/dst/src/a.txt")).unwrap().len, 3);
+        assert!(!fs.exists(Path::new("/dst/broken")));
+        assert!(fs.symlink_metadata(Path::new("/dst/broken")).is_ok());
+        assert_eq!(
+            fs.read_dir(Path::new("/dst")).unwrap(),
+            vec![PathBuf::from("/dst/broken"), PathBuf::from("/dst/src")]
+        );
+
+        fs.rename(Path::new("/src"), Path::new("/moved")).unwrap();
+        assert!(!fs.exists(Path::new("/dst/src")));
+        assert!(fs.exists(Path::new("/moved/a.txt")));
+        assert!(fs.remove_dir(Path::new("/moved")).is_err());
+        fs.remove_file(Path::new("/dst/src")).unwrap();
+        assert_eq!(fs.journal().len(), 3);
+    }
+
+    #[test]
+    fn test_mem_fs_fault_injection() {
+        let fs = MemFs::new();
+        fs.add_dir("/d").fail_nth(FsOp::Symlink, 2);
+        assert!(fs
+            .symlink(Path::new("/t"), Path::new("/d/1"), false)
+            .is_ok());
+        assert!(fs
+            .symlink(Path::new("/t"), Path::new("/d/2"), false)
+            .is_err());
+        assert!(fs
+            .symlink(Path::new("/t"), Path::new("/d/3"), false)
+            .is_ok());
+        assert_eq!(fs.read_dir(Path::new("/d")).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_walk_on_mem_fs() {
+        let fs = MemFs::new();
+        fs.add_file("/r/b/x", 1)
+            .add_file("/r/a/y", 1)
+            .add_file("/r/c", 1)
+            .add_symlink("/r", "/r/a/loop")
+            .add_dir("/r/mnt/inner")
+            .set_device("/r/mnt", 1);
+        let paths = |walk: Walk| -> Vec<String> {
+            walk.filter_map(Result::ok)
+                .map(|entry| entry.path.display().to_string())
+                .collect()
+        };
+
+        // 先目录后内容，同一目录下按名称排序，不跟随符号链接
+        assert_eq!(
+            paths(walk(&fs, "/r")),
+            [
+                "/r",
+                "/r/a",
+                "/r/a/loop",
+                "/r/a/y",
+                "/r/b",
+                "/r/b/x",
+                "/r/c",
+                "/r/mnt",
+                "/r/mnt/inner"
+            ]
+        );
+        assert_eq!(
+            paths(walk(&fs, "/r").max_depth(1)),
+            ["/r", "/r/a", "/r/b", "/r/c", "/r/mnt"]
+        );
+        // 挂载点本身产出，但不进入
+        assert!(!paths(walk(&fs, "/r").same_file_system(true)).contains(&"/r/mnt/inner".into()));
+
+        // 跟随符号链接时，指向祖先目录的链接不再深入，并返回错误
+        let entries: Vec<_> = walk(&fs, "/r").follow_links(true).collect();
+        assert_eq!(entries.iter().filter(|e| e.is_err()).count(), 1);
+        let link = entries
+            .iter()
+            .flatten()
+            .find(|e| e.path == Path::new("/r/a/loop"))
+            .unwrap();
+        assert!(link.path_is_symlink && link.metadata.is_dir());
+
+        let mut it = walk(&fs, "/r");
+        assert_eq!(it.nth(1).unwrap().unwrap().path, Path::new("/r/a"));
+        it.skip_current_dir();
+        assert_eq!(it.next().unwrap().unwrap().path, Path::new("/r/b"));
+        // 上一项是文件时不跳过其所在目录的剩余内容
+        assert_eq!(it.next().unwrap().unwrap().path, Path::new("/r/b/x"));
+        it.skip_current_dir();
+        assert_eq!(it.next().unwrap().unwrap().path, Path::new("/r/c"));
+        it.skip_current_dir();
+        assert_eq!(it.next().unwrap().unwrap().path, Path::new("/r/mnt"));
+    }
+}